@@ -0,0 +1,30 @@
+//! Drive arbitrary PTY output through `VteHandler` and check that the
+//! terminal never panics and stays structurally valid.
+//!
+//! Run with: `cargo +nightly fuzz run vte` from `moterm-core/`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use moterm_core::harness::Harness;
+
+fuzz_target!(|data: &[u8]| {
+    // First two bytes pick a (small) grid size so edge cases like 1x1 get hit
+    let (cols, rows, bytes) = match data {
+        [c, r, rest @ ..] => (1 + (*c as usize % 40), 1 + (*r as usize % 20), rest),
+        _ => return,
+    };
+    let mut h = Harness::new(cols, rows);
+    // Feed in two halves with a resize in between to cover resize paths
+    let mid = bytes.len() / 2;
+    h.feed(&bytes[..mid]);
+    if let Err(e) = h.check_invariants() {
+        panic!("invariant violated: {e}");
+    }
+    h.term.resize(cols % 37 + 2, rows % 17 + 2);
+    h.feed(&bytes[mid..]);
+    if let Err(e) = h.check_invariants() {
+        panic!("invariant violated after resize: {e}");
+    }
+    let _ = h.take_replies();
+});
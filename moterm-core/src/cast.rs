@@ -0,0 +1,181 @@
+//! asciinema v2 `.cast` recording and loading: a JSON header line
+//! followed by one `[seconds, "o", "data"]` event per chunk of output.
+
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub struct CastWriter<W: Write> {
+    out: W,
+    started: Instant,
+    /// Trailing bytes of an incomplete UTF-8 sequence, held for the next chunk
+    pending: Vec<u8>,
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(mut out: W, cols: usize, rows: usize) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        writeln!(
+            out,
+            r#"{{"version": 2, "width": {cols}, "height": {rows}, "timestamp": {timestamp}, "env": {{"TERM": "xterm-256color"}}}}"#
+        )?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    /// Record PTY output.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Keep an incomplete sequence at the end; replace real garbage
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if valid == 0 {
+            return Ok(());
+        }
+        let chunk: Vec<u8> = self.pending.drain(..valid).collect();
+        let text = String::from_utf8_lossy(&chunk);
+        let t = self.elapsed();
+        writeln!(self.out, "[{t:.6}, \"o\", {}]", json_string(&text))
+    }
+
+    /// Record a terminal resize.
+    pub fn resize(&mut self, cols: usize, rows: usize) -> io::Result<()> {
+        let t = self.elapsed();
+        writeln!(self.out, "[{t:.6}, \"r\", \"{cols}x{rows}\"]")
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Encode `s` as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A loaded recording.
+#[derive(Debug)]
+pub struct Cast {
+    pub width: usize,
+    pub height: usize,
+    pub events: Vec<CastEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds since the start of the recording
+    pub time: f64,
+    /// Event type: 'o' output, 'i' input, 'r' resize, 'm' marker
+    pub kind: char,
+    pub data: String,
+}
+
+/// Parse a `.cast` file. Only the fields moterm needs are read; unknown
+/// header keys and malformed event lines are skipped.
+pub fn parse_cast(text: &str) -> Result<Cast, String> {
+    let mut lines = text.lines();
+    let header = lines.next().ok_or("empty cast file")?;
+    if !header.trim_start().starts_with('{') {
+        return Err("missing cast header".to_string());
+    }
+    let width = header_number(header, "width").ok_or("cast header has no width")?;
+    let height = header_number(header, "height").ok_or("cast header has no height")?;
+    let events = lines.filter_map(parse_event).collect();
+    Ok(Cast {
+        width,
+        height,
+        events,
+    })
+}
+
+fn header_number(header: &str, key: &str) -> Option<usize> {
+    let pat = format!("\"{key}\"");
+    let rest = &header[header.find(&pat)? + pat.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// `[1.234, "o", "text"]`
+fn parse_event(line: &str) -> Option<CastEvent> {
+    let rest = line.trim().strip_prefix('[')?;
+    let (time, rest) = rest.split_once(',')?;
+    let time: f64 = time.trim().parse().ok()?;
+    let (kind, rest) = json_string_prefix(rest.trim_start())?;
+    let rest = rest.trim_start().strip_prefix(',')?;
+    let (data, _) = json_string_prefix(rest.trim_start())?;
+    Some(CastEvent {
+        time,
+        kind: kind.chars().next()?,
+        data,
+    })
+}
+
+/// Decode a JSON string literal at the start of `s`; returns it and the rest.
+fn json_string_prefix(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let body_start = 1;
+    let mut out = String::new();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((out, &s[body_start + i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'u' => {
+                    let mut code = hex4(&mut chars)?;
+                    // Surrogate pair
+                    if (0xd800..0xdc00).contains(&code) {
+                        if chars.next()?.1 != '\\' || chars.next()?.1 != 'u' {
+                            return None;
+                        }
+                        let low = hex4(&mut chars)?;
+                        code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                    }
+                    out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn hex4(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let mut v = 0;
+    for _ in 0..4 {
+        v = v * 16 + chars.next()?.1.to_digit(16)?;
+    }
+    Some(v)
+}
@@ -0,0 +1,188 @@
+//! Serializing grid contents (scrollback + screen) as plain text or HTML.
+
+use std::fmt::Write;
+
+use crate::color::{resolve_color, ColorSpec, Rgb};
+use crate::terminal::{Style, Terminal};
+
+/// Output format for [`export`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Html,
+}
+
+impl Format {
+    /// Picks HTML for `.html`/`.htm` paths, plain text otherwise.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => {
+                Format::Html
+            }
+            _ => Format::Text,
+        }
+    }
+}
+
+/// Formats a row's [`Row::time`](crate::terminal::Row::time) as a line prefix.
+pub type Stamp<'a> = &'a dyn Fn(u64) -> String;
+
+/// Whole scrollback plus screen, with trailing blank lines dropped.
+pub fn export(term: &Terminal, format: Format) -> String {
+    export_with(term, format, None)
+}
+
+/// Like [`export`], prefixing each line that has a timestamp with
+/// `stamp(time)`.
+pub fn export_with(term: &Terminal, format: Format, stamp: Option<Stamp>) -> String {
+    let mut last = term.total_lines();
+    while last > 0 && row_is_blank(term, last - 1) {
+        last -= 1;
+    }
+    match format {
+        Format::Text => text(term, 0, last, stamp),
+        Format::Html => html(term, 0, last, stamp),
+    }
+}
+
+fn row_is_blank(term: &Terminal, row: usize) -> bool {
+    term.line_at_global(row)
+        .is_none_or(|r| r.cells.iter().all(|c| c.ch == ' ' && is_default(c.style)))
+}
+
+/// Rows `first..last` (global indices) as text. Soft-wrapped rows are
+/// joined into one line.
+pub fn rows_text(term: &Terminal, first: usize, last: usize) -> String {
+    text(term, first, last, None)
+}
+
+fn text(term: &Terminal, first: usize, last: usize, stamp: Option<Stamp>) -> String {
+    let mut out = String::new();
+    let mut line_start = true;
+    for (row, wrapped, text) in row_strings(term, first, last) {
+        if line_start {
+            push_stamp(&mut out, term, row, stamp);
+        }
+        line_start = !wrapped;
+        out.push_str(&text);
+        if !wrapped && row + 1 < last {
+            out.push('\n');
+        }
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+fn row_strings(term: &Terminal, first: usize, last: usize) -> Vec<(usize, bool, String)> {
+    (first..last)
+        .filter_map(|r| {
+            let row = term.line_at_global(r)?;
+            let mut line: String = row
+                .cells
+                .iter()
+                .filter(|c| !c.wide_cont)
+                .map(|c| c.ch)
+                .collect();
+            if !row.wrapped {
+                line.truncate(line.trim_end_matches(' ').len());
+            }
+            Some((r, row.wrapped, line))
+        })
+        .collect()
+}
+
+/// Rows `first..last` as a standalone HTML document with colors.
+pub fn rows_html(term: &Terminal, first: usize, last: usize) -> String {
+    html(term, first, last, None)
+}
+
+fn html(term: &Terminal, first: usize, last: usize, stamp: Option<Stamp>) -> String {
+    let mut out = String::new();
+    let mut line_start = true;
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>moterm</title></head>\n\
+         <body style=\"background:{};color:{}\"><pre style=\"font-family:monospace\">",
+        css(term.palette.bg),
+        css(term.palette.fg)
+    );
+    for r in first..last {
+        let Some(row) = term.line_at_global(r) else {
+            continue;
+        };
+        if line_start {
+            let mut prefix = String::new();
+            push_stamp(&mut prefix, term, r, stamp);
+            for ch in prefix.chars() {
+                push_escaped(&mut out, ch);
+            }
+        }
+        line_start = !row.wrapped;
+        // Drop trailing default-styled blanks
+        let mut end = row.cells.len();
+        while end > 0 {
+            let c = &row.cells[end - 1];
+            if c.ch != ' ' || !is_default(c.style) {
+                break;
+            }
+            end -= 1;
+        }
+        let mut span: Option<(Rgb, Rgb)> = None;
+        for cell in row.cells[..end].iter().filter(|c| !c.wide_cont) {
+            let colors = (resolve_color(cell.style.fg, &term.palette), resolve_color(cell.style.bg, &term.palette));
+            if span != Some(colors) {
+                if span.is_some() {
+                    out.push_str("</span>");
+                }
+                if is_default(cell.style) {
+                    span = None;
+                } else {
+                    let _ = write!(
+                        out,
+                        "<span style=\"color:{};background:{}\">",
+                        css(colors.0),
+                        css(colors.1)
+                    );
+                    span = Some(colors);
+                }
+            }
+            push_escaped(&mut out, cell.ch);
+        }
+        if span.is_some() {
+            out.push_str("</span>");
+        }
+        if !row.wrapped {
+            out.push('\n');
+        }
+    }
+    out.push_str("</pre></body></html>\n");
+    out
+}
+
+fn push_stamp(out: &mut String, term: &Terminal, row: usize, stamp: Option<Stamp>) {
+    let (Some(stamp), Some(line)) = (stamp, term.line_at_global(row)) else {
+        return;
+    };
+    if line.time != 0 {
+        out.push_str(&stamp(line.time));
+    }
+}
+
+fn is_default(style: Style) -> bool {
+    style.fg == ColorSpec::DefaultFg && style.bg == ColorSpec::DefaultBg
+}
+
+fn css(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+
+fn push_escaped(out: &mut String, ch: char) {
+    match ch {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        _ => out.push(ch),
+    }
+}
@@ -0,0 +1,476 @@
+//! Inline images: kitty's graphics protocol (APC `G`) and iTerm2's
+//! `OSC 1337 ; File=`, as sent by `kitty +kitten icat`, `imgcat` and `timg`.
+//!
+//! Decoded images live in the terminal's [`ImageStore`]. Placing one puts an
+//! [`ImageSlice`] on each grid row it covers, so the picture scrolls into
+//! scrollback, reflows and is erased along with the rows, like OSC 133 marks.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::notify::base64_decode;
+use crate::terminal::Terminal;
+
+/// Longest image sequence kept (base64 payload); longer ones are dropped.
+const MAX_SEQUENCE: usize = 64 << 20;
+/// Largest decoded image, in RGBA bytes.
+const MAX_IMAGE_BYTES: usize = 64 << 20;
+/// Decoded bytes kept across all images before the oldest are dropped.
+const MAX_STORE_BYTES: usize = 256 << 20;
+/// Most rows or columns one image may cover.
+const MAX_SPAN: usize = 1000;
+
+const ITERM_FILE: &[u8] = b"1337;File=";
+
+/// A decoded image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// RGBA, row by row
+    pub pixels: Vec<u8>,
+}
+
+/// The band of an image drawn on one grid row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageSlice {
+    pub id: u32,
+    /// First column and width of the whole image, in cells
+    pub col: usize,
+    pub cols: usize,
+    /// Which of the image's `rows` this row shows
+    pub row: usize,
+    pub rows: usize,
+}
+
+/// Images by id. Rows refer to them by id; once the store is over
+/// [`MAX_STORE_BYTES`] the oldest go, and their slices draw nothing.
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    images: HashMap<u32, Image>,
+    order: VecDeque<u32>,
+    bytes: usize,
+    next_id: u32,
+}
+
+impl ImageStore {
+    pub fn get(&self, id: u32) -> Option<&Image> {
+        self.images.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    pub(crate) fn insert(&mut self, id: u32, image: Image) {
+        self.remove(id);
+        self.bytes += image.pixels.len();
+        self.images.insert(id, image);
+        self.order.push_back(id);
+        while self.bytes > MAX_STORE_BYTES && self.order.len() > 1 {
+            if let Some(old) = self.order.pop_front() {
+                self.bytes -= self.images.remove(&old).map_or(0, |i| i.pixels.len());
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: u32) {
+        if let Some(image) = self.images.remove(&id) {
+            self.bytes -= image.pixels.len();
+            self.order.retain(|&i| i != id);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self { next_id: self.next_id, ..Self::default() };
+    }
+
+    /// An id for an image the program didn't name, from the top half so it
+    /// stays clear of the small ids kitty clients pick.
+    fn fresh_id(&mut self) -> u32 {
+        self.next_id = self.next_id.wrapping_add(1);
+        self.next_id | 1 << 31
+    }
+}
+
+/// An image sequence picked out of PTY output.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Sequence {
+    /// APC body, starting with `G`
+    Kitty(Vec<u8>),
+    /// OSC body, starting with `1337;File=`
+    ITerm(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Sniff {
+    #[default]
+    Ground,
+    Esc,
+    Apc,
+    Osc,
+    /// In a string that isn't an image, or one too long to keep
+    Skip,
+}
+
+/// Watches PTY output for image sequences alongside the vte parser, which
+/// drops APC strings and cuts OSC strings at 1 KiB.
+#[derive(Debug, Default)]
+pub(crate) struct Sniffer {
+    state: Sniff,
+    buf: Vec<u8>,
+}
+
+impl Sniffer {
+    /// Take one byte; returns a sequence when its terminator arrives.
+    pub(crate) fn advance(&mut self, byte: u8) -> Option<Sequence> {
+        // ESC ends any string (ESC \ is ST) and starts an escape, as in vte
+        if byte == 0x1b {
+            let done = self.finish();
+            self.state = Sniff::Esc;
+            return done;
+        }
+        match (self.state, byte) {
+            (Sniff::Ground, _) => {}
+            (Sniff::Esc, b'_') => self.state = Sniff::Apc,
+            (Sniff::Esc, b']') => self.state = Sniff::Osc,
+            (Sniff::Esc, _) => self.state = Sniff::Ground,
+            (Sniff::Osc, 0x07) => {
+                let done = self.finish();
+                self.state = Sniff::Ground;
+                return done;
+            }
+            // CAN and SUB abort a string; BEL ends an OSC
+            (_, 0x18 | 0x1a) | (Sniff::Skip, 0x07) => {
+                self.buf = Vec::new();
+                self.state = Sniff::Ground;
+            }
+            (Sniff::Skip, _) => {}
+            (Sniff::Apc | Sniff::Osc, _) => {
+                self.buf.push(byte);
+                let wanted = match self.state {
+                    Sniff::Apc => self.buf[0] == b'G',
+                    _ => self.buf.len() > ITERM_FILE.len() || ITERM_FILE.starts_with(&self.buf),
+                };
+                if !wanted || self.buf.len() > MAX_SEQUENCE {
+                    self.buf = Vec::new();
+                    self.state = Sniff::Skip;
+                }
+            }
+        }
+        None
+    }
+
+    fn finish(&mut self) -> Option<Sequence> {
+        let buf = std::mem::take(&mut self.buf);
+        match std::mem::take(&mut self.state) {
+            Sniff::Apc if !buf.is_empty() => Some(Sequence::Kitty(buf)),
+            Sniff::Osc if buf.starts_with(ITERM_FILE) => Some(Sequence::ITerm(buf)),
+            _ => None,
+        }
+    }
+}
+
+/// A kitty graphics command's control keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KittyCommand {
+    /// `a`: transmit (`t`), transmit and show (`T`), put (`p`), delete
+    /// (`d`) or query (`q`)
+    action: u8,
+    /// `i`: the program's image id; 0 if unset, and then nothing is replied
+    id: u32,
+    /// `f`: 24 (RGB), 32 (RGBA) or 100 (PNG)
+    format: u32,
+    /// `t`: only `d` (data in the sequence) is supported
+    medium: u8,
+    /// `o=z`: zlib-compressed data
+    compressed: bool,
+    /// `m=1`: more chunks follow
+    more: bool,
+    /// `q`: 1 suppresses OK replies, 2 errors too
+    quiet: u8,
+    /// `s`, `v`: pixel size of RGB(A) data
+    width: usize,
+    height: usize,
+    /// `c`, `r`: cells to show the image over; 0 sizes it from its pixels
+    cols: usize,
+    rows: usize,
+    /// `d`: what to delete
+    delete: u8,
+    /// `C=1`: leave the cursor where it is
+    stay: bool,
+}
+
+impl Default for KittyCommand {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            id: 0,
+            format: 32,
+            medium: b'd',
+            compressed: false,
+            more: false,
+            quiet: 0,
+            width: 0,
+            height: 0,
+            cols: 0,
+            rows: 0,
+            delete: b'a',
+            stay: false,
+        }
+    }
+}
+
+/// Split an APC body (after `G`) into its keys and payload.
+fn parse_kitty(body: &[u8]) -> (KittyCommand, &[u8]) {
+    let (control, payload) = match body.iter().position(|&b| b == b';') {
+        Some(i) => (&body[..i], &body[i + 1..]),
+        None => (body, &[][..]),
+    };
+    let mut cmd = KittyCommand::default();
+    for kv in control.split(|&b| b == b',') {
+        let [key, b'=', value @ ..] = kv else {
+            continue;
+        };
+        let number = || std::str::from_utf8(value).ok().and_then(|v| v.parse::<u32>().ok()).unwrap_or(0);
+        let letter = value.first().copied().unwrap_or(0);
+        match key {
+            b'a' => cmd.action = letter,
+            b'i' => cmd.id = number(),
+            b'f' => cmd.format = number(),
+            b't' => cmd.medium = letter,
+            b'o' => cmd.compressed = letter == b'z',
+            b'm' => cmd.more = number() == 1,
+            b'q' => cmd.quiet = number() as u8,
+            b's' => cmd.width = number() as usize,
+            b'v' => cmd.height = number() as usize,
+            b'c' => cmd.cols = number() as usize,
+            b'r' => cmd.rows = number() as usize,
+            b'd' => cmd.delete = letter,
+            b'C' => cmd.stay = number() == 1,
+            _ => {}
+        }
+    }
+    (cmd, payload)
+}
+
+/// Apply one kitty graphics command (`apc` is the whole APC body) and
+/// queue the reply, if the program wants one.
+pub(crate) fn kitty(term: &mut Terminal, apc: &[u8]) {
+    let Some(body) = apc.strip_prefix(b"G") else {
+        return;
+    };
+    let (mut cmd, payload) = parse_kitty(body);
+    let mut data = payload.to_vec();
+    // Later chunks only carry `m` (and maybe `q`); the first one has the rest
+    if let Some((first, mut buf)) = term.pending_image.take() {
+        buf.extend_from_slice(&data);
+        (cmd, data) = (KittyCommand { more: cmd.more, ..first }, buf);
+    }
+    if cmd.more {
+        if data.len() <= MAX_SEQUENCE {
+            term.pending_image = Some((cmd, data));
+        }
+        return;
+    }
+    let result = match cmd.action {
+        b'q' => kitty_image(&cmd, &data).map(drop),
+        b't' | b'T' => kitty_image(&cmd, &data).and_then(|image| {
+            let span = cell_span(image.width, image.height, cmd.cols, cmd.rows, term.cell_size);
+            let (cols, rows) = span.ok_or("EINVAL:image too large to place")?;
+            let id = if cmd.id == 0 { term.images.fresh_id() } else { cmd.id };
+            term.images.insert(id, image);
+            if cmd.action == b'T' {
+                term.place_image(id, cols, rows, cmd.stay);
+            }
+            Ok(())
+        }),
+        b'p' => match term.images.get(cmd.id) {
+            Some(image) => match cell_span(image.width, image.height, cmd.cols, cmd.rows, term.cell_size) {
+                Some((cols, rows)) => {
+                    term.place_image(cmd.id, cols, rows, cmd.stay);
+                    Ok(())
+                }
+                None => Err("EINVAL:image too large to place"),
+            },
+            None => Err("ENOENT:no such image"),
+        },
+        b'd' => {
+            // Lowercase removes placements, uppercase frees the images too
+            match cmd.delete {
+                b'a' | b'A' => term.grid.retain_images(|_| false),
+                b'i' | b'I' => term.grid.retain_images(|s| s.id != cmd.id),
+                d => log::debug!("unhandled kitty graphics delete d={}", d as char),
+            }
+            match cmd.delete {
+                b'A' => term.images.clear(),
+                b'I' => term.images.remove(cmd.id),
+                _ => {}
+            }
+            return;
+        }
+        _ => Err("EINVAL:unsupported action"),
+    };
+    let quiet = match result {
+        Ok(()) => cmd.quiet >= 1,
+        Err(_) => cmd.quiet >= 2,
+    };
+    if cmd.id != 0 && !quiet {
+        let msg = result.err().unwrap_or("OK");
+        term.reply_buf.extend_from_slice(format!("\x1b_Gi={};{msg}\x1b\\", cmd.id).as_bytes());
+    }
+}
+
+/// Decode the image a kitty command transmits.
+fn kitty_image(cmd: &KittyCommand, data: &[u8]) -> Result<Image, &'static str> {
+    if cmd.medium != b'd' {
+        return Err("EINVAL:only direct transmission is supported");
+    }
+    let mut bytes = base64_decode(data).ok_or("EINVAL:bad base64 data")?;
+    if cmd.compressed {
+        bytes = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&bytes, MAX_IMAGE_BYTES)
+            .map_err(|_| "EINVAL:bad zlib data")?;
+    }
+    match cmd.format {
+        100 => decode_png(&bytes).ok_or("EBADPNG:cannot decode PNG"),
+        f @ (24 | 32) => {
+            let (w, h, depth) = (cmd.width, cmd.height, f as usize / 8);
+            if w == 0 || h == 0 || w.saturating_mul(h).saturating_mul(4) > MAX_IMAGE_BYTES {
+                return Err("EINVAL:bad image size");
+            }
+            if bytes.len() < w * h * depth {
+                return Err("ENODATA:not enough image data");
+            }
+            let pixels = match depth {
+                4 => bytes[..w * h * 4].to_vec(),
+                _ => bytes.chunks_exact(3).take(w * h).flat_map(|p| [p[0], p[1], p[2], 0xff]).collect(),
+            };
+            Ok(Image { width: w, height: h, pixels })
+        }
+        _ => Err("EINVAL:unsupported format"),
+    }
+}
+
+/// An iTerm2 `width=` / `height=` value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Extent {
+    Auto,
+    Cells(usize),
+    Pixels(usize),
+    Percent(usize),
+}
+
+impl Extent {
+    fn parse(s: &str) -> Self {
+        let num = |n: &str| n.parse().ok();
+        if let Some(n) = s.strip_suffix("px").and_then(num) {
+            Extent::Pixels(n)
+        } else if let Some(n) = s.strip_suffix('%').and_then(num) {
+            Extent::Percent(n)
+        } else {
+            num(s).map_or(Extent::Auto, Extent::Cells)
+        }
+    }
+
+    /// Pixels out of `full` (the terminal's width or height) for cells of
+    /// `cell`, saturating for absurd cell counts.
+    fn pixels(self, cell: usize, full: usize) -> Option<usize> {
+        match self {
+            Extent::Auto => None,
+            Extent::Cells(n) => Some(n.saturating_mul(cell)),
+            Extent::Pixels(n) => Some(n),
+            Extent::Percent(p) => Some(full * p.min(100) / 100),
+        }
+    }
+}
+
+/// Show the file an iTerm2 `OSC 1337 ; File=` sequence carries (`osc` is
+/// the whole OSC body). Only inline PNG images are shown; downloads
+/// (`inline=0`) are ignored.
+pub(crate) fn iterm(term: &mut Terminal, osc: &[u8]) {
+    let body = &osc[ITERM_FILE.len()..];
+    let Some(colon) = body.iter().position(|&b| b == b':') else {
+        return;
+    };
+    let args = String::from_utf8_lossy(&body[..colon]);
+    let (mut width, mut height, mut inline, mut keep_aspect) = (Extent::Auto, Extent::Auto, false, true);
+    for (key, value) in args.split(';').filter_map(|kv| kv.split_once('=')) {
+        match key {
+            "width" => width = Extent::parse(value),
+            "height" => height = Extent::parse(value),
+            "inline" => inline = value == "1",
+            "preserveAspectRatio" => keep_aspect = value != "0",
+            _ => {}
+        }
+    }
+    if !inline {
+        log::debug!("ignoring OSC 1337 file download");
+        return;
+    }
+    let Some(image) = base64_decode(&body[colon + 1..]).and_then(|b| decode_png(&b)) else {
+        log::debug!("OSC 1337 image is not a PNG");
+        return;
+    };
+    let (cw, ch) = (term.cell_size.0.max(1), term.cell_size.1.max(1));
+    let (full_w, full_h) = (term.cols() * cw, term.rows() * ch);
+    let (iw, ih) = (image.width as f64, image.height as f64);
+    let (w, h) = match (width.pixels(cw, full_w), height.pixels(ch, full_h)) {
+        (Some(w), Some(h)) if keep_aspect => {
+            let scale = (w as f64 / iw).min(h as f64 / ih);
+            ((iw * scale) as usize, (ih * scale) as usize)
+        }
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, (ih * w as f64 / iw) as usize),
+        (None, Some(h)) => ((iw * h as f64 / ih) as usize, h),
+        (None, None) => (image.width, image.height),
+    };
+    // Like iTerm2, shrink images wider than the terminal to fit
+    let fit = if w > full_w { h.checked_mul(full_w).map(|hw| (full_w, hw / w)) } else { Some((w, h)) };
+    let Some((cols, rows)) = fit.and_then(|(w, h)| cell_span(w, h, 0, 0, term.cell_size)) else {
+        log::debug!("OSC 1337 image size out of range");
+        return;
+    };
+    let id = term.images.fresh_id();
+    term.images.insert(id, image);
+    term.place_image(id, cols, rows, false);
+}
+
+/// Cells to show a `width`×`height` pixel image over: `cols` and `rows`
+/// when given (non-zero), the missing one keeping the aspect ratio;
+/// otherwise enough cells of `cell` size to hold it. `None` when the
+/// sizes are too large to compute with.
+pub(crate) fn cell_span(width: usize, height: usize, cols: usize, rows: usize, cell: (usize, usize)) -> Option<(usize, usize)> {
+    let (cw, ch) = (cell.0.max(1), cell.1.max(1));
+    let (width, height) = (width.max(1), height.max(1));
+    let scaled = |n: usize, cell: usize, num: usize, den: usize| Some(n.checked_mul(cell)?.checked_mul(num)? / den);
+    let (cols, rows) = match (cols, rows) {
+        (0, 0) => (width.div_ceil(cw), height.div_ceil(ch)),
+        (0, r) => (scaled(r, ch, width, height)?.div_ceil(cw), r),
+        (c, 0) => (c, scaled(c, cw, height, width)?.div_ceil(ch)),
+        (c, r) => (c, r),
+    };
+    Some((cols.clamp(1, MAX_SPAN), rows.clamp(1, MAX_SPAN)))
+}
+
+/// Decode a PNG to 8-bit RGBA.
+pub fn decode_png(bytes: &[u8]) -> Option<Image> {
+    let limits = png::Limits { bytes: MAX_IMAGE_BYTES };
+    let mut decoder = png::Decoder::new_with_limits(bytes, limits);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    buf.truncate(info.buffer_size());
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buf,
+        png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 0xff]).collect(),
+        png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 0xff]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+    let (width, height) = (info.width as usize, info.height as usize);
+    (pixels.len() == width * height * 4).then_some(Image { width, height, pixels })
+}
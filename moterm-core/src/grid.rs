@@ -0,0 +1,417 @@
+//! Scrollback and screen rows in a single ring buffer.
+
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::graphics::ImageSlice;
+use crate::terminal::{Cell, Mark, Pos, Row, Style};
+
+/// Scrollback followed by the screen, stored in one ring buffer so scrolling
+/// moves no rows. Rows are addressed either as screen rows (`grid[r]`) or as
+/// global rows (scrollback first, then screen) via [`Grid::line`].
+///
+/// Screen rows are always `cols` wide. A row that isn't soft-wrapped loses
+/// its trailing blank cells when it moves into scrollback, so read those
+/// with `cells.get(col)`.
+pub struct Grid {
+    lines: VecDeque<Row>,
+    cols: usize,
+    screen_rows: usize,
+    max_scrollback: usize,
+    /// Screen rows written since [`Grid::take_damage`]
+    damaged: Vec<bool>,
+    /// Rows moved or the size changed: everything needs redrawing
+    damaged_all: bool,
+}
+
+/// What changed on the screen since the last frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Damage {
+    Full,
+    /// Screen rows, in order
+    Rows(Vec<usize>),
+}
+
+impl Grid {
+    pub fn new(cols: usize, rows: usize, max_scrollback: usize) -> Self {
+        // The scrollback limit may be huge; let it grow as it fills
+        let mut lines = VecDeque::with_capacity(rows);
+        lines.extend((0..rows).map(|_| Row::new(cols)));
+        Self {
+            lines,
+            cols,
+            screen_rows: rows,
+            max_scrollback,
+            damaged: vec![false; rows],
+            damaged_all: true,
+        }
+    }
+
+    /// Screen rows changed since the last call, and start over.
+    pub fn take_damage(&mut self) -> Damage {
+        let rows = (0..self.screen_rows).filter(|&r| self.damaged.get(r) == Some(&true)).collect();
+        self.damaged.clear();
+        self.damaged.resize(self.screen_rows, false);
+        if std::mem::take(&mut self.damaged_all) {
+            return Damage::Full;
+        }
+        Damage::Rows(rows)
+    }
+
+    fn damage(&mut self, rows: std::ops::Range<usize>) {
+        for r in rows {
+            if let Some(d) = self.damaged.get_mut(r) {
+                *d = true;
+            }
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Scrollback rows beyond `max` are dropped at the next scroll.
+    pub fn set_max_scrollback(&mut self, max: usize) {
+        self.max_scrollback = max;
+    }
+
+    pub fn screen_rows(&self) -> usize {
+        self.screen_rows
+    }
+
+    pub fn scrollback_len(&self) -> usize {
+        self.lines.len() - self.screen_rows
+    }
+
+    /// Scrollback plus screen rows.
+    pub fn total(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Row at global index `row`.
+    pub fn line(&self, row: usize) -> Option<&Row> {
+        self.lines.get(row)
+    }
+
+    /// Screen row `row`, if in range.
+    pub fn get_mut(&mut self, row: usize) -> Option<&mut Row> {
+        if row < self.screen_rows {
+            self.damage(row..row + 1);
+            let base = self.scrollback_len();
+            self.lines.get_mut(base + row)
+        } else {
+            None
+        }
+    }
+
+    pub fn screen(&self) -> impl DoubleEndedIterator<Item = &Row> {
+        self.lines.range(self.scrollback_len()..)
+    }
+
+    pub fn scrollback(&self) -> impl DoubleEndedIterator<Item = &Row> {
+        self.lines.range(..self.scrollback_len())
+    }
+
+    /// Scroll the whole screen up one row. The top row goes to scrollback,
+    /// or is dropped without `keep`. Returns how many rows were evicted from
+    /// the front of a full scrollback.
+    pub fn scroll_up(&mut self, keep: bool) -> usize {
+        if !keep {
+            self.lines.remove(self.scrollback_len());
+        }
+        self.lines.push_back(Row::new(self.cols));
+        if keep && self.max_scrollback > 0 {
+            let top = self.scrollback_len() - 1;
+            compact(&mut self.lines[top]);
+        }
+        self.damaged_all = true;
+        self.trim_scrollback()
+    }
+
+    /// Scroll screen rows `top..bottom` down one row: a blank row appears at
+    /// `top` and the row at `bottom - 1` is dropped.
+    pub fn scroll_down(&mut self, top: usize, bottom: usize) {
+        let bottom = min(bottom, self.screen_rows);
+        if top + 1 >= bottom {
+            return;
+        }
+        let base = self.scrollback_len();
+        self.lines.remove(base + bottom - 1);
+        self.lines.insert(base + top, Row::new(self.cols));
+        self.damage(top..bottom);
+    }
+
+    /// Scroll screen rows `top..bottom` up one row: the row at `top` is
+    /// dropped (not kept in scrollback) and a blank row appears at `bottom - 1`.
+    pub fn scroll_region_up(&mut self, top: usize, bottom: usize) {
+        let bottom = min(bottom, self.screen_rows);
+        if top + 1 >= bottom {
+            return;
+        }
+        let base = self.scrollback_len();
+        self.lines.remove(base + top);
+        self.lines.insert(base + bottom - 1, Row::new(self.cols));
+        self.damage(top..bottom);
+    }
+
+    /// Drop all scrollback; returns how many rows it had.
+    pub fn clear_scrollback(&mut self) -> usize {
+        let n = self.scrollback_len();
+        self.lines.drain(..n);
+        n
+    }
+
+    /// Keep only the first `len` scrollback rows.
+    pub fn truncate_scrollback(&mut self, len: usize) {
+        let end = self.scrollback_len();
+        if len < end {
+            self.lines.drain(len..end);
+        }
+    }
+
+    /// Remove the scrollback row at global index `row`.
+    pub fn remove_scrollback_row(&mut self, row: usize) {
+        if row < self.scrollback_len() {
+            self.lines.remove(row);
+        }
+    }
+
+    /// Keep only the image slices `keep` accepts, on screen and in scrollback.
+    pub fn retain_images(&mut self, mut keep: impl FnMut(&ImageSlice) -> bool) {
+        for row in &mut self.lines {
+            row.images.retain(&mut keep);
+        }
+        self.damaged_all = true;
+    }
+
+    /// Swap the screen rows for `rows` (same count), returning the old ones.
+    pub fn replace_screen(&mut self, rows: Vec<Row>) -> Vec<Row> {
+        let base = self.scrollback_len();
+        let old = self.lines.split_off(base).into();
+        self.lines.extend(rows);
+        self.screen_rows = self.lines.len() - base;
+        self.damaged_all = true;
+        old
+    }
+
+    /// Change to `cols` x `rows`. A width change rewraps every logical line
+    /// (see [`Grid::reflow`]). Then the cursor's row is kept in place relative
+    /// to the bottom: shrinking drops rows below the cursor first, then moves
+    /// rows into scrollback; growing pulls them back.
+    ///
+    /// `cursor` is on the screen (`cursor.row` is a screen row); `top` is a
+    /// global row to carry through the rewrap. Returns how many rows were
+    /// evicted from the front of a full scrollback.
+    pub fn resize(&mut self, cols: usize, rows: usize, cursor: &mut Pos, top: &mut Option<usize>) -> usize {
+        if cols != self.cols {
+            self.reflow(cols, cursor, top);
+        }
+        let cursor_row = &mut cursor.row;
+        if rows < self.screen_rows {
+            let cursor = min(*cursor_row, self.screen_rows - 1);
+            let drop = min(self.screen_rows - rows, self.screen_rows - 1 - cursor);
+            self.lines.truncate(self.lines.len() - drop);
+            *cursor_row = cursor - (self.screen_rows - drop - rows);
+        } else if rows > self.screen_rows {
+            let pull = min(rows - self.screen_rows, self.scrollback_len());
+            *cursor_row += pull;
+            let first = self.scrollback_len() - pull;
+            for row in self.lines.range_mut(first..first + pull) {
+                row.cells.resize(self.cols, Cell::default());
+            }
+            let blank = rows - self.screen_rows - pull;
+            self.lines.extend((0..blank).map(|_| Row::new(self.cols)));
+        }
+        self.screen_rows = rows;
+        self.damaged_all = true;
+        self.trim_scrollback()
+    }
+
+    /// Rewrap every logical line (rows joined by `wrapped`) to `cols`,
+    /// moving the cursor, `top` and OSC 133 marks with the text they're on.
+    /// Image slices stay on the same row of their line.
+    /// Trailing blanks of a line are dropped, and rows below the cursor that
+    /// become blank are removed so the cursor keeps its distance from the
+    /// bottom of the screen.
+    fn reflow(&mut self, cols: usize, cursor: &mut Pos, top: &mut Option<usize>) {
+        let below = self.screen_rows - 1 - min(cursor.row, self.screen_rows - 1);
+        let points = [(self.scrollback_len() + cursor.row, cursor.col), (top.unwrap_or(usize::MAX), 0)];
+        let mut moved = points;
+        let old: Vec<Row> = self.lines.drain(..).collect();
+        let mut start = 0;
+        while start < old.len() {
+            let mut end = start;
+            while end + 1 < old.len() && old[end].wrapped {
+                end += 1;
+            }
+            let base = self.lines.len();
+            for (p, (row, col)) in reflow_line(&old[start..=end], start, cols, &points, &mut self.lines) {
+                moved[p] = (base + row, col);
+            }
+            start = end + 1;
+        }
+        self.cols = cols;
+
+        let (cursor_row, cursor_col) = moved[0];
+        while self.lines.len() - 1 > cursor_row + below && self.lines.back().is_some_and(is_blank_row) {
+            self.lines.pop_back();
+        }
+        self.lines.truncate(cursor_row + self.screen_rows);
+        while self.lines.len() < self.screen_rows {
+            self.lines.push_back(Row::new(cols));
+        }
+        cursor.row = cursor_row - self.scrollback_len();
+        cursor.col = cursor_col;
+        if top.is_some() {
+            *top = Some(moved[1].0);
+        }
+    }
+
+    fn trim_scrollback(&mut self) -> usize {
+        let excess = self.scrollback_len().saturating_sub(self.max_scrollback);
+        self.lines.drain(..excess);
+        excess
+    }
+}
+
+/// Rewrap one logical line (`line`, starting at global row `first`) into
+/// `out`. `points` are global (row, col) positions; for each one on this
+/// line, returns its index and new (row within the line, col).
+fn reflow_line(
+    line: &[Row],
+    first: usize,
+    cols: usize,
+    points: &[(usize, usize)],
+    out: &mut VecDeque<Row>,
+) -> Vec<(usize, (usize, usize))> {
+    // Join the rows, noting which cell each point and mark is on
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut on_line = Vec::new();
+    let mut marks = Vec::new();
+    let mut images = Vec::new();
+    for (i, row) in line.iter().enumerate() {
+        let len = if row.wrapped && ends_with_wrap_pad(row) { row.cells.len() - 1 } else { row.cells.len() };
+        let at = |col: usize| cells.len() + min(col, len);
+        for (p, &(row_idx, col)) in points.iter().enumerate() {
+            if row_idx == first + i {
+                on_line.push((p, at(col)));
+            }
+        }
+        marks.extend(row.marks.iter().map(|m| (at(m.col), m.kind)));
+        images.extend(row.images.iter().map(|&s| (i, s)));
+        cells.extend_from_slice(&row.cells[..len]);
+    }
+    let keep = on_line.iter().map(|&(_, i)| i).chain(marks.iter().map(|&(i, _)| i)).max().unwrap_or(0);
+    while cells.len() > keep && cells.last().is_some_and(is_blank) {
+        cells.pop();
+    }
+
+    // Cut into rows of `cols`; a wide char that doesn't fit moves down
+    let mut rows: Vec<Vec<Cell>> = vec![Vec::with_capacity(cols)];
+    let mut pos = Vec::with_capacity(cells.len() + 1);
+    let mut i = 0;
+    while i < cells.len() {
+        let wide = !cells[i].wide_cont && cells.get(i + 1).is_some_and(|c| c.wide_cont);
+        let width = if wide && cols >= 2 { 2 } else { 1 };
+        if rows.last().is_some_and(|r| r.len() + width > cols) {
+            let pad = Cell { wide_cont: true, ..Cell::default() };
+            if let Some(r) = rows.last_mut() {
+                r.resize(cols, pad);
+            }
+            rows.push(Vec::with_capacity(cols));
+        }
+        let r = rows.len() - 1;
+        for _ in 0..if wide { 2 } else { 1 } {
+            pos.push((r, min(rows[r].len(), cols)));
+            if rows[r].len() < cols {
+                rows[r].push(cells[i]);
+            }
+            i += 1;
+        }
+    }
+    let last = rows.len() - 1;
+    pos.push((last, rows[last].len()));
+
+    let time = line[0].time;
+    for (r, mut row_cells) in rows.into_iter().enumerate() {
+        row_cells.resize(cols, Cell::default());
+        out.push_back(Row {
+            cells: row_cells,
+            wrapped: r < last,
+            time,
+            marks: Vec::new(),
+            images: Vec::new(),
+        });
+    }
+    let line_start = out.len() - (last + 1);
+    for (idx, kind) in marks {
+        let (r, col) = pos[idx];
+        out[line_start + r].marks.push(Mark { kind, col });
+    }
+    for (r, slice) in images {
+        let col = min(slice.col, cols - 1);
+        out[line_start + min(r, last)].images.push(ImageSlice { col, ..slice });
+    }
+    on_line.into_iter().map(|(p, idx)| (p, pos[idx])).collect()
+}
+
+/// Whether a wrapped row ends in the padding left when a wide char didn't
+/// fit in its last column (a `wide_cont` cell not after a wide char).
+fn ends_with_wrap_pad(row: &Row) -> bool {
+    let n = row.cells.len();
+    n >= 2
+        && row.cells[n - 1].wide_cont
+        && (row.cells[n - 2].wide_cont || UnicodeWidthChar::width(row.cells[n - 2].ch) != Some(2))
+}
+
+/// Drop the trailing blanks of a row going into scrollback; reading past the
+/// end gives `Cell::default()`, which is what they were.
+fn compact(row: &mut Row) {
+    if row.wrapped {
+        return;
+    }
+    let len = row.cells.iter().rposition(|c| c.ch != ' ' || c.wide_cont || c.style != Style::default()).map_or(0, |i| i + 1);
+    row.cells.truncate(len);
+    row.cells.shrink_to_fit();
+}
+
+/// An unwritten-looking cell: a space with no background or attributes.
+fn is_blank(cell: &Cell) -> bool {
+    cell.ch == ' ' && !cell.wide_cont && cell.style.bg == crate::color::ColorSpec::DefaultBg && !cell.style.inverse
+}
+
+fn is_blank_row(row: &Row) -> bool {
+    !row.wrapped && row.marks.is_empty() && row.images.is_empty() && row.cells.iter().all(is_blank)
+}
+
+/// Truncate or pad `row` to `cols` cells. A wrap no longer lines up at a new
+/// width, so it is dropped.
+pub fn resize_row(row: &mut Row, cols: usize) {
+    row.cells.resize(cols, Cell::default());
+    row.wrapped = false;
+    for m in &mut row.marks {
+        m.col = min(m.col, cols);
+    }
+}
+
+/// Screen rows.
+impl Index<usize> for Grid {
+    type Output = Row;
+
+    fn index(&self, row: usize) -> &Row {
+        assert!(row < self.screen_rows, "screen row {row} out of range");
+        &self.lines[self.scrollback_len() + row]
+    }
+}
+
+impl IndexMut<usize> for Grid {
+    fn index_mut(&mut self, row: usize) -> &mut Row {
+        assert!(row < self.screen_rows, "screen row {row} out of range");
+        self.damage(row..row + 1);
+        let base = self.scrollback_len();
+        &mut self.lines[base + row]
+    }
+}
@@ -0,0 +1,168 @@
+//! Headless test support: feed raw bytes through the parser into a
+//! [`Terminal`] and dump the grid as plain-text snapshots.
+//!
+//! ```
+//! use moterm_core::harness::Harness;
+//!
+//! let mut h = Harness::new(10, 3);
+//! h.feed(b"hi\r\n\x1b[31mred");
+//! assert_eq!(h.screen(), "hi\nred\n\n");
+//! assert_eq!(h.attrs(), "1: 0..3 fg=1\n");
+//! ```
+
+use std::fmt::Write;
+
+use crate::color::ColorSpec;
+use crate::terminal::{Row, Style, Terminal};
+use crate::vte_handler::VteHandler;
+
+pub struct Harness {
+    pub term: Terminal,
+    parser: vte::Parser,
+}
+
+impl Harness {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            term: Terminal::new(cols, rows),
+            parser: vte::Parser::new(),
+        }
+    }
+
+    /// Parse `bytes` as if they came from the PTY.
+    pub fn feed(&mut self, bytes: &[u8]) -> &mut Self {
+        let mut performer = VteHandler::new(&mut self.term);
+        for &b in bytes {
+            performer.advance(&mut self.parser, b);
+        }
+        self
+    }
+
+    pub fn feed_str(&mut self, s: &str) -> &mut Self {
+        self.feed(s.as_bytes())
+    }
+
+    /// Drain whatever the terminal wants to send back to the PTY.
+    pub fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.term.reply_buf)
+    }
+
+    /// (row, col) of the cursor on screen.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.term.cursor_row, self.term.cursor_col)
+    }
+
+    /// Structural invariants that must hold after any input: grid shape
+    /// matches (cols, rows), scrollback rows are no wider than the screen,
+    /// cursor and scroll region are in bounds.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let t = &self.term;
+        let (cols, rows) = (t.cols(), t.rows());
+        if t.grid.screen_rows() != rows {
+            return Err(format!("screen has {} rows, expected {rows}", t.grid.screen_rows()));
+        }
+        for (i, row) in t.grid.screen().enumerate() {
+            if row.cells.len() != cols {
+                return Err(format!("screen row {i} has {} cells, expected {cols}", row.cells.len()));
+            }
+        }
+        // Scrollback rows drop their trailing blanks
+        for (i, row) in t.grid.scrollback().enumerate() {
+            if row.cells.len() > cols {
+                return Err(format!("scrollback row {i} has {} cells, more than {cols}", row.cells.len()));
+            }
+        }
+        if t.cursor_row >= rows {
+            return Err(format!("cursor row {} out of {rows}", t.cursor_row));
+        }
+        // col == cols is the pending-wrap position
+        if t.cursor_col > cols {
+            return Err(format!("cursor col {} out of {cols}", t.cursor_col));
+        }
+        if t.scroll_top >= t.scroll_bottom || t.scroll_bottom > rows {
+            return Err(format!("scroll region {}..{} out of {rows}", t.scroll_top, t.scroll_bottom));
+        }
+        if t.view_scroll > t.grid.scrollback_len() {
+            return Err(format!("view_scroll {} beyond scrollback {}", t.view_scroll, t.grid.scrollback_len()));
+        }
+        Ok(())
+    }
+
+    /// Screen rows, trailing spaces trimmed, one per line.
+    pub fn screen(&self) -> String {
+        rows_text(self.term.grid.screen())
+    }
+
+    /// Scrollback rows, oldest first.
+    pub fn scrollback(&self) -> String {
+        rows_text(self.term.grid.scrollback())
+    }
+
+    /// Non-default styles on screen as runs: `row: start..end fg=.. bg=..`.
+    pub fn attrs(&self) -> String {
+        let mut out = String::new();
+        for (r, row) in self.term.grid.screen().enumerate() {
+            let mut c = 0;
+            while c < row.cells.len() {
+                let style = row.cells[c].style;
+                let mut end = c + 1;
+                while end < row.cells.len() && row.cells[end].style == style {
+                    end += 1;
+                }
+                let desc = style_desc(style);
+                if !desc.is_empty() {
+                    let _ = writeln!(out, "{r}: {c}..{end}{desc}");
+                }
+                c = end;
+            }
+        }
+        out
+    }
+}
+
+fn rows_text<'a>(rows: impl Iterator<Item = &'a Row>) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let line: String = row
+            .cells
+            .iter()
+            .filter(|c| !c.wide_cont)
+            .map(|c| c.ch)
+            .collect();
+        out.push_str(line.trim_end_matches(' '));
+        out.push('\n');
+    }
+    out
+}
+
+fn color_desc(spec: ColorSpec) -> Option<String> {
+    match spec {
+        ColorSpec::DefaultFg | ColorSpec::DefaultBg => None,
+        ColorSpec::Indexed(i) => Some(i.to_string()),
+        ColorSpec::Rgb(r, g, b) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+    }
+}
+
+fn style_desc(style: Style) -> String {
+    let mut out = String::new();
+    if let Some(fg) = color_desc(style.fg) {
+        let _ = write!(out, " fg={fg}");
+    }
+    if let Some(bg) = color_desc(style.bg) {
+        let _ = write!(out, " bg={bg}");
+    }
+    let flags = [
+        (style.bold, "bold"),
+        (style.dim, "dim"),
+        (style.italic, "italic"),
+        (style.underline, "underline"),
+        (style.blink, "blink"),
+        (style.inverse, "inverse"),
+        (style.hidden, "hidden"),
+        (style.strikethrough, "strike"),
+    ];
+    for (_, name) in flags.iter().filter(|(on, _)| *on) {
+        let _ = write!(out, " {name}");
+    }
+    out
+}
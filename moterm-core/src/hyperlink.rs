@@ -0,0 +1,58 @@
+//! Explicit hyperlinks (OSC 8): `ESC ] 8 ; params ; URI ST` turns the text
+//! that follows into a link to `URI`, until an OSC 8 with an empty URI.
+
+use std::collections::VecDeque;
+
+/// Links kept at once; the oldest are dropped first
+pub const MAX_LINKS: usize = 8192;
+
+/// URIs that cells point at through [`Cell::link`](crate::terminal::Cell::link).
+///
+/// Ids start at 1 (0 means no link) and are never reused: once a link is
+/// dropped, cells still naming it just lose their link.
+#[derive(Debug, Default)]
+pub struct Hyperlinks {
+    /// `(id parameter, URI)`, oldest first
+    links: VecDeque<(String, String)>,
+    /// Links dropped so far; `links[0]` has id `dropped + 1`
+    dropped: u32,
+}
+
+impl Hyperlinks {
+    /// Id for a link opened with `params` (`key=value` pairs split by `:`)
+    /// to `uri`. Links sharing an `id=` and URI get the same id, so a link
+    /// printed in pieces highlights as one; without `id=` only a repeat of
+    /// the latest link is merged.
+    pub fn open(&mut self, params: &str, uri: &str) -> u32 {
+        let key = params
+            .split(':')
+            .find_map(|kv| kv.strip_prefix("id="))
+            .unwrap_or_default();
+        let existing = if key.is_empty() {
+            self.links.back().filter(|(k, u)| k.is_empty() && u == uri).map(|_| self.links.len() - 1)
+        } else {
+            self.links.iter().rposition(|(k, u)| k == key && u == uri)
+        };
+        if let Some(i) = existing {
+            return self.dropped + i as u32 + 1;
+        }
+        if self.links.len() == MAX_LINKS {
+            self.links.pop_front();
+            self.dropped += 1;
+        }
+        self.links.push_back((key.into(), uri.into()));
+        self.dropped + self.links.len() as u32
+    }
+
+    /// URI of link `id`, if it's still kept
+    pub fn get(&self, id: u32) -> Option<&str> {
+        let i = id.checked_sub(self.dropped + 1)?;
+        self.links.get(i as usize).map(|(_, uri)| uri.as_str())
+    }
+
+    /// Drop every link; ids handed out so far stop resolving
+    pub fn clear(&mut self) {
+        self.dropped += self.links.len() as u32;
+        self.links.clear();
+    }
+}
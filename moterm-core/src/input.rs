@@ -0,0 +1,114 @@
+//! Keyboard input to the bytes a terminal application expects.
+
+/// A key that doesn't produce text by itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Enter,
+    Backspace,
+    Tab,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Delete,
+    Insert,
+    PageUp,
+    PageDown,
+    /// F1–F12
+    F(u8),
+    /// A character key, for Ctrl combinations (Ctrl+C, Ctrl+[ …)
+    Char(char),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+/// Bytes for `key`, or `None` if it sends nothing (e.g. a plain letter,
+/// which arrives as text instead). Alt prefixes ESC.
+pub fn key_bytes(key: Key, mods: Modifiers) -> Option<Vec<u8>> {
+    let bytes = match key {
+        Key::Enter => vec![b'\r'],
+        Key::Backspace => vec![0x7f],
+        Key::Tab if mods.shift => b"\x1b[Z".to_vec(),
+        Key::Tab => vec![b'\t'],
+        Key::Escape => vec![0x1b],
+        Key::Up => csi_mod('A', mods),
+        Key::Down => csi_mod('B', mods),
+        Key::Right => csi_mod('C', mods),
+        Key::Left => csi_mod('D', mods),
+        Key::Home => b"\x1b[H".to_vec(),
+        Key::End => b"\x1b[F".to_vec(),
+        Key::Delete => b"\x1b[3~".to_vec(),
+        Key::Insert => b"\x1b[2~".to_vec(),
+        Key::PageUp => b"\x1b[5~".to_vec(),
+        Key::PageDown => b"\x1b[6~".to_vec(),
+        Key::F(n) => function_key(n)?.to_vec(),
+        Key::Char(c) if mods.ctrl => vec![ctrl_char(c)?],
+        Key::Char(_) => return None,
+    };
+    if mods.alt && bytes.first() != Some(&0x1b) {
+        return Some([&[0x1b], &bytes[..]].concat());
+    }
+    Some(bytes)
+}
+
+/// UTF-8 for typed text, Alt-prefixed with ESC. Control characters and
+/// Ctrl combinations go through [`key_bytes`] instead.
+pub fn text_bytes(ch: char, mods: Modifiers) -> Option<Vec<u8>> {
+    if mods.ctrl || ch.is_control() {
+        return None;
+    }
+    let mut out = Vec::new();
+    if mods.alt {
+        out.push(0x1b);
+    }
+    let mut buf = [0u8; 4];
+    out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+    Some(out)
+}
+
+fn function_key(n: u8) -> Option<&'static [u8]> {
+    Some(match n {
+        1 => b"\x1bOP",
+        2 => b"\x1bOQ",
+        3 => b"\x1bOR",
+        4 => b"\x1bOS",
+        5 => b"\x1b[15~",
+        6 => b"\x1b[17~",
+        7 => b"\x1b[18~",
+        8 => b"\x1b[19~",
+        9 => b"\x1b[20~",
+        10 => b"\x1b[21~",
+        11 => b"\x1b[23~",
+        12 => b"\x1b[24~",
+        _ => return None,
+    })
+}
+
+fn ctrl_char(c: char) -> Option<u8> {
+    match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => Some(c as u8 - b'a' + 1),
+        ' ' => Some(0),
+        '[' => Some(0x1b),
+        '\\' => Some(0x1c),
+        ']' => Some(0x1d),
+        '-' => Some(0x1f),
+        _ => None,
+    }
+}
+
+fn csi_mod(final_char: char, mods: Modifiers) -> Vec<u8> {
+    let code = 1 + mods.shift as u8 + 2 * mods.alt as u8 + 4 * mods.ctrl as u8;
+    if code == 1 {
+        format!("\x1b[{final_char}").into_bytes()
+    } else {
+        format!("\x1b[1;{code}{final_char}").into_bytes()
+    }
+}
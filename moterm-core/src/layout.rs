@@ -0,0 +1,143 @@
+//! Split pane layout: a binary tree of panes. Each split halves its area,
+//! leaving a one-pixel divider between the halves.
+
+/// Identifies a pane (and its PTY); unique for the life of the process.
+pub type PaneId = u64;
+
+/// A pixel rectangle in the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, w: usize, h: usize) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.w).contains(&x) && (self.y..self.y + self.h).contains(&y)
+    }
+}
+
+/// Where to move focus from the current pane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Pane(PaneId),
+    /// `vertical`: side by side with a vertical divider; otherwise stacked
+    Split { vertical: bool, first: Box<Layout>, second: Box<Layout> },
+}
+
+impl Layout {
+    /// Split `target` in two, putting `new` right of or below it.
+    pub fn split(&mut self, target: PaneId, new: PaneId, vertical: bool) -> bool {
+        match self {
+            Layout::Pane(id) if *id == target => {
+                *self = Layout::Split {
+                    vertical,
+                    first: Box::new(Layout::Pane(target)),
+                    second: Box::new(Layout::Pane(new)),
+                };
+                true
+            }
+            Layout::Pane(_) => false,
+            Layout::Split { first, second, .. } => {
+                first.split(target, new, vertical) || second.split(target, new, vertical)
+            }
+        }
+    }
+
+    /// Remove a pane; its sibling takes over the space. Returns the pane that
+    /// should get focus if `id` had it, or `None` if `id` isn't here or is
+    /// the only pane.
+    pub fn remove(&mut self, id: PaneId) -> Option<PaneId> {
+        let Layout::Split { first, second, .. } = self else {
+            return None;
+        };
+        let sibling = if **first == Layout::Pane(id) {
+            std::mem::replace(&mut **second, Layout::Pane(0))
+        } else if **second == Layout::Pane(id) {
+            std::mem::replace(&mut **first, Layout::Pane(0))
+        } else {
+            return first.remove(id).or_else(|| second.remove(id));
+        };
+        let next = sibling.panes()[0];
+        *self = sibling;
+        Some(next)
+    }
+
+    /// Pane ids from left to right, top to bottom.
+    pub fn panes(&self) -> Vec<PaneId> {
+        match self {
+            Layout::Pane(id) => vec![*id],
+            Layout::Split { first, second, .. } => {
+                let mut ids = first.panes();
+                ids.extend(second.panes());
+                ids
+            }
+        }
+    }
+
+    /// Each pane's area within `area`, and the dividers between them.
+    pub fn rects(&self, area: Rect) -> (Vec<(PaneId, Rect)>, Vec<Rect>) {
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        self.place(area, &mut panes, &mut dividers);
+        (panes, dividers)
+    }
+
+    fn place(&self, area: Rect, panes: &mut Vec<(PaneId, Rect)>, dividers: &mut Vec<Rect>) {
+        match self {
+            Layout::Pane(id) => panes.push((*id, area)),
+            Layout::Split { vertical: true, first, second } => {
+                let w = area.w.saturating_sub(1) / 2;
+                first.place(Rect { w, ..area }, panes, dividers);
+                dividers.push(Rect { x: area.x + w, w: 1, ..area });
+                let rest = area.w.saturating_sub(w + 1);
+                second.place(Rect { x: area.x + w + 1, w: rest, ..area }, panes, dividers);
+            }
+            Layout::Split { vertical: false, first, second } => {
+                let h = area.h.saturating_sub(1) / 2;
+                first.place(Rect { h, ..area }, panes, dividers);
+                dividers.push(Rect { y: area.y + h, h: 1, ..area });
+                let rest = area.h.saturating_sub(h + 1);
+                second.place(Rect { y: area.y + h + 1, h: rest, ..area }, panes, dividers);
+            }
+        }
+    }
+
+    /// The pane next to `from` in `dir`: the nearest one on that side that
+    /// shares some of its edge, preferring the one lined up with its top or
+    /// left.
+    pub fn neighbor(&self, area: Rect, from: PaneId, dir: Direction) -> Option<PaneId> {
+        let (panes, _) = self.rects(area);
+        let (_, f) = *panes.iter().find(|(id, _)| *id == from)?;
+        let overlap = |a0: usize, a1: usize, b0: usize, b1: usize| a1.min(b1).saturating_sub(a0.max(b0));
+        panes
+            .iter()
+            .filter(|(id, _)| *id != from)
+            .filter_map(|&(id, r)| {
+                let (gap, shared, offset) = match dir {
+                    Direction::Left if r.x + r.w <= f.x => (f.x - (r.x + r.w), overlap(f.y, f.y + f.h, r.y, r.y + r.h), r.y.abs_diff(f.y)),
+                    Direction::Right if r.x >= f.x + f.w => (r.x - (f.x + f.w), overlap(f.y, f.y + f.h, r.y, r.y + r.h), r.y.abs_diff(f.y)),
+                    Direction::Up if r.y + r.h <= f.y => (f.y - (r.y + r.h), overlap(f.x, f.x + f.w, r.x, r.x + r.w), r.x.abs_diff(f.x)),
+                    Direction::Down if r.y >= f.y + f.h => (r.y - (f.y + f.h), overlap(f.x, f.x + f.w, r.x, r.x + r.w), r.x.abs_diff(f.x)),
+                    _ => return None,
+                };
+                (shared > 0).then_some((gap, offset, id))
+            })
+            .min()
+            .map(|(_, _, id)| id)
+    }
+}
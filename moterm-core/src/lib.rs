@@ -0,0 +1,78 @@
+//! Frontend-independent core of moterm.
+//!
+//! - [`terminal`] — grid, cursor, scrollback and selection state
+//! - [`grid`] — ring buffer holding scrollback and screen rows
+//! - [`vte_handler`] — applies parsed escape sequences to a [`Terminal`]
+//! - [`color`] — color specs and palette resolution
+//! - [`search`] — text search over scrollback + screen
+//! - [`mouse`] — mouse report encoding (X10 / SGR)
+//! - [`layout`] — split pane layout tree
+//! - [`notify`] — desktop notifications (OSC 9 / OSC 99)
+//! - [`graphics`] — inline images (kitty graphics protocol, iTerm2 OSC 1337)
+//! - [`url`] — URL, path and email detection and opening
+//! - [`hyperlink`] — explicit hyperlinks (OSC 8)
+//! - [`cast`] — asciinema v2 session recording and playback
+//! - [`export`] — scrollback export as plain text or HTML
+//! - [`terminfo`] — device attributes and XTGETTCAP answers for the advertised `TERM`
+//! - [`input`] — key and text input encoding
+//! - [`view`] — [`MotermView`], an embeddable terminal pane over a host-supplied PTY
+//! - [`harness`] — headless grid snapshots for tests
+//!
+//! Feeding PTY output into a terminal:
+//!
+//! ```
+//! use moterm_core::{Terminal, VteHandler};
+//!
+//! let mut term = Terminal::new(80, 24);
+//! let mut parser = vte::Parser::new();
+//! let mut performer = VteHandler::new(&mut term);
+//! for b in b"hello\r\n\x1b[31mworld" {
+//!     parser.advance(&mut performer, *b);
+//! }
+//! assert_eq!(term.cursor_row, 1);
+//! ```
+//!
+//! Embedding a terminal pane: implement [`Host`] over your PTY and drive a
+//! [`MotermView`]:
+//!
+//! ```
+//! use moterm_core::input::{Key, Modifiers};
+//! use moterm_core::{Host, MotermView};
+//!
+//! struct Pty(Vec<u8>);
+//! impl Host for Pty {
+//!     fn write(&mut self, bytes: &[u8]) {
+//!         self.0.extend_from_slice(bytes);
+//!     }
+//!     fn resize(&mut self, _cols: u16, _rows: u16) {}
+//! }
+//!
+//! let mut pty = Pty(Vec::new());
+//! let mut view = MotermView::new(80, 24);
+//! view.feed(b"$ ", &mut pty);
+//! view.text("ls", Modifiers::default(), &mut pty);
+//! view.key(Key::Enter, Modifiers::default(), &mut pty);
+//! assert_eq!(pty.0, b"ls\r");
+//! ```
+
+pub mod cast;
+pub mod color;
+pub mod export;
+pub mod graphics;
+pub mod grid;
+pub mod harness;
+pub mod hyperlink;
+pub mod input;
+pub mod layout;
+pub mod mouse;
+pub mod notify;
+pub mod search;
+pub mod terminal;
+pub mod terminfo;
+pub mod url;
+pub mod view;
+pub mod vte_handler;
+
+pub use terminal::Terminal;
+pub use view::{Host, MotermView};
+pub use vte_handler::VteHandler;
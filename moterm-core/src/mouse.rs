@@ -0,0 +1,63 @@
+//! Encode mouse events as terminal escape sequences.
+
+/// Button values for mouse protocol
+pub const BUTTON_LEFT: u8 = 0;
+pub const BUTTON_MIDDLE: u8 = 1;
+pub const BUTTON_RIGHT: u8 = 2;
+pub const BUTTON_RELEASE: u8 = 3;
+pub const BUTTON_SCROLL_UP: u8 = 64;
+pub const BUTTON_SCROLL_DOWN: u8 = 65;
+pub const BUTTON_SCROLL_LEFT: u8 = 66;
+pub const BUTTON_SCROLL_RIGHT: u8 = 67;
+
+/// Modifier bits added to the button value
+pub const MOD_SHIFT: u8 = 4;
+pub const MOD_META: u8 = 8;
+pub const MOD_CTRL: u8 = 16;
+/// Added to the button value for motion events (modes 1002/1003)
+pub const MOTION: u8 = 32;
+
+/// Modifier bits to add to a button value.
+pub fn modifier_bits(shift: bool, alt: bool, ctrl: bool) -> u8 {
+    let mut bits = 0;
+    if shift {
+        bits |= MOD_SHIFT;
+    }
+    if alt {
+        bits |= MOD_META;
+    }
+    if ctrl {
+        bits |= MOD_CTRL;
+    }
+    bits
+}
+
+/// Encode a mouse event in SGR or normal format. Normal format can't say
+/// which button was released, so releases report button 3 with the
+/// modifier bits kept.
+pub fn encode(button: u8, col: usize, row: usize, pressed: bool, sgr: bool) -> Vec<u8> {
+    if sgr {
+        encode_sgr(button, col, row, pressed)
+    } else if pressed {
+        encode_normal(button, col, row)
+    } else {
+        let mods = button & (MOD_SHIFT | MOD_META | MOD_CTRL);
+        encode_normal(BUTTON_RELEASE | mods, col, row)
+    }
+}
+
+/// Encode a mouse event in SGR format: CSI < Pb ; Px ; Py M/m
+pub fn encode_sgr(button: u8, col: usize, row: usize, pressed: bool) -> Vec<u8> {
+    let c = col + 1; // 1-based
+    let r = row + 1;
+    let suffix = if pressed { 'M' } else { 'm' };
+    format!("\x1b[<{};{};{}{}", button, c, r, suffix).into_bytes()
+}
+
+/// Encode a mouse event in normal (X10) format: CSI M Cb Cx Cy
+pub fn encode_normal(button: u8, col: usize, row: usize) -> Vec<u8> {
+    let cb = 32 + button;
+    let cx = 32 + (col + 1).min(223) as u8;
+    let cy = 32 + (row + 1).min(223) as u8;
+    vec![0x1b, b'[', b'M', cb, cx, cy]
+}
@@ -0,0 +1,141 @@
+//! Desktop notifications requested by programs: OSC 9 (iTerm2, body only)
+//! and OSC 99 (kitty's protocol, with a title, buttons and click reports).
+
+/// A notification for the frontend to show.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    /// The program's identifier (`i=`), echoed in reports; "0" if unset
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub buttons: Vec<String>,
+    /// Focus the window when clicked (`a=focus`, on by default)
+    pub focus: bool,
+    /// Tell the program when it is clicked (`a=report`)
+    pub report: bool,
+    /// Skip it while the window has focus (`o=unfocused` / `o=invisible`)
+    pub only_unfocused: bool,
+}
+
+impl Notification {
+    fn new(id: &str) -> Self {
+        Self {
+            id: if id.is_empty() { "0".into() } else { id.into() },
+            title: String::new(),
+            body: String::new(),
+            buttons: Vec::new(),
+            focus: true,
+            report: false,
+            only_unfocused: false,
+        }
+    }
+
+    /// An OSC 9 notification: just a body.
+    pub fn simple(body: &str) -> Self {
+        Self { body: body.into(), ..Self::new("") }
+    }
+}
+
+/// What an OSC 99 chunk asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Kitty {
+    /// More chunks follow (`d=0`)
+    Pending,
+    Show(Notification),
+    /// `p=?`: reply with what we support
+    Query(String),
+    /// Payload types we don't handle (icons, close, alive)
+    Ignored,
+}
+
+/// Apply one OSC 99 chunk. `pending` holds a notification still receiving
+/// chunks; a chunk with another id replaces it.
+pub(crate) fn kitty(pending: &mut Option<Notification>, metadata: &[u8], payload: &[u8]) -> Kitty {
+    let metadata = String::from_utf8_lossy(metadata);
+    let (mut id, mut done, mut kind, mut encoded) = ("", true, "title", false);
+    let mut actions = None;
+    let mut occasion = None;
+    for (key, value) in metadata.split(':').filter_map(|kv| kv.split_once('=')) {
+        match key {
+            "i" => id = value,
+            "d" => done = value != "0",
+            "p" => kind = value,
+            "e" => encoded = value == "1",
+            "a" => actions = Some(value),
+            "o" => occasion = Some(value),
+            _ => {}
+        }
+    }
+    if kind == "?" {
+        let id = if id.is_empty() { "0" } else { id };
+        return Kitty::Query(format!(
+            "\x1b]99;i={id}:p=?;a=focus,report:o=always,unfocused,invisible:p=title,body,buttons,?\x1b\\"
+        ));
+    }
+    if !matches!(kind, "title" | "body" | "buttons") {
+        return Kitty::Ignored;
+    }
+    let payload = if encoded {
+        base64_decode(payload).unwrap_or_default()
+    } else {
+        payload.to_vec()
+    };
+    let text = String::from_utf8_lossy(&payload);
+    let n = match pending.take() {
+        Some(n) if id.is_empty() || n.id == id => pending.insert(n),
+        _ => pending.insert(Notification::new(id)),
+    };
+    match kind {
+        "title" => n.title.push_str(&text),
+        "body" => n.body.push_str(&text),
+        // Labels are separated by U+2028 LINE SEPARATOR
+        _ => n.buttons.extend(text.split('\u{2028}').filter(|b| !b.is_empty()).map(String::from)),
+    }
+    for action in actions.into_iter().flat_map(|a| a.split(',')) {
+        match action {
+            "focus" => n.focus = true,
+            "-focus" => n.focus = false,
+            "report" => n.report = true,
+            "-report" => n.report = false,
+            _ => {}
+        }
+    }
+    if let Some(o) = occasion {
+        n.only_unfocused = o != "always";
+    }
+    if !done {
+        return Kitty::Pending;
+    }
+    pending.take().map_or(Kitty::Pending, Kitty::Show)
+}
+
+/// What to send the program when the user clicks a notification that asked
+/// for reports: `button` is 1-based, `None` for the notification itself.
+pub fn activation_report(id: &str, button: Option<usize>) -> Vec<u8> {
+    let button = button.map(|b| b.to_string()).unwrap_or_default();
+    format!("\x1b]99;i={id};{button}\x1b\\").into_bytes()
+}
+
+pub(crate) fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let value = |c: u8| -> Option<u32> {
+        Some(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as u32)
+    };
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &c in input.iter().filter(|c| !c.is_ascii_whitespace() && **c != b'=') {
+        acc = ((acc << 6) | value(c)?) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
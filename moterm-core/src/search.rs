@@ -0,0 +1,167 @@
+use crate::terminal::Pos;
+
+/// Simple text search through terminal scrollback + screen.
+pub struct SearchState {
+    pub active: bool,
+    pub query: String,
+    pub matches: Vec<SearchMatch>,
+    pub current: usize,
+}
+
+/// A match from `start` to `end` (inclusive). It spans rows when the
+/// match crosses a soft wrap.
+#[derive(Clone, Debug)]
+pub struct SearchMatch {
+    pub start: Pos,
+    pub end: Pos,
+}
+
+impl SearchMatch {
+    pub fn contains(&self, global_row: usize, col: usize) -> bool {
+        let p = (global_row, col);
+        p >= (self.start.row, self.start.col) && p <= (self.end.row, self.end.col)
+    }
+}
+
+/// Columns `start..end` of one row covered by a match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+    pub current: bool,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            query: String::new(),
+            matches: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        if !self.active {
+            self.query.clear();
+            self.matches.clear();
+            self.current = 0;
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.query.clear();
+        self.matches.clear();
+        self.current = 0;
+    }
+
+    pub fn push_char(&mut self, ch: char) {
+        self.query.push(ch);
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+    }
+
+    pub fn search(&mut self, term: &crate::terminal::Terminal) {
+        self.matches.clear();
+        if self.query.is_empty() {
+            return;
+        }
+        let q: Vec<char> = self.query.chars().map(fold).collect();
+        let cols = term.cols().max(1);
+
+        // Match over logical lines so hits across a soft wrap are found
+        let total = term.total_lines();
+        let mut row = 0;
+        while row < total {
+            let (first, last) = term.logical_line_bounds(row);
+            let text: Vec<char> = (first..=last)
+                .filter_map(|r| term.line_at_global(r))
+                .flat_map(|line| {
+                    // Scrollback rows may be trimmed of trailing blanks
+                    let blanks = cols.saturating_sub(line.cells.len());
+                    line.cells.iter().map(|c| fold(c.ch)).chain(std::iter::repeat_n(' ', blanks))
+                })
+                .collect();
+            let at = |i: usize| Pos { row: first + i / cols, col: i % cols };
+            for i in 0..text.len().saturating_sub(q.len() - 1) {
+                if text[i..i + q.len()] == q[..] {
+                    self.matches.push(SearchMatch {
+                        start: at(i),
+                        end: at(i + q.len() - 1),
+                    });
+                }
+            }
+            row = last + 1;
+        }
+
+        // Clamp current
+        if !self.matches.is_empty() {
+            self.current = self.current.min(self.matches.len() - 1);
+        } else {
+            self.current = 0;
+        }
+    }
+
+    pub fn next_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+    }
+
+    pub fn prev_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.current = if self.current == 0 {
+                self.matches.len() - 1
+            } else {
+                self.current - 1
+            };
+        }
+    }
+
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.matches.get(self.current)
+    }
+
+    pub fn is_highlighted(&self, global_row: usize, col: usize) -> bool {
+        self.matches.iter().any(|m| m.contains(global_row, col))
+    }
+
+    pub fn is_current_highlight(&self, global_row: usize, col: usize) -> bool {
+        self.current_match().is_some_and(|m| m.contains(global_row, col))
+    }
+
+    /// Match spans for each of `rows` rows starting at global row `first`,
+    /// so a frame only visits the matches it shows. Matches are in order,
+    /// so the first visible one is found by binary search.
+    pub fn row_spans(&self, first: usize, rows: usize, cols: usize) -> Vec<Vec<MatchSpan>> {
+        let mut spans = vec![Vec::new(); rows];
+        let from = self.matches.partition_point(|m| m.end.row < first);
+        for (i, m) in self.matches.iter().enumerate().skip(from) {
+            if m.start.row >= first + rows {
+                break;
+            }
+            let current = i == self.current;
+            for row in m.start.row.max(first)..=m.end.row.min(first + rows - 1) {
+                let start = if row == m.start.row { m.start.col } else { 0 };
+                let end = if row == m.end.row { m.end.col + 1 } else { cols };
+                spans[row - first].push(MatchSpan { start, end, current });
+            }
+        }
+        spans
+    }
+}
+
+/// Case-insensitive comparison key for one cell.
+fn fold(ch: char) -> char {
+    ch.to_lowercase().next().unwrap_or(ch)
+}
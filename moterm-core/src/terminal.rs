@@ -0,0 +1,1628 @@
+#![allow(clippy::manual_range_patterns)]
+
+use std::cmp::{max, min};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::color::{ColorSpec, Palette};
+use crate::graphics::{ImageSlice, ImageStore, KittyCommand, Sniffer};
+use crate::grid::{resize_row, Damage, Grid};
+use crate::hyperlink::Hyperlinks;
+use crate::notify::Notification;
+use crate::terminfo::Features;
+
+/// Default number of scrollback rows; see [`Terminal::with_scrollback`].
+pub const SCROLLBACK_LIMIT: usize = 2000;
+/// Longest window title kept from OSC 0/2.
+const MAX_TITLE_CHARS: usize = 256;
+/// Notifications kept until the frontend takes them.
+const MAX_NOTIFICATIONS: usize = 16;
+/// Image slices kept per row; more images overlapping one row are dropped.
+const MAX_ROW_IMAGES: usize = 16;
+
+/// Colors and SGR attributes applied to a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub fg: ColorSpec,
+    pub bg: ColorSpec,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub inverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fg: ColorSpec::DefaultFg,
+            bg: ColorSpec::DefaultBg,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+            blink: false,
+            inverse: false,
+            hidden: false,
+            strikethrough: false,
+        }
+    }
+}
+
+impl Style {
+    /// Foreground to draw; with `bold_is_bright`, bold text in colors 0–7
+    /// uses the bright variants 8–15.
+    pub fn draw_fg(&self, bold_is_bright: bool) -> ColorSpec {
+        match self.fg {
+            ColorSpec::Indexed(i) if bold_is_bright && self.bold && i < 8 => ColorSpec::Indexed(i + 8),
+            fg => fg,
+        }
+    }
+}
+
+/// One grid cell. The right half of a wide char is a `wide_cont` cell.
+#[derive(Clone, Copy, Debug)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+    /// Second half of a wide character, or the padding left at the end of
+    /// a row when a wide character wrapped early; holds no text
+    pub wide_cont: bool,
+    /// OSC 8 link id in [`Terminal::links`], 0 for none
+    pub link: u32,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: Style::default(),
+            wide_cont: false,
+            link: 0,
+        }
+    }
+}
+
+/// One grid row, on screen or in scrollback.
+#[derive(Clone, Debug)]
+pub struct Row {
+    pub cells: Vec<Cell>,
+    /// Soft-wrapped: the logical line continues on the next row
+    pub wrapped: bool,
+    /// Unix time (seconds) the row was first written to; 0 if never
+    pub time: u64,
+    /// Shell integration marks received while the cursor was on this row
+    pub marks: Vec<Mark>,
+    /// Bands of inline images drawn over this row
+    pub images: Vec<ImageSlice>,
+}
+
+/// Shell integration mark kinds (OSC 133).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkKind {
+    /// `A`: prompt starts
+    Prompt,
+    /// `B`: prompt ends, command line starts
+    Command,
+    /// `C`: command line ends, output starts
+    Output,
+    /// `D`: command finished
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mark {
+    pub kind: MarkKind,
+    pub col: usize,
+}
+
+impl Row {
+    pub fn new(cols: usize) -> Self {
+        Self {
+            cells: vec![Cell::default(); cols],
+            wrapped: false,
+            time: 0,
+            marks: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    pub fn has_mark(&self, kind: MarkKind) -> bool {
+        self.marks.iter().any(|m| m.kind == kind)
+    }
+
+    pub fn clear_range(&mut self, start: usize, end: usize, fill: Cell) {
+        let s = min(start, self.cells.len());
+        let e = min(end, self.cells.len());
+        for c in &mut self.cells[s..e] {
+            *c = fill;
+        }
+        if e == self.cells.len() && s < e {
+            self.wrapped = false;
+            if s == 0 {
+                self.time = 0;
+                self.marks.clear();
+                self.images.clear();
+            }
+        }
+    }
+}
+
+/// Position in global rows (scrollback first, then screen) and columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Selection from where the drag started (anchor) to where it is now (focus).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: Pos,
+    pub focus: Pos,
+}
+
+impl Selection {
+    pub fn normalized(&self) -> (Pos, Pos) {
+        if (self.anchor.row, self.anchor.col) <= (self.focus.row, self.focus.col) {
+            (self.anchor, self.focus)
+        } else {
+            (self.focus, self.anchor)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CursorStyle {
+    Block,
+    Beam,
+    Underline,
+}
+
+/// Character set designated into G0/G1 (`ESC ( 0`, `ESC ) B` …).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    /// DEC Special Graphics: `lqqk` and friends draw boxes
+    DecSpecial,
+}
+
+impl Charset {
+    fn map(self, ch: char) -> char {
+        if self == Charset::Ascii {
+            return ch;
+        }
+        match ch {
+            '`' => '◆',
+            'a' => '▒',
+            'b' => '␉',
+            'c' => '␌',
+            'd' => '␍',
+            'e' => '␊',
+            'f' => '°',
+            'g' => '±',
+            'h' => '␤',
+            'i' => '␋',
+            'j' => '┘',
+            'k' => '┐',
+            'l' => '┌',
+            'm' => '└',
+            'n' => '┼',
+            'o' => '⎺',
+            'p' => '⎻',
+            'q' => '─',
+            'r' => '⎼',
+            's' => '⎽',
+            't' => '├',
+            'u' => '┤',
+            'v' => '┴',
+            'w' => '┬',
+            'x' => '│',
+            'y' => '≤',
+            'z' => '≥',
+            '{' => 'π',
+            '|' => '≠',
+            '}' => '£',
+            '~' => '·',
+            _ => ch,
+        }
+    }
+}
+
+/// Terminal state: screen grid, scrollback, cursor, modes and selection.
+///
+/// Mutated by [`VteHandler`](crate::vte_handler::VteHandler) as PTY output
+/// is parsed; read by the frontend to render.
+pub struct Terminal {
+    cols: usize,
+    rows: usize,
+    /// Scrollback and screen rows
+    pub grid: Grid,
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+    pub style: Style,
+    pub selection: Option<Selection>,
+    pub view_scroll: usize,
+    pub title: String,
+    pub title_changed: bool,
+    /// Set when scrollback was cleared; the frontend drops state keyed to
+    /// global rows (search matches, highlighted command block)
+    pub scrollback_cleared: bool,
+    /// Rows dropped from the top of scrollback so far; state keyed to
+    /// global rows shifts by how much this grew
+    pub evicted_rows: u64,
+    /// Shell working directory reported via OSC 7
+    pub cwd: Option<String>,
+    pub cursor_style: CursorStyle,
+    /// Style from the config, restored by DECSCUSR 0, resets and prompts
+    pub default_cursor_style: CursorStyle,
+    /// Mouse pointer shape requested with OSC 22 ("pointer", "text",
+    /// "crosshair" …); empty for the default
+    pub pointer_shape: String,
+    /// G0 and G1 character sets; shift out (SO) selects G1
+    pub charsets: [Charset; 2],
+    pub shift_out: bool,
+    pub bell: bool,
+    /// Mouse tracking mode: 0=off, 1000=normal, 1002=button, 1003=any
+    pub mouse_mode: u16,
+    /// Mouse encoding: false=normal/utf8, true=SGR (1006)
+    pub mouse_sgr: bool,
+    /// Bracketed paste mode
+    pub bracketed_paste: bool,
+    /// Alternate screen buffer active
+    pub alt_screen: bool,
+    /// Primary screen rows, kept aside while the alternate screen is shown
+    primary_screen: Option<Vec<Row>>,
+    /// Rows scrolled off the alternate screen that are kept for viewing
+    /// (`scrolling.alt_screen_history`); 0 keeps none
+    pub alt_history: usize,
+    /// Scrollback length when the alternate screen was entered: rows past
+    /// it are alternate-screen history, dropped on exit
+    alt_base: usize,
+    /// Scroll region (top, bottom) — 0-indexed, bottom is exclusive
+    pub scroll_top: usize,
+    pub scroll_bottom: usize,
+    /// Saved cursor position
+    saved_cursor_row: usize,
+    saved_cursor_col: usize,
+    /// Reply buffer for DSR responses
+    pub reply_buf: Vec<u8>,
+    /// Current unix time, set by the frontend before feeding output.
+    /// Stamped on rows as they are first written.
+    pub clock: u64,
+    /// Drop NBSP and soft hyphen instead of giving each a cell
+    /// (`compat.invisible_chars = "collapse"`)
+    pub collapse_invisible: bool,
+    /// Colors cells resolve to when drawn or exported
+    pub palette: Palette,
+    /// `TERM` given to the shell; replies to DA/XTGETTCAP follow it
+    term_name: String,
+    /// What `term_name` supports; see [`Self::set_term_name`]
+    features: Features,
+    /// OSC 9/99 notifications for the frontend to show
+    pub notifications: Vec<Notification>,
+    /// OSC 99 notification still receiving chunks (`d=0`)
+    pub(crate) pending_notification: Option<Notification>,
+    /// Payload of an XTGETTCAP request being received
+    pub(crate) dcs: Option<Vec<u8>>,
+    /// Inline images (kitty graphics, OSC 1337), drawn where rows' slices say
+    pub images: ImageStore,
+    /// OSC 8 link targets, by the ids cells carry
+    pub links: Hyperlinks,
+    /// Link given to printed cells: the open OSC 8 link, or 0
+    pub(crate) link: u32,
+    /// Cell width and height in pixels, set by the frontend: sizes images
+    /// given in pixels and answers CSI 14/16 t
+    pub cell_size: (usize, usize),
+    /// Picks image sequences out of the output vte drops or truncates
+    pub(crate) image_sniffer: Sniffer,
+    /// Kitty image still receiving chunks (`m=1`): its keys and data so far
+    pub(crate) pending_image: Option<(KittyCommand, Vec<u8>)>,
+}
+
+impl Terminal {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self::with_scrollback(cols, rows, SCROLLBACK_LIMIT)
+    }
+
+    /// A terminal keeping up to `scrollback` rows of history; 0 keeps none.
+    pub fn with_scrollback(cols: usize, rows: usize, scrollback: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        Self {
+            cols,
+            rows,
+            grid: Grid::new(cols, rows, scrollback),
+            cursor_row: 0,
+            cursor_col: 0,
+            style: Style::default(),
+            selection: None,
+            view_scroll: 0,
+            title: String::new(),
+            title_changed: false,
+            scrollback_cleared: false,
+            evicted_rows: 0,
+            cwd: None,
+            cursor_style: CursorStyle::Block,
+            default_cursor_style: CursorStyle::Block,
+            pointer_shape: String::new(),
+            charsets: [Charset::Ascii; 2],
+            shift_out: false,
+            bell: false,
+            mouse_mode: 0,
+            mouse_sgr: false,
+            bracketed_paste: false,
+            alt_screen: false,
+            primary_screen: None,
+            alt_history: 0,
+            alt_base: 0,
+            scroll_top: 0,
+            scroll_bottom: rows,
+            saved_cursor_row: 0,
+            saved_cursor_col: 0,
+            reply_buf: Vec::new(),
+            clock: 0,
+            collapse_invisible: false,
+            palette: Palette::default(),
+            term_name: crate::terminfo::DEFAULT_TERM.to_string(),
+            features: Features::of(crate::terminfo::DEFAULT_TERM),
+            notifications: Vec::new(),
+            pending_notification: None,
+            dcs: None,
+            images: ImageStore::default(),
+            links: Hyperlinks::default(),
+            link: 0,
+            cell_size: (8, 16),
+            image_sniffer: Sniffer::default(),
+            pending_image: None,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Cell left by erasing: current colors, but no attributes (an erased
+    /// cell isn't underlined or inverted).
+    fn blank_cell(&self) -> Cell {
+        Cell {
+            ch: ' ',
+            style: Style { fg: self.style.fg, bg: self.style.bg, ..Style::default() },
+            wide_cont: false,
+            link: 0,
+        }
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    pub fn start_selection(&mut self, pos: Pos) {
+        self.selection = Some(Selection {
+            anchor: pos,
+            focus: pos,
+        });
+    }
+
+    /// Keep the selection on the same text after `evicted` rows are dropped
+    /// from the top of scrollback; clear it if any of its rows were dropped.
+    fn shift_selection(&mut self, evicted: usize) {
+        if evicted == 0 {
+            return;
+        }
+        self.evicted_rows += evicted as u64;
+        if let Some(sel) = &mut self.selection {
+            if sel.anchor.row < evicted || sel.focus.row < evicted {
+                self.selection = None;
+            } else {
+                sel.anchor.row -= evicted;
+                sel.focus.row -= evicted;
+            }
+        }
+    }
+
+    /// Drop the selection if it touches screen rows `rows`, whose content is
+    /// about to change.
+    fn invalidate_selection_rows(&mut self, rows: std::ops::Range<usize>) {
+        if let Some((a, b)) = self.normalized_selection() {
+            let base = self.grid.scrollback_len();
+            if a.row < base + rows.end && b.row >= base + rows.start {
+                self.selection = None;
+            }
+        }
+    }
+
+    pub fn update_selection(&mut self, pos: Pos) {
+        if let Some(sel) = &mut self.selection {
+            sel.focus = pos;
+        }
+    }
+
+    pub fn set_view_scroll(&mut self, delta: isize) {
+        // Full-screen apps own the viewport; only their own history is viewable
+        let max_scroll = self.max_view_scroll() as isize;
+        let next = (self.view_scroll as isize + delta).clamp(0, max_scroll);
+        self.view_scroll = next as usize;
+    }
+
+    pub fn reset_view_scroll(&mut self) {
+        self.view_scroll = 0;
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+
+        if self.alt_screen {
+            // History rows sit above the shell's screen; don't carry them into it
+            self.grid.truncate_scrollback(self.alt_base);
+            self.view_scroll = 0;
+        }
+        let mut scrolled_top = (self.view_scroll > 0).then(|| self.visible_start_global_row());
+        if cols != self.cols {
+            // Rewrapped text no longer lines up with selected cells
+            self.selection = None;
+        }
+        let evicted = match self.primary_screen.take() {
+            // Under a full-screen app, anchor the shell's screen at its saved cursor
+            Some(primary) => {
+                let mut alt = self.grid.replace_screen(primary);
+                let mut saved = Pos { row: self.saved_cursor_row, col: self.saved_cursor_col };
+                let evicted = self.grid.resize(cols, rows, &mut saved, &mut scrolled_top);
+                (self.saved_cursor_row, self.saved_cursor_col) = (saved.row, saved.col);
+                // The app redraws its own screen, so it is cut rather than rewrapped
+                if cols != self.cols {
+                    alt.iter_mut().for_each(|r| resize_row(r, cols));
+                }
+                alt.resize_with(rows, || Row::new(cols));
+                self.primary_screen = Some(self.grid.replace_screen(alt));
+                evicted
+            }
+            None => {
+                let mut cursor = Pos { row: self.cursor_row, col: self.cursor_col };
+                let evicted = self.grid.resize(cols, rows, &mut cursor, &mut scrolled_top);
+                (self.cursor_row, self.cursor_col) = (cursor.row, cursor.col);
+                evicted
+            }
+        };
+        self.shift_selection(evicted);
+        self.alt_base = self.grid.scrollback_len();
+        self.cols = cols;
+        self.rows = rows;
+        self.cursor_row = min(self.cursor_row, rows - 1);
+        // cols itself is the pending-wrap position
+        self.cursor_col = min(self.cursor_col, cols);
+        self.saved_cursor_row = min(self.saved_cursor_row, rows - 1);
+        self.scroll_top = 0;
+        self.scroll_bottom = rows;
+        self.view_scroll = match scrolled_top {
+            // Keep the same line at the top while reviewing history
+            Some(top) => self.grid.scrollback_len().saturating_sub(top.saturating_sub(evicted)),
+            None => 0,
+        };
+    }
+
+    /// Queue a notification; a frontend that never drains the queue only
+    /// keeps the newest few.
+    pub(crate) fn notify(&mut self, n: Notification) {
+        if self.notifications.len() >= MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
+        }
+        self.notifications.push(n);
+    }
+
+    pub fn line_feed(&mut self) {
+        if self.cursor_row + 1 == self.scroll_bottom.min(self.rows) {
+            self.scroll_up(1);
+        } else if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        }
+    }
+
+    pub fn carriage_return(&mut self) {
+        self.cursor_col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor_col > 0 {
+            self.cursor_col -= 1;
+        }
+    }
+
+    pub fn tab(&mut self) {
+        let next = ((self.cursor_col / 8) + 1) * 8;
+        self.cursor_col = min(next, self.cols.saturating_sub(1));
+    }
+
+    /// Scroll the scroll region up. Rows only reach scrollback when the
+    /// region is the whole screen.
+    fn scroll_up(&mut self, lines: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom.min(self.rows));
+        if top > 0 || bottom < self.rows {
+            self.invalidate_selection_rows(top..bottom);
+            for _ in 0..min(lines, bottom - top) {
+                self.grid.scroll_region_up(top, bottom);
+            }
+            return;
+        }
+        if self.alt_screen {
+            // Rows shift in place instead of moving into scrollback
+            self.invalidate_selection_rows(0..self.rows);
+        }
+        for _ in 0..lines {
+            // The alternate screen keeps only `alt_history` rows of its own
+            let evicted = self.grid.scroll_up(!self.alt_screen || self.alt_history > 0);
+            self.shift_selection(evicted);
+            if self.alt_screen {
+                self.alt_base = self.alt_base.saturating_sub(evicted);
+                if self.grid.scrollback_len() - self.alt_base > self.alt_history {
+                    self.grid.remove_scrollback_row(self.alt_base);
+                    // Rows after it moved up; only alt rows can be selected
+                    self.selection = None;
+                }
+            }
+        }
+        if self.view_scroll > 0 {
+            self.view_scroll = min(self.view_scroll + lines, self.max_view_scroll());
+        }
+    }
+
+    pub fn put_char(&mut self, ch: char) {
+        if ch == '\0' || ch == '\u{7f}' {
+            return;
+        }
+        let ch = self.charsets[self.shift_out as usize].map(ch);
+        if self.collapse_invisible && matches!(ch, '\u{a0}' | '\u{ad}') {
+            return;
+        }
+        // A one-column grid can't hold a wide char; it overflows its cell instead
+        let width = UnicodeWidthChar::width(ch).unwrap_or(1).clamp(1, self.cols.min(2));
+        if self.cursor_col >= self.cols {
+            self.soft_wrap();
+        }
+        if width == 2 && self.cursor_col + 1 >= self.cols {
+            // The last column stays empty; mark it so copies don't add a space
+            let pad = Cell { wide_cont: true, style: self.style, ..Cell::default() };
+            if let Some(cell) = self.grid.get_mut(self.cursor_row).and_then(|r| r.cells.get_mut(self.cursor_col)) {
+                *cell = pad;
+            }
+            self.soft_wrap();
+        }
+        if self.cursor_row >= self.rows {
+            self.cursor_row = self.rows - 1;
+        }
+        if self.selection.is_some() {
+            let global_row = self.grid.scrollback_len() + self.cursor_row;
+            let old = self.grid[self.cursor_row].cells[self.cursor_col];
+            if old.ch != ch && self.is_selected(global_row, self.cursor_col) {
+                self.selection = None;
+            }
+        }
+        let clock = self.clock;
+        let style = self.style;
+        let link = self.link;
+        let col = self.cursor_col;
+        let cols = self.cols;
+        let row = self.current_row_mut();
+        if row.time == 0 {
+            row.time = clock;
+        }
+        if col + width >= cols {
+            // Rewriting the end of a row: it only stays wrapped if we wrap again
+            row.wrapped = false;
+        }
+        row.cells[col] = Cell {
+            ch,
+            style,
+            wide_cont: false,
+            link,
+        };
+        if width == 2 {
+            row.cells[col + 1] = Cell {
+                ch: ' ',
+                style,
+                wide_cont: true,
+                link,
+            };
+        }
+        self.cursor_col += width;
+        if self.cursor_col >= self.cols {
+            self.cursor_col = self.cols;
+        }
+    }
+
+    /// The cursor's screen row. The row is clamped so a cursor left out of
+    /// range by a bad sequence can't index past the grid.
+    fn current_row_mut(&mut self) -> &mut Row {
+        let row = min(self.cursor_row, self.rows - 1);
+        &mut self.grid[row]
+    }
+
+    fn soft_wrap(&mut self) {
+        if let Some(row) = self.grid.get_mut(self.cursor_row) {
+            row.wrapped = true;
+        }
+        self.cursor_col = 0;
+        self.line_feed();
+    }
+
+    pub fn move_cursor(&mut self, row: usize, col: usize) {
+        self.cursor_row = min(row, self.rows.saturating_sub(1));
+        self.cursor_col = min(col, self.cols.saturating_sub(1));
+    }
+
+    pub fn move_rel(&mut self, dr: isize, dc: isize) {
+        let nr = (self.cursor_row as isize + dr).clamp(0, self.rows.saturating_sub(1) as isize);
+        let nc = (self.cursor_col as isize + dc).clamp(0, self.cols.saturating_sub(1) as isize);
+        self.cursor_row = nr as usize;
+        self.cursor_col = nc as usize;
+    }
+
+    pub fn erase_in_display(&mut self, mode: usize) {
+        let fill = self.blank_cell();
+        match mode {
+            0 => self.invalidate_selection_rows(self.cursor_row..self.rows),
+            1 => self.invalidate_selection_rows(0..self.cursor_row + 1),
+            2 => self.invalidate_selection_rows(0..self.rows),
+            _ => {}
+        }
+        match mode {
+            0 => {
+                self.erase_in_line(0);
+                for r in self.cursor_row + 1..self.rows {
+                    self.grid[r].clear_range(0, self.cols, fill);
+                }
+            }
+            1 => {
+                for r in 0..self.cursor_row {
+                    self.grid[r].clear_range(0, self.cols, fill);
+                }
+                self.erase_in_line(1);
+            }
+            2 => {
+                for r in 0..self.rows {
+                    self.grid[r].clear_range(0, self.cols, fill);
+                }
+            }
+            // Like xterm (and Cmd+K): scrollback only, the screen is kept
+            3 => self.clear_scrollback(),
+            _ => {}
+        }
+    }
+
+    pub fn erase_in_line(&mut self, mode: usize) {
+        self.invalidate_selection_rows(self.cursor_row..self.cursor_row + 1);
+        let fill = self.blank_cell();
+        let (col, cols) = (self.cursor_col, self.cols);
+        let row = self.current_row_mut();
+        match mode {
+            0 => row.clear_range(col, cols, fill),
+            1 => row.clear_range(0, col + 1, fill),
+            2 => row.clear_range(0, cols, fill),
+            _ => {}
+        }
+    }
+
+    pub fn visible_start_global_row(&self) -> usize {
+        self.grid.scrollback_len().saturating_sub(self.view_scroll)
+    }
+
+    pub fn total_lines(&self) -> usize {
+        self.grid.total()
+    }
+
+    /// Scrollback rows that only change by being evicted: all of it, less
+    /// the alternate screen's own history, which is dropped on exit.
+    pub fn settled_rows(&self) -> usize {
+        if self.alt_screen {
+            self.alt_base
+        } else {
+            self.grid.scrollback_len()
+        }
+    }
+
+    /// Change how many scrollback rows are kept; extra rows go at the next scroll.
+    pub fn set_scrollback_limit(&mut self, rows: usize) {
+        self.grid.set_max_scrollback(rows);
+    }
+
+    /// Screen rows written since the last frame; see [`Grid::take_damage`].
+    pub fn take_damage(&mut self) -> Damage {
+        self.grid.take_damage()
+    }
+
+    pub fn line_at_global(&self, row: usize) -> Option<&Row> {
+        self.grid.line(row)
+    }
+
+    /// URI of the OSC 8 link on the cell at `row`, `col`, if any
+    pub fn link_at(&self, row: usize, col: usize) -> Option<&str> {
+        let cell = self.line_at_global(row)?.cells.get(col)?;
+        self.links.get(cell.link)
+    }
+
+    /// Global row range [first, last] of the logical line containing `row`,
+    /// following soft wraps in both directions.
+    pub fn logical_line_bounds(&self, row: usize) -> (usize, usize) {
+        let mut first = row;
+        while first > 0 && self.line_at_global(first - 1).is_some_and(|r| r.wrapped) {
+            first -= 1;
+        }
+        let mut last = row;
+        let total = self.total_lines();
+        while last + 1 < total && self.line_at_global(last).is_some_and(|r| r.wrapped) {
+            last += 1;
+        }
+        (first, last)
+    }
+
+    /// Text of the logical line containing `row`, one char per cell, plus
+    /// the global row it starts on. Column `c` of row `r` is at char index
+    /// `(r - first) * cols + c`.
+    pub fn logical_line_text(&self, row: usize) -> (String, usize) {
+        let (first, last) = self.logical_line_bounds(row);
+        let mut text = String::new();
+        for r in first..=last {
+            if let Some(line) = self.line_at_global(r) {
+                text.extend(line.cells.iter().map(|c| c.ch));
+                // Put back the blanks a scrollback row was trimmed of
+                for _ in line.cells.len()..self.cols {
+                    text.push(' ');
+                }
+            }
+        }
+        (text, first)
+    }
+
+    pub fn visible_line(&self, view_row: usize) -> Option<&Row> {
+        let global = self.visible_start_global_row().saturating_add(view_row);
+        self.line_at_global(global)
+    }
+
+    pub fn pos_for_view(&self, view_row: usize, col: usize) -> Pos {
+        let max_col = self.cols.saturating_sub(1);
+        let max_row = self.total_lines().saturating_sub(1);
+        let row = min(
+            self.visible_start_global_row().saturating_add(view_row),
+            max_row,
+        );
+        Pos {
+            row,
+            col: min(col, max_col),
+        }
+    }
+
+    pub fn is_selected(&self, global_row: usize, col: usize) -> bool {
+        let Some(sel) = &self.selection else {
+            return false;
+        };
+        let (a, b) = sel.normalized();
+        if global_row < a.row || global_row > b.row {
+            return false;
+        }
+        if a.row == b.row {
+            return col >= a.col && col <= b.col && global_row == a.row;
+        }
+        if global_row == a.row {
+            return col >= a.col;
+        }
+        if global_row == b.row {
+            return col <= b.col;
+        }
+        true
+    }
+
+    pub fn selection_text(&self) -> Option<String> {
+        let (a, b) = self.selection.as_ref()?.normalized();
+        self.text_between(a, b)
+    }
+
+    /// Text from `a` to `b` inclusive, with trailing blanks trimmed per row.
+    pub fn text_between(&self, a: Pos, b: Pos) -> Option<String> {
+        let mut out = String::new();
+        for row_idx in a.row..=b.row {
+            let row = self.line_at_global(row_idx)?;
+            let mut start = if row_idx == a.row { a.col } else { 0 };
+            // Starting on the right half of a wide char copies the whole char
+            if start > 0 && row.cells.get(start).is_some_and(|c| c.wide_cont) && !row.cells[start - 1].wide_cont {
+                start -= 1;
+            }
+            let end = if row_idx == b.row {
+                b.col
+            } else {
+                self.cols.saturating_sub(1)
+            };
+            let mut line = String::new();
+            for col in start..=min(end, self.cols.saturating_sub(1)) {
+                let cell = row.cells.get(col).copied().unwrap_or_default();
+                if cell.wide_cont {
+                    continue;
+                }
+                line.push(cell.ch);
+            }
+            // A soft-wrapped row continues on the next one: keep its
+            // trailing blanks and don't break the line
+            if row_idx == b.row || !row.wrapped {
+                while line.ends_with(' ') {
+                    line.pop();
+                }
+            }
+            out.push_str(&line);
+            if row_idx != b.row && !row.wrapped {
+                out.push('\n');
+            }
+        }
+        Some(out)
+    }
+
+    pub fn cursor_global_pos(&self) -> Pos {
+        Pos {
+            row: self.grid.scrollback_len() + self.cursor_row,
+            col: self.cursor_col.min(self.cols.saturating_sub(1)),
+        }
+    }
+
+    pub fn sgr(&mut self, params: &[i64]) {
+        if params.is_empty() {
+            self.style = Style::default();
+            return;
+        }
+        // Colors the advertised TERM doesn't have are ignored
+        let colors = self.features.colors;
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                2 => self.style.dim = true,
+                3 => self.style.italic = true,
+                // Underline styles (4:x) are drawn as a single underline
+                4 => self.style.underline = true,
+                5 | 6 => self.style.blink = true,
+                7 => self.style.inverse = true,
+                8 => self.style.hidden = true,
+                9 => self.style.strikethrough = true,
+                21 => self.style.underline = true,
+                22 => {
+                    self.style.bold = false;
+                    self.style.dim = false;
+                }
+                23 => self.style.italic = false,
+                24 => self.style.underline = false,
+                25 => self.style.blink = false,
+                27 => self.style.inverse = false,
+                28 => self.style.hidden = false,
+                29 => self.style.strikethrough = false,
+                39 => self.style.fg = ColorSpec::DefaultFg,
+                49 => self.style.bg = ColorSpec::DefaultBg,
+                30..=37 if colors > 0 => self.style.fg = ColorSpec::Indexed((params[i] - 30) as u8),
+                40..=47 if colors > 0 => self.style.bg = ColorSpec::Indexed((params[i] - 40) as u8),
+                90..=97 if colors > 0 => self.style.fg = ColorSpec::Indexed((params[i] - 90 + 8) as u8),
+                100..=107 if colors > 0 => self.style.bg = ColorSpec::Indexed((params[i] - 100 + 8) as u8),
+                38 | 48 => {
+                    let is_fg = params[i] == 38;
+                    let color = match params.get(i + 1) {
+                        Some(5) if i + 2 < params.len() => {
+                            i += 2;
+                            Some(ColorSpec::Indexed(params[i].clamp(0, 255) as u8))
+                        }
+                        Some(2) if i + 4 < params.len() => {
+                            i += 4;
+                            let c = |n: i64| n.clamp(0, 255) as u8;
+                            Some(ColorSpec::Rgb(c(params[i - 2]), c(params[i - 1]), c(params[i])))
+                        }
+                        _ => None,
+                    };
+                    match color.filter(|_| colors >= 256) {
+                        Some(c) if is_fg => self.style.fg = c,
+                        Some(c) => self.style.bg = c,
+                        None => {}
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    pub fn scroll_view_page(&mut self, pages: isize) {
+        let delta = pages * self.rows as isize;
+        self.set_view_scroll(delta);
+    }
+
+    pub fn clamp_col(&self, col: usize) -> usize {
+        min(col, self.cols.saturating_sub(1))
+    }
+
+    pub fn clamp_view_row(&self, row: usize) -> usize {
+        min(row, self.rows.saturating_sub(1))
+    }
+
+    /// OSC 0/2. Control characters are dropped (newlines become spaces) and
+    /// the length is capped before the title reaches the window system.
+    pub fn append_osc_title(&mut self, title: &str) {
+        let title: String = title
+            .chars()
+            .filter_map(|c| match c {
+                '\n' | '\r' | '\t' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .take(MAX_TITLE_CHARS)
+            .collect();
+        if title != self.title {
+            self.title = title;
+            self.title_changed = true;
+        }
+    }
+
+    /// OSC 7: `file://host/path` working directory report.
+    pub fn set_cwd_from_osc(&mut self, uri: &str) {
+        if let Some(path) = crate::url::file_url_to_path(uri) {
+            self.cwd = Some(path);
+        }
+    }
+
+    pub fn insert_blank_chars(&mut self, count: usize) {
+        self.invalidate_selection_rows(self.cursor_row..self.cursor_row + 1);
+        let fill = self.blank_cell();
+        let (col, cols) = (self.cursor_col, self.cols);
+        let count = min(count, cols.saturating_sub(col));
+        if count == 0 {
+            return;
+        }
+        let row = self.current_row_mut();
+        for c in (col..cols).rev() {
+            if c >= col + count {
+                row.cells[c] = row.cells[c - count];
+            } else {
+                row.cells[c] = fill;
+            }
+        }
+    }
+
+    pub fn delete_chars(&mut self, count: usize) {
+        self.invalidate_selection_rows(self.cursor_row..self.cursor_row + 1);
+        let fill = self.blank_cell();
+        let (col, cols) = (self.cursor_col, self.cols);
+        let count = min(count, cols.saturating_sub(col));
+        if count == 0 {
+            return;
+        }
+        let row = self.current_row_mut();
+        for c in col..cols {
+            if c + count < cols {
+                row.cells[c] = row.cells[c + count];
+            } else {
+                row.cells[c] = fill;
+            }
+        }
+    }
+
+    pub fn set_cursor_col(&mut self, col: usize) {
+        self.cursor_col = min(col, self.cols.saturating_sub(1));
+    }
+
+    pub fn set_cursor_row(&mut self, row: usize) {
+        self.cursor_row = min(row, self.rows.saturating_sub(1));
+    }
+
+    /// SD: scroll the scroll region down, blank rows appearing at its top.
+    pub fn scroll_down_lines(&mut self, lines: usize) {
+        let (top, bottom) = (self.scroll_top, self.scroll_bottom.min(self.rows));
+        self.scroll_region_down(top, bottom, lines);
+    }
+
+    /// SU: scroll the scroll region up.
+    pub fn scroll_up_lines(&mut self, lines: usize) {
+        self.scroll_up(lines);
+    }
+
+    /// IL: insert blank rows at the cursor, pushing rows below it towards
+    /// the bottom of the scroll region. Does nothing outside the region.
+    pub fn insert_lines(&mut self, lines: usize) {
+        let bottom = self.scroll_bottom.min(self.rows);
+        if self.cursor_row < self.scroll_top || self.cursor_row >= bottom {
+            return;
+        }
+        self.scroll_region_down(self.cursor_row, bottom, lines);
+        self.cursor_col = 0;
+    }
+
+    /// DL: delete rows at the cursor, pulling rows up from the bottom of the
+    /// scroll region. Deleted rows never go to scrollback.
+    pub fn delete_lines(&mut self, lines: usize) {
+        let bottom = self.scroll_bottom.min(self.rows);
+        if self.cursor_row < self.scroll_top || self.cursor_row >= bottom {
+            return;
+        }
+        self.invalidate_selection_rows(self.cursor_row..bottom);
+        for _ in 0..min(lines, bottom - self.cursor_row) {
+            self.grid.scroll_region_up(self.cursor_row, bottom);
+        }
+        self.cursor_col = 0;
+    }
+
+    fn scroll_region_down(&mut self, top: usize, bottom: usize, lines: usize) {
+        if top >= bottom {
+            return;
+        }
+        self.invalidate_selection_rows(top..bottom);
+        for _ in 0..min(lines, bottom - top) {
+            self.grid.scroll_down(top, bottom);
+        }
+    }
+
+    pub fn clear_all(&mut self) {
+        self.invalidate_selection_rows(0..self.rows);
+        let fill = self.blank_cell();
+        for r in 0..self.rows {
+            self.grid[r].clear_range(0, self.cols, fill);
+        }
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+    }
+
+    pub fn viewport_contains_cursor(&self) -> bool {
+        if self.view_scroll != 0 {
+            return false;
+        }
+        true
+    }
+
+    pub fn selection_bounds(&self) -> Option<(Pos, Pos)> {
+        self.selection.as_ref().map(|s| s.normalized())
+    }
+
+    pub fn clamp_global_row(&self, row: usize) -> usize {
+        min(row, self.total_lines().saturating_sub(1))
+    }
+
+    pub fn ensure_cursor_visible(&mut self) {
+        self.view_scroll = 0;
+    }
+
+    pub fn home_cursor(&mut self) {
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+    }
+
+    pub fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        let bottom = bottom.min(self.rows);
+        if top < bottom {
+            self.scroll_top = top;
+            self.scroll_bottom = bottom;
+        }
+        self.cursor_row = 0;
+        self.cursor_col = 0;
+    }
+
+    pub fn save_cursor(&mut self) {
+        self.saved_cursor_row = self.cursor_row;
+        self.saved_cursor_col = self.cursor_col;
+    }
+
+    pub fn restore_cursor(&mut self) {
+        self.cursor_row = self.saved_cursor_row.min(self.rows.saturating_sub(1));
+        self.cursor_col = self.saved_cursor_col.min(self.cols.saturating_sub(1));
+    }
+
+    pub fn erase_chars(&mut self, count: usize) {
+        let fill = self.blank_cell();
+        self.invalidate_selection_rows(self.cursor_row..self.cursor_row + 1);
+        let (col, end) = (self.cursor_col, self.cursor_col.saturating_add(count).min(self.cols));
+        self.current_row_mut().clear_range(col, end, fill);
+    }
+
+    pub fn reverse_index(&mut self) {
+        if self.cursor_row == self.scroll_top {
+            // Scroll down within scroll region
+            let (top, bottom) = (self.scroll_top, self.scroll_bottom.min(self.rows));
+            self.scroll_region_down(top, bottom, 1);
+        } else if self.cursor_row > 0 {
+            self.cursor_row -= 1;
+        }
+    }
+
+    /// Switch to a blank alternate screen (DECSET 1049/1047/47). With
+    /// `save_cursor` (1049) the cursor is saved first.
+    pub fn enter_alt_screen(&mut self, save_cursor: bool) {
+        if self.alt_screen {
+            return;
+        }
+        if save_cursor {
+            self.save_cursor();
+        }
+        let blank = (0..self.rows).map(|_| Row::new(self.cols)).collect();
+        self.primary_screen = Some(self.grid.replace_screen(blank));
+        self.alt_screen = true;
+        self.alt_base = self.grid.scrollback_len();
+        // Selection and scroll position referred to the primary screen
+        self.selection = None;
+        self.view_scroll = 0;
+    }
+
+    /// Return to the primary screen, restoring the cursor for 1049.
+    pub fn exit_alt_screen(&mut self, restore_cursor: bool) {
+        if !self.alt_screen {
+            return;
+        }
+        // The full-screen program is done with its pointer
+        self.pointer_shape.clear();
+        if let Some(primary) = self.primary_screen.take() {
+            self.grid.replace_screen(primary);
+        }
+        self.grid.truncate_scrollback(self.alt_base);
+        self.alt_screen = false;
+        if restore_cursor {
+            self.restore_cursor();
+        }
+        self.selection = None;
+        self.view_scroll = 0;
+    }
+
+    pub fn next_line(&mut self) {
+        self.line_feed();
+        self.carriage_return();
+    }
+
+    /// Drop all scrollback (ED 3, Cmd+K). Every path that clears history
+    /// goes through here so the view, selection and `scrollback_cleared`
+    /// stay consistent.
+    pub fn clear_scrollback(&mut self) {
+        let cleared = self.grid.clear_scrollback();
+        self.shift_selection(cleared);
+        self.alt_base = 0;
+        self.view_scroll = 0;
+        self.scrollback_cleared = true;
+    }
+
+    /// `TERM` the shell was started with.
+    pub fn term_name(&self) -> &str {
+        &self.term_name
+    }
+
+    /// Advertise `name`: DA and XTGETTCAP replies follow it, and sequences
+    /// for features its terminfo entry lacks are ignored.
+    pub fn set_term_name(&mut self, name: &str) {
+        self.term_name = name.to_string();
+        self.features = Features::of(name);
+    }
+
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
+    /// Use `style` as the configured cursor style, and show it now.
+    pub fn set_default_cursor_style(&mut self, style: CursorStyle) {
+        self.default_cursor_style = style;
+        self.cursor_style = style;
+    }
+
+    /// DECSTR (CSI ! p): reset colors, cursor style and scroll region,
+    /// keeping screen contents.
+    pub fn soft_reset(&mut self) {
+        self.style = Style::default();
+        self.cursor_style = self.default_cursor_style;
+        self.pointer_shape.clear();
+        self.charsets = [Charset::Ascii; 2];
+        self.shift_out = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = self.rows;
+    }
+
+    /// Blank screen and scrollback, reset colors and home the cursor
+    /// (Cmd+Shift+K). Modes set by the running program are left alone.
+    pub fn clear_and_reset(&mut self) {
+        self.style = Style::default();
+        self.clear_scrollback();
+        self.clear_all();
+        self.selection = None;
+        self.images.clear();
+        self.links.clear();
+        self.link = 0;
+    }
+
+    /// Show image `id` at the cursor over `cols`×`rows` cells, scrolling as
+    /// needed; the cursor ends up after it on its last row. With `stay` the
+    /// cursor doesn't move and rows past the bottom are cut.
+    pub(crate) fn place_image(&mut self, id: u32, cols: usize, rows: usize, stay: bool) {
+        let top = min(self.cursor_row, self.rows - 1);
+        let col = min(self.cursor_col, self.cols - 1);
+        for r in 0..rows {
+            let screen_row = if stay {
+                top + r
+            } else {
+                if r > 0 {
+                    self.line_feed();
+                }
+                self.cursor_row
+            };
+            let Some(row) = self.grid.get_mut(screen_row) else {
+                break;
+            };
+            if row.images.len() < MAX_ROW_IMAGES {
+                row.images.push(ImageSlice { id, col, cols, row: r, rows });
+            }
+        }
+        if !stay {
+            self.cursor_col = min(col + cols, self.cols);
+        }
+    }
+
+    pub fn place_str(&mut self, s: &str) {
+        for ch in s.chars() {
+            self.put_char(ch);
+        }
+    }
+
+    pub fn normalized_selection(&self) -> Option<(Pos, Pos)> {
+        self.selection.as_ref().map(|s| s.normalized())
+    }
+
+    pub fn visible_global_row_for_view(&self, view_row: usize) -> usize {
+        self.visible_start_global_row() + self.clamp_view_row(view_row)
+    }
+
+    pub fn selection_non_empty(&self) -> bool {
+        matches!(self.selection_bounds(), Some((a, b)) if a != b)
+    }
+
+    pub fn max_view_scroll(&self) -> usize {
+        if self.alt_screen {
+            self.grid.scrollback_len() - self.alt_base
+        } else {
+            self.grid.scrollback_len()
+        }
+    }
+
+    pub fn set_selection_focus_from_view(&mut self, view_row: usize, col: usize) {
+        let pos = self.pos_for_view(self.clamp_view_row(view_row), self.clamp_col(col));
+        self.update_selection(pos);
+    }
+
+    pub fn start_selection_from_view(&mut self, view_row: usize, col: usize) {
+        let pos = self.pos_for_view(self.clamp_view_row(view_row), self.clamp_col(col));
+        self.start_selection(pos);
+    }
+
+    /// Select the word at (view_row, col)
+    pub fn select_word_at_view(&mut self, view_row: usize, col: usize) {
+        let global_row = self.visible_start_global_row() + view_row;
+        if let Some(row) = self.line_at_global(global_row) {
+            let mut cells = row.cells.clone();
+            cells.resize(self.cols, Cell::default());
+            let col = col.min(cells.len().saturating_sub(1));
+            let ch = cells[col].ch;
+            // On blanks, the run of blanks (handy for checking alignment)
+            let is_word_char: &dyn Fn(char) -> bool = if ch == ' ' { &|c: char| c == ' ' } else { &is_word_char };
+            if !is_word_char(ch) {
+                // Single char selection for non-word chars
+                let pos = Pos { row: global_row, col };
+                self.selection = Some(Selection { anchor: pos, focus: pos });
+                return;
+            }
+            let mut start = col;
+            while start > 0 && is_word_char(cells[start - 1].ch) {
+                start -= 1;
+            }
+            let mut end = col;
+            while end + 1 < cells.len() && is_word_char(cells[end + 1].ch) {
+                end += 1;
+            }
+            self.selection = Some(Selection {
+                anchor: Pos { row: global_row, col: start },
+                focus: Pos { row: global_row, col: end },
+            });
+        }
+    }
+
+    /// Select the logical line at view_row, including its wrapped rows, up
+    /// to the last non-blank cell.
+    pub fn select_line_at_view(&mut self, view_row: usize) {
+        let global_row = self.visible_start_global_row() + view_row;
+        let (anchor, focus) = self.logical_line_range(global_row);
+        self.selection = Some(Selection { anchor, focus });
+    }
+
+    /// The logical line containing `row`, from its first cell to its last
+    /// non-blank one.
+    fn logical_line_range(&self, row: usize) -> (Pos, Pos) {
+        let (first, last) = self.logical_line_bounds(row);
+        let end = self
+            .line_at_global(last)
+            .and_then(|row| row.cells.iter().rposition(|c| c.ch != ' ' || c.wide_cont))
+            .unwrap_or(0);
+        (Pos { row: first, col: 0 }, Pos { row: last, col: end })
+    }
+
+    /// Grow the selection one step, to the smallest of: the word, the run of
+    /// non-blanks (stopping at quotes and brackets), the text inside the quotes or brackets around it, the same
+    /// with the delimiters, the logical line, the command output (OSC 133).
+    /// Without a selection, starts at the cursor.
+    pub fn expand_selection(&mut self) {
+        let (a, b) = match &self.selection {
+            Some(sel) => sel.normalized(),
+            None => (self.cursor_global_pos(), self.cursor_global_pos()),
+        };
+        let cols = self.cols.max(1);
+        let (text, first) = self.logical_line_text(a.row);
+        let text: Vec<char> = text.chars().collect();
+        let (s, e) = ((a.row - first) * cols + a.col, (b.row - first) * cols + b.col);
+        let mut spans = Vec::new();
+        if e < text.len() {
+            spans.extend(run_around(&text, s, e, is_word_char));
+            spans.extend(run_around(&text, s, e, |c| c != ' ' && !PAIRS.iter().any(|p| c == p.0 || c == p.1)));
+            spans.extend(enclosing_pair(&text, s, e));
+        }
+        let at = |i: usize| Pos { row: first + i / cols, col: i % cols };
+        let mut candidates: Vec<(Pos, Pos)> = spans.into_iter().map(|(s, e)| (at(s), at(e))).collect();
+        candidates.push(self.logical_line_range(a.row));
+        candidates.extend(self.command_output_at(a));
+        let linear = |p: Pos| p.row * cols + p.col;
+        candidates.sort_by_key(|&(s, e)| linear(e) - linear(s));
+        let grows = |&&(s, e): &&(Pos, Pos)| linear(s) <= linear(a) && linear(e) >= linear(b) && (s, e) != (a, b);
+        if let Some(&(anchor, focus)) = candidates.iter().find(grows) {
+            self.selection = Some(Selection { anchor, focus });
+        }
+    }
+
+    /// Select all content (scrollback + screen)
+    pub fn select_all(&mut self) {
+        let last_row = self.total_lines().saturating_sub(1);
+        // On the alternate screen only the app's grid is selectable
+        let first_row = if self.alt_screen { self.alt_base } else { 0 };
+        self.selection = Some(Selection {
+            anchor: Pos { row: first_row, col: 0 },
+            focus: Pos { row: last_row, col: self.cols.saturating_sub(1) },
+        });
+    }
+
+    /// Move the selection's focus by `rows` and `cols` (Shift+arrows,
+    /// Shift+PageUp/PageDown), wrapping across row ends, and scroll the
+    /// view so the focus stays visible.
+    pub fn extend_selection(&mut self, rows: isize, cols: isize) {
+        let first_row = if self.alt_screen { self.alt_base } else { 0 };
+        let last_row = self.total_lines().saturating_sub(1);
+        let width = self.cols as isize;
+        let Some(sel) = &mut self.selection else {
+            return;
+        };
+        let cell = (sel.focus.row as isize + rows) * width + sel.focus.col as isize + cols;
+        let cell = cell.clamp(first_row as isize * width, (last_row as isize + 1) * width - 1);
+        sel.focus = Pos { row: (cell / width) as usize, col: (cell % width) as usize };
+        let row = sel.focus.row;
+        self.scroll_view_to_show(row);
+    }
+
+    /// Scroll the view as little as possible so global `row` is visible.
+    pub fn scroll_view_to_show(&mut self, row: usize) {
+        let (top, bottom) = self.visible_range();
+        let scrollback = self.grid.scrollback_len();
+        if row < top {
+            self.view_scroll = (scrollback - row).min(self.max_view_scroll());
+        } else if row > bottom {
+            self.view_scroll = (scrollback + self.rows - 1).saturating_sub(row);
+        }
+    }
+
+    pub fn selection_text_or_empty(&self) -> String {
+        self.selection_text().unwrap_or_default()
+    }
+
+    pub fn clamp_position(&self, mut pos: Pos) -> Pos {
+        pos.row = self.clamp_global_row(pos.row);
+        pos.col = self.clamp_col(pos.col);
+        pos
+    }
+
+    pub fn last_visible_global_row(&self) -> usize {
+        self.visible_start_global_row() + self.rows.saturating_sub(1)
+    }
+
+    pub fn visible_range(&self) -> (usize, usize) {
+        let start = self.visible_start_global_row();
+        (start, start + self.rows.saturating_sub(1))
+    }
+
+    pub fn scroll_view_to_bottom(&mut self) {
+        self.view_scroll = 0;
+    }
+
+    /// Scroll the view so global `row` is at the top, as far as possible.
+    pub fn scroll_view_to_row(&mut self, row: usize) {
+        if self.alt_screen {
+            return;
+        }
+        self.view_scroll = self.grid.scrollback_len().saturating_sub(row);
+    }
+
+    /// Record an OSC 133 mark at the cursor. Ignored on the alternate screen.
+    pub fn add_mark(&mut self, kind: MarkKind) {
+        if self.alt_screen {
+            return;
+        }
+        let col = min(self.cursor_col, self.cols);
+        if let Some(row) = self.grid.get_mut(self.cursor_row) {
+            row.marks.push(Mark { kind, col });
+        }
+    }
+
+    /// All marks in scrollback and screen, in order.
+    fn all_marks(&self) -> Vec<(Pos, MarkKind)> {
+        (0..self.total_lines())
+            .filter_map(|r| Some((r, self.line_at_global(r)?)))
+            .flat_map(|(r, line)| line.marks.iter().map(move |m| (Pos { row: r, col: m.col }, m.kind)))
+            .collect()
+    }
+
+    /// Inclusive range of the most recent command's output: from its `C`
+    /// mark up to the following `D` or prompt mark, or the cursor if it is
+    /// still running.
+    pub fn last_command_output(&self) -> Option<(Pos, Pos)> {
+        let marks = self.all_marks();
+        let i = marks.iter().rposition(|m| m.1 == MarkKind::Output)?;
+        let end = marks[i + 1..]
+            .iter()
+            .find(|m| matches!(m.1, MarkKind::Finished | MarkKind::Prompt))
+            .map_or(self.cursor_global_pos(), |m| m.0);
+        self.inclusive_range(marks[i].0, end)
+    }
+
+    /// Inclusive range of the output containing `pos`: from the `C` mark
+    /// at or before it up to the next `D` or prompt mark, or the cursor.
+    fn command_output_at(&self, pos: Pos) -> Option<(Pos, Pos)> {
+        let marks = self.all_marks();
+        let i = marks.iter().rposition(|m| m.1 == MarkKind::Output && (m.0.row, m.0.col) <= (pos.row, pos.col))?;
+        let end = marks[i + 1..]
+            .iter()
+            .find(|m| matches!(m.1, MarkKind::Finished | MarkKind::Prompt))
+            .map_or(self.cursor_global_pos(), |m| m.0);
+        self.inclusive_range(marks[i].0, end)
+    }
+
+    /// Inclusive range of the most recent command line, between its `B`
+    /// and `C` marks.
+    pub fn last_command_line(&self) -> Option<(Pos, Pos)> {
+        let marks = self.all_marks();
+        let i = marks.iter().rposition(|m| m.1 == MarkKind::Output)?;
+        let start = marks[..i].iter().rev().find(|m| m.1 == MarkKind::Command)?;
+        self.inclusive_range(start.0, marks[i].0)
+    }
+
+    /// `start..end` as an inclusive range; `None` if empty.
+    fn inclusive_range(&self, start: Pos, end: Pos) -> Option<(Pos, Pos)> {
+        if (end.row, end.col) <= (start.row, start.col) {
+            return None;
+        }
+        let last = if end.col == 0 {
+            Pos { row: end.row - 1, col: self.cols.saturating_sub(1) }
+        } else {
+            Pos { row: end.row, col: end.col - 1 }
+        };
+        Some((start, last))
+    }
+
+    /// Nearest global row above `row` with a prompt mark.
+    pub fn prev_prompt(&self, row: usize) -> Option<usize> {
+        (0..row.min(self.total_lines()))
+            .rev()
+            .find(|&r| self.line_at_global(r).is_some_and(|l| l.has_mark(MarkKind::Prompt)))
+    }
+
+    /// Nearest global row below `row` with a prompt mark.
+    pub fn next_prompt(&self, row: usize) -> Option<usize> {
+        (row + 1..self.total_lines())
+            .find(|&r| self.line_at_global(r).is_some_and(|l| l.has_mark(MarkKind::Prompt)))
+    }
+
+    pub fn selection_contains_row(&self, row: usize) -> bool {
+        if let Some((a, b)) = self.normalized_selection() {
+            row >= a.row && row <= b.row
+        } else {
+            false
+        }
+    }
+
+    pub fn cursor_screen_pos(&self) -> (usize, usize) {
+        (
+            self.cursor_row,
+            self.cursor_col.min(self.cols.saturating_sub(1)),
+        )
+    }
+
+    pub fn cursor_visible_view_row(&self) -> Option<usize> {
+        if self.view_scroll != 0 {
+            return None;
+        }
+        Some(self.cursor_row)
+    }
+
+    pub fn ensure_nonzero_size(&mut self) {
+        if self.cols == 0 || self.rows == 0 {
+            self.cols = max(self.cols, 1);
+            self.rows = max(self.rows, 1);
+        }
+    }
+}
+
+/// Characters a double-click keeps together as a word.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// `s..=e` widened to the run of chars matching `keep`, if they all do.
+fn run_around(text: &[char], s: usize, e: usize, keep: impl Fn(char) -> bool) -> Option<(usize, usize)> {
+    if !text[s..=e].iter().all(|&c| keep(c)) {
+        return None;
+    }
+    let start = text[..s].iter().rposition(|&c| !keep(c)).map_or(0, |i| i + 1);
+    let end = text[e + 1..].iter().position(|&c| !keep(c)).map_or(text.len() - 1, |i| e + i);
+    Some((start, end))
+}
+
+/// Delimiters `expand_selection` selects inside of.
+const PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>'), ('"', '"'), ('\'', '\''), ('`', '`')];
+
+/// The innermost quotes or brackets around `s..=e`: the text inside them,
+/// then with them.
+fn enclosing_pair(text: &[char], s: usize, e: usize) -> Vec<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    for &(open, close) in PAIRS {
+        let found = if open == close {
+            let o = text[..s].iter().rposition(|&c| c == open);
+            let c = text[e + 1..].iter().position(|&c| c == close).map(|i| e + 1 + i);
+            o.zip(c)
+        } else {
+            let mut depth = 0;
+            let o = (0..s).rev().find(|&i| {
+                if text[i] == close {
+                    depth += 1;
+                } else if text[i] == open {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            });
+            depth = 0;
+            let c = (e + 1..text.len()).find(|&i| {
+                if text[i] == open {
+                    depth += 1;
+                } else if text[i] == close {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            });
+            o.zip(c)
+        };
+        if let Some((o, c)) = found {
+            if best.is_none_or(|(bo, _)| o > bo) {
+                best = Some((o, c));
+            }
+        }
+    }
+    let Some((o, c)) = best else {
+        return Vec::new();
+    };
+    let mut spans = vec![(o, c)];
+    if o + 1 < c {
+        spans.push((o + 1, c - 1));
+    }
+    spans
+}
@@ -0,0 +1,119 @@
+//! What moterm claims to be for a given `TERM`, so device attributes,
+//! XTGETTCAP answers and the features it honours match the terminfo entry
+//! applications look up.
+
+pub const DEFAULT_TERM: &str = "xterm-256color";
+
+/// Features honoured for a `TERM`. Sequences for ones its terminfo entry
+/// doesn't have are ignored, so claiming less capability disables them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Features {
+    /// As [`colors`]: 0 ignores color SGR, 8 takes the 16 ANSI colors and
+    /// 256 or more also the indexed and RGB forms of SGR 38/48
+    pub colors: u32,
+    /// Mouse reporting (DECSET 1000/1002/1003/1006)
+    pub mouse: bool,
+    /// Bracketed paste (DECSET 2004)
+    pub bracketed_paste: bool,
+    /// Alternate screen (DECSET 47/1047/1049)
+    pub alt_screen: bool,
+}
+
+impl Features {
+    pub fn of(term: &str) -> Self {
+        // Real VT terminals predate all of the xterm extensions
+        let xterm = !term.starts_with("vt");
+        Self {
+            colors: colors(term),
+            mouse: xterm,
+            bracketed_paste: xterm,
+            alt_screen: xterm,
+        }
+    }
+}
+
+/// Color count of the terminfo entry (`colors`/`Co`).
+pub fn colors(term: &str) -> u32 {
+    if term.ends_with("-direct") {
+        1 << 24
+    } else if term.contains("256color") {
+        256
+    } else if term.starts_with("vt") {
+        0
+    } else {
+        8
+    }
+}
+
+/// Whether to advertise 24-bit color (`COLORTERM`, `RGB`).
+pub fn truecolor(term: &str) -> bool {
+    colors(term) >= 256
+}
+
+/// Primary device attributes reply (CSI c).
+pub fn primary_da(term: &str) -> &'static [u8] {
+    match term {
+        "vt100" => b"\x1b[?1;2c", // VT100 with advanced video
+        "vt102" => b"\x1b[?6c",
+        // VT220 with ANSI color
+        _ => b"\x1b[?62;22c",
+    }
+}
+
+/// Secondary device attributes reply (CSI > c); real VT1xx terminals
+/// don't answer it.
+pub fn secondary_da(term: &str) -> Option<String> {
+    if term.starts_with("vt1") {
+        return None;
+    }
+    let version = env!("CARGO_PKG_VERSION")
+        .split('.')
+        .take(3)
+        .fold(0, |v, n| v * 100 + n.parse::<u32>().unwrap_or(0));
+    Some(format!("\x1b[>0;{version};1c"))
+}
+
+/// XTVERSION reply (CSI > 0 q): terminal name and version.
+pub fn xtversion() -> String {
+    format!("\x1bP>|moterm {}\x1b\\", env!("CARGO_PKG_VERSION"))
+}
+
+/// XTGETTCAP value for capability `name`, if the entry has it.
+pub fn capability(term: &str, name: &str) -> Option<String> {
+    match name {
+        "TN" | "name" => Some(term.to_string()),
+        "Co" | "colors" => Some(colors(term).to_string()).filter(|_| colors(term) > 0),
+        "RGB" => truecolor(term).then(|| "8/8/8".to_string()),
+        _ => None,
+    }
+}
+
+/// Replies to an XTGETTCAP request (DCS + q ST) whose payload is hex-encoded
+/// capability names separated by `;`.
+pub fn xtgettcap_reply(term: &str, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for hex_name in payload.split(|&b| b == b';') {
+        let hex_name = String::from_utf8_lossy(hex_name);
+        let value = decode_hex(&hex_name).and_then(|name| capability(term, &name));
+        match value {
+            Some(v) => out.extend(format!("\x1bP1+r{hex_name}={}\x1b\\", encode_hex(&v)).bytes()),
+            None => out.extend(format!("\x1bP0+r{hex_name}\x1b\\").bytes()),
+        }
+    }
+    out
+}
+
+fn decode_hex(s: &str) -> Option<String> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn encode_hex(s: &str) -> String {
+    s.bytes().map(|b| format!("{b:02X}")).collect()
+}
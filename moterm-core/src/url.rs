@@ -0,0 +1,504 @@
+use std::path::{Path, PathBuf};
+
+/// What kind of clickable thing was detected in a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    /// http(s):// URL, or a bare host (`localhost:3000`, `www.x.com`)
+    /// with `http://` prepended to the target
+    Url,
+    /// Absolute, `~/` or `./`-relative file path, or a `file://` URL.
+    /// `src/foo.rs:12:7` style references carry the line/column.
+    Path {
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// Email address (bare or `mailto:`); target is the address only
+    Email,
+}
+
+/// A detected link: columns [start, end) plus the text to open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub start: usize,
+    pub end: usize,
+    pub kind: LinkKind,
+    pub target: String,
+}
+
+/// Simple pattern detector — finds URLs and file paths in terminal text.
+pub fn detect_links(line: &str) -> Vec<Link> {
+    let mut results = Vec::new();
+    let chars: Vec<char> = line.chars().collect();
+    let len = chars.len();
+    let mut i = 0;
+
+    while i < len {
+        let remaining: String = chars[i..].iter().collect();
+        let at_boundary = i == 0 || is_token_boundary(chars[i - 1]);
+
+        if remaining.starts_with("https://") || remaining.starts_with("http://") {
+            let end = token_end(&chars, i);
+            if end > i + 8 {
+                // At least "http://x"
+                results.push(Link {
+                    start: i,
+                    end,
+                    kind: LinkKind::Url,
+                    target: chars[i..end].iter().collect(),
+                });
+            }
+            i = end.max(i + 1);
+        } else if remaining.starts_with("file://") {
+            let end = token_end(&chars, i);
+            let url: String = chars[i..end].iter().collect();
+            if let Some(path) = file_url_to_path(&url) {
+                results.push(Link {
+                    start: i,
+                    end,
+                    kind: LinkKind::Path { line: None, column: None },
+                    target: path,
+                });
+            }
+            i = end.max(i + 1);
+        } else if remaining.starts_with("mailto:") {
+            let end = token_end(&chars, i);
+            let addr: String = chars[i + 7..end].iter().collect();
+            if email_len(&addr.chars().collect::<Vec<_>>(), 0).is_some() {
+                results.push(Link {
+                    start: i,
+                    end,
+                    kind: LinkKind::Email,
+                    target: addr,
+                });
+            }
+            i = end.max(i + 1);
+        } else if let Some(n) = at_boundary.then(|| email_len(&chars, i)).flatten() {
+            results.push(Link {
+                start: i,
+                end: i + n,
+                kind: LinkKind::Email,
+                target: chars[i..i + n].iter().collect(),
+            });
+            i += n;
+        } else if let Some(n) = at_boundary.then(|| bare_host_len(&chars, i)).flatten() {
+            let text: String = chars[i..i + n].iter().collect();
+            results.push(Link {
+                start: i,
+                end: i + n,
+                kind: LinkKind::Url,
+                target: format!("http://{text}"),
+            });
+            i += n;
+        } else if at_boundary && looks_like_path_start(&remaining) {
+            let end = path_end(&chars, i);
+            // Require something after the leading "/", "~/", "./" or "../"
+            let prefix = path_prefix_len(&remaining);
+            if end > i + prefix {
+                let text: String = chars[i..end].iter().collect();
+                let (path, line, column) = split_line_col(&text);
+                results.push(Link {
+                    start: i,
+                    end,
+                    kind: LinkKind::Path { line, column },
+                    target: path.to_string(),
+                });
+            }
+            i = end.max(i + 1);
+        } else if let Some(end) = at_boundary.then(|| file_ref_end(&chars, i)).flatten() {
+            // Bare relative reference like `src/foo.rs:123:7` (compiler/grep output)
+            let text: String = chars[i..end].iter().collect();
+            let (path, line, column) = split_line_col(&text);
+            results.push(Link {
+                start: i,
+                end,
+                kind: LinkKind::Path { line, column },
+                target: path.to_string(),
+            });
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    results
+}
+
+/// Find the link covering `col`, if any.
+pub fn link_at(line: &str, col: usize) -> Option<Link> {
+    detect_links(line)
+        .into_iter()
+        .find(|l| col >= l.start && col < l.end)
+}
+
+fn is_token_boundary(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '"' | '\'' | '(' | '[' | '<' | '=' | ':' | '`')
+}
+
+fn looks_like_path_start(s: &str) -> bool {
+    s.starts_with('/') || s.starts_with("~/") || s.starts_with("./") || s.starts_with("../")
+}
+
+fn path_prefix_len(s: &str) -> usize {
+    if s.starts_with("../") {
+        3
+    } else if s.starts_with("~/") || s.starts_with("./") {
+        2
+    } else {
+        1
+    }
+}
+
+/// Advance to end of a URL (stop at whitespace or certain delimiters),
+/// then strip trailing punctuation that's likely not part of it.
+fn token_end(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() {
+        let ch = chars[end];
+        if ch.is_whitespace() || ch == '"' || ch == '\'' || ch == '>' || ch == '<' {
+            break;
+        }
+        end += 1;
+    }
+    trim_trailing_punct(chars, start, end)
+}
+
+/// Paths are stricter than URLs: stop at anything that can't reasonably
+/// appear in a file name printed by a shell tool.
+fn path_end(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() {
+        let ch = chars[end];
+        if ch.is_whitespace() || matches!(ch, '"' | '\'' | '`' | '<' | '>' | '|' | '(' | ')' | '[' | ']' | '{' | '}') {
+            break;
+        }
+        end += 1;
+    }
+    trim_trailing_punct(chars, start, end)
+}
+
+fn trim_trailing_punct(chars: &[char], start: usize, mut end: usize) -> usize {
+    while end > start {
+        let ch = chars[end - 1];
+        if matches!(ch, '.' | ',' | ')' | ']' | ';' | ':' | '!' | '?') {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// Length of an email address starting at `start`: `local@domain.tld`,
+/// where the TLD is at least two letters.
+fn email_len(chars: &[char], start: usize) -> Option<usize> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-');
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-');
+    let mut i = start;
+    while i < chars.len() && is_local(chars[i]) {
+        i += 1;
+    }
+    if i == start || chars.get(i) != Some(&'@') {
+        return None;
+    }
+    let domain_start = i + 1;
+    let mut end = domain_start;
+    while end < chars.len() && is_domain(chars[end]) {
+        end += 1;
+    }
+    // Trailing dots/dashes belong to the sentence, not the address
+    while end > domain_start && matches!(chars[end - 1], '.' | '-') {
+        end -= 1;
+    }
+    let domain: String = chars[domain_start..end].iter().collect();
+    let (host, tld) = domain.rsplit_once('.')?;
+    if host.is_empty() || tld.len() < 2 || !tld.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(end - start)
+}
+
+/// Length of a scheme-less URL starting at `start`: `localhost:PORT`,
+/// an IPv4 address, or a `www.` host, each with optional port and path.
+/// Dev servers almost never print the scheme.
+fn bare_host_len(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '-')) {
+        i += 1;
+    }
+    while i > start && matches!(chars[i - 1], '.' | '-') {
+        i -= 1;
+    }
+    let host: String = chars[start..i].iter().collect();
+    let has_port = chars.get(i) == Some(&':') && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit());
+
+    let valid = if host == "localhost" {
+        has_port
+    } else if is_ipv4(&host) {
+        true
+    } else if let Some(rest) = host.strip_prefix("www.") {
+        rest.rsplit_once('.')
+            .is_some_and(|(name, tld)| !name.is_empty() && tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+    } else {
+        false
+    };
+    if !valid {
+        return None;
+    }
+
+    if has_port {
+        i += 1;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    if chars.get(i) == Some(&'/') {
+        i = token_end(chars, i);
+    }
+    Some(trim_trailing_punct(chars, start, i) - start)
+}
+
+fn is_ipv4(host: &str) -> bool {
+    let parts: Vec<&str> = host.split('.').collect();
+    parts.len() == 4
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.len() <= 3 && p.parse::<u8>().is_ok())
+}
+
+/// Split a trailing `:line` or `:line:col` off a path.
+fn split_line_col(text: &str) -> (&str, Option<usize>, Option<usize>) {
+    let mut parts = text.rsplitn(3, ':');
+    let last = parts.next().unwrap_or_default();
+    let mid = parts.next();
+    let first = parts.next();
+    match (first, mid, last.parse::<usize>().ok()) {
+        (Some(path), Some(line), Some(col)) if !path.is_empty() => match line.parse::<usize>() {
+            Ok(line) => (path, Some(line), Some(col)),
+            // `a:b:12` — only the final number is a line
+            Err(_) => (&text[..text.len() - last.len() - 1], Some(col), None),
+        },
+        (None, Some(path), Some(line)) if !path.is_empty() => (path, Some(line), None),
+        _ => (text, None, None),
+    }
+}
+
+/// Match a relative `path:line[:col]` token starting at `start`. The path
+/// part must look like a file (contain `/` or an alphabetic extension) so
+/// clock times and port numbers aren't picked up.
+fn file_ref_end(chars: &[char], start: usize) -> Option<usize> {
+    let is_path_char = |c: char| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+' | '@');
+    let mut path_end = start;
+    while path_end < chars.len() && is_path_char(chars[path_end]) {
+        path_end += 1;
+    }
+    if path_end == start || path_end >= chars.len() || chars[path_end] != ':' {
+        return None;
+    }
+    let path: String = chars[start..path_end].iter().collect();
+    let has_ext = path
+        .rsplit_once('.')
+        .is_some_and(|(stem, ext)| !stem.is_empty() && ext.starts_with(|c: char| c.is_ascii_alphabetic()));
+    if !path.contains('/') && !has_ext {
+        return None;
+    }
+
+    // One or two `:digits` groups
+    let mut end = path_end;
+    for _ in 0..2 {
+        if end < chars.len() && chars[end] == ':' && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit()) {
+            end += 1;
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    if end == path_end {
+        return None;
+    }
+    Some(end)
+}
+
+/// `file:///Users/me/a%20b.txt` → `/Users/me/a b.txt`. The host part is ignored.
+pub fn file_url_to_path(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    Some(percent_decode(path))
+}
+
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(v) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Percent-encode text for use as a URL query component.
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Fill a search-engine template (`https://duckduckgo.com/?q={query}`).
+pub fn search_url(template: &str, query: &str) -> String {
+    template.replace("{query}", &percent_encode(query.trim()))
+}
+
+/// Resolve a detected path: expand `~/`, and join relative paths onto the
+/// shell's working directory (from OSC 7) when known.
+pub fn resolve_path(target: &str, cwd: Option<&str>) -> PathBuf {
+    if let Some(rest) = target.strip_prefix("~/") {
+        let home = std::env::var("HOME").unwrap_or_default();
+        return Path::new(&home).join(rest);
+    }
+    let p = Path::new(target);
+    if p.is_absolute() {
+        return p.to_path_buf();
+    }
+    match cwd {
+        Some(dir) => Path::new(dir).join(p),
+        None => p.to_path_buf(),
+    }
+}
+
+/// Context needed to open a link.
+#[derive(Clone, Copy)]
+pub struct OpenContext<'a> {
+    /// Shell working directory (OSC 7), for relative paths
+    pub cwd: Option<&'a str>,
+    /// Editor command template for `file:line:col` references,
+    /// e.g. `code -g {file}:{line}:{col}` or `nvim +{line} {file}`
+    pub editor: Option<&'a str>,
+    /// Show the file in Finder instead of opening it
+    pub reveal: bool,
+    /// Schemes opened without asking (e.g. http, https, mailto, file)
+    pub allowed_schemes: &'a [String],
+    /// Asked before opening anything outside the allowlist
+    pub confirm: fn(&str) -> bool,
+}
+
+impl OpenContext<'_> {
+    /// Terminal output is untrusted: only allowlisted schemes open
+    /// directly, everything else needs confirmation.
+    pub fn permits(&self, url: &str) -> bool {
+        let allowed = scheme_of(url)
+            .is_some_and(|scheme| self.allowed_schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme)));
+        allowed || (self.confirm)(url)
+    }
+}
+
+/// `https://x` → `https`. None if the text doesn't start with a scheme.
+pub fn scheme_of(url: &str) -> Option<&str> {
+    let (scheme, _) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// Open a URL if its scheme is allowed (or the user confirms).
+pub fn open_url_checked(url: &str, ctx: &OpenContext) {
+    if ctx.permits(url) {
+        open_url(url);
+    }
+}
+
+/// Open a detected link. Paths that don't exist are ignored so stray
+/// slashes in ordinary text don't launch anything.
+pub fn open_link(link: &Link, ctx: &OpenContext) {
+    match link.kind {
+        LinkKind::Url => open_url_checked(&link.target, ctx),
+        LinkKind::Path { line, column } => {
+            let path = resolve_path(&link.target, ctx.cwd);
+            if !path.exists() || !ctx.permits(&format!("file://{}", path.display())) {
+                return;
+            }
+            match (ctx.editor, line) {
+                (Some(template), Some(line)) if !ctx.reveal => {
+                    open_in_editor(template, &path, line, column.unwrap_or(1));
+                }
+                _ => open_path(&path, ctx.reveal),
+            }
+        }
+        LinkKind::Email => open_url_checked(&format!("mailto:{}", link.target), ctx),
+    }
+}
+
+/// Expand an editor template into argv. The template is split on
+/// whitespace *before* substitution so file names with spaces stay one
+/// argument and never reach a shell.
+pub fn editor_command(template: &str, file: &Path, line: usize, col: usize) -> Vec<String> {
+    let file = file.to_string_lossy();
+    template
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{file}", &file)
+                .replace("{line}", &line.to_string())
+                .replace("{col}", &col.to_string())
+        })
+        .collect()
+}
+
+fn open_in_editor(template: &str, file: &Path, line: usize, col: usize) {
+    let argv = editor_command(template, file, line, col);
+    if let Some((prog, args)) = argv.split_first() {
+        let _ = std::process::Command::new(prog).args(args).spawn();
+    }
+}
+
+pub fn open_url(url: &str) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = std::process::Command::new("open")
+            .arg(url)
+            .spawn();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = std::process::Command::new("xdg-open")
+            .arg(url)
+            .spawn();
+    }
+}
+
+pub fn open_path(path: &Path, reveal: bool) {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = std::process::Command::new("open");
+        if reveal {
+            cmd.arg("-R");
+        }
+        let _ = cmd.arg(path).spawn();
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let target = if reveal {
+            path.parent().unwrap_or(path)
+        } else {
+            path
+        };
+        let _ = std::process::Command::new("xdg-open")
+            .arg(target)
+            .spawn();
+    }
+}
@@ -0,0 +1,129 @@
+//! An embeddable terminal pane: grid, parser and input encoding in one
+//! value, for Rust GUI apps that want a terminal without moterm's window.
+//!
+//! The embedding app owns the PTY and the drawing surface. It passes PTY
+//! output to [`MotermView::feed`], forwards keys, text, pastes and mouse
+//! events, and draws [`MotermView::terminal`] however it likes. Everything
+//! the view needs back from the app goes through [`Host`].
+
+use crate::input::{key_bytes, text_bytes, Key, Modifiers};
+use crate::mouse;
+use crate::notify::{self, Notification};
+use crate::terminal::Terminal;
+use crate::vte_handler::VteHandler;
+
+/// What the embedding app provides to a [`MotermView`].
+pub trait Host {
+    /// Write to the PTY: typed input, pastes, mouse reports and replies to
+    /// the program's queries.
+    fn write(&mut self, bytes: &[u8]);
+    /// The grid changed size; pass it on to the PTY (`TIOCSWINSZ`).
+    fn resize(&mut self, cols: u16, rows: u16);
+    /// The program set the title (OSC 0/2).
+    fn set_title(&mut self, _title: &str) {}
+    /// The program rang the bell.
+    fn bell(&mut self) {}
+    /// The program asked for a desktop notification (OSC 9/99). Answer
+    /// clicks with [`MotermView::notification_activated`].
+    fn notify(&mut self, _notification: &Notification) {}
+}
+
+pub struct MotermView {
+    term: Terminal,
+    parser: vte::Parser,
+}
+
+impl MotermView {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            term: Terminal::new(cols, rows),
+            parser: vte::Parser::new(),
+        }
+    }
+
+    /// State to draw: rows via [`Terminal::visible_line`], cursor, selection.
+    pub fn terminal(&self) -> &Terminal {
+        &self.term
+    }
+
+    /// For settings and selection/scroll control.
+    pub fn terminal_mut(&mut self) -> &mut Terminal {
+        &mut self.term
+    }
+
+    /// Apply PTY output, then send any replies and notifications to `host`.
+    pub fn feed(&mut self, data: &[u8], host: &mut impl Host) {
+        let mut performer = VteHandler::new(&mut self.term);
+        for &b in data {
+            performer.advance(&mut self.parser, b);
+        }
+        if !self.term.reply_buf.is_empty() {
+            host.write(&std::mem::take(&mut self.term.reply_buf));
+        }
+        if std::mem::take(&mut self.term.bell) {
+            host.bell();
+        }
+        if std::mem::take(&mut self.term.title_changed) {
+            host.set_title(&self.term.title);
+        }
+        for n in std::mem::take(&mut self.term.notifications) {
+            host.notify(&n);
+        }
+    }
+
+    /// The user clicked a notification (`button` 1-based, `None` for its
+    /// body); reports it if the program asked for that.
+    pub fn notification_activated(&mut self, n: &Notification, button: Option<usize>, host: &mut impl Host) {
+        if n.report {
+            host.write(&notify::activation_report(&n.id, button));
+        }
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize, host: &mut impl Host) {
+        self.term.resize(cols, rows);
+        host.resize(self.term.cols() as u16, self.term.rows() as u16);
+    }
+
+    /// Send a special key. Returns false if it sends nothing.
+    pub fn key(&mut self, key: Key, mods: Modifiers, host: &mut impl Host) -> bool {
+        self.send(key_bytes(key, mods), host)
+    }
+
+    /// Send typed text (not pastes).
+    pub fn text(&mut self, text: &str, mods: Modifiers, host: &mut impl Host) {
+        for ch in text.chars() {
+            self.send(text_bytes(ch, mods), host);
+        }
+    }
+
+    /// Send a paste, bracketed if the program enabled mode 2004.
+    pub fn paste(&mut self, text: &str, host: &mut impl Host) {
+        let bytes = if self.term.bracketed_paste {
+            format!("\x1b[200~{text}\x1b[201~").into_bytes()
+        } else {
+            text.as_bytes().to_vec()
+        };
+        self.send(Some(bytes), host);
+    }
+
+    /// Report a mouse button or wheel event (a `mouse::BUTTON_*` value) at
+    /// a visible cell. Returns false when the program isn't tracking the
+    /// mouse, so the app can select text instead.
+    pub fn mouse(&mut self, button: u8, row: usize, col: usize, pressed: bool, mods: Modifiers, host: &mut impl Host) -> bool {
+        if self.term.mouse_mode == 0 {
+            return false;
+        }
+        let btn = button | mouse::modifier_bits(mods.shift, mods.alt, mods.ctrl);
+        host.write(&mouse::encode(btn, col, row, pressed, self.term.mouse_sgr));
+        true
+    }
+
+    fn send(&mut self, bytes: Option<Vec<u8>>, host: &mut impl Host) -> bool {
+        let Some(bytes) = bytes else {
+            return false;
+        };
+        self.term.scroll_view_to_bottom();
+        host.write(&bytes);
+        true
+    }
+}
@@ -0,0 +1,370 @@
+use vte::{Params, Perform};
+
+use crate::graphics::{self, Sequence};
+use crate::notify::{self, Kitty, Notification};
+use crate::terminal::{Charset, MarkKind, Terminal};
+use crate::terminfo;
+
+/// Longest XTGETTCAP payload kept; the rest is dropped.
+const MAX_DCS_LEN: usize = 1024;
+
+/// `vte::Perform` implementation that applies escape sequences to a [`Terminal`].
+pub struct VteHandler<'a> {
+    pub term: &'a mut Terminal,
+}
+
+impl<'a> VteHandler<'a> {
+    pub fn new(term: &'a mut Terminal) -> Self {
+        Self { term }
+    }
+
+    /// Feed one byte of PTY output to `parser`. Image sequences, which vte
+    /// drops (APC) or cuts at 1 KiB (OSC), are picked out on the way.
+    pub fn advance(&mut self, parser: &mut vte::Parser, byte: u8) {
+        match self.term.image_sniffer.advance(byte) {
+            Some(Sequence::Kitty(apc)) => graphics::kitty(self.term, &apc),
+            Some(Sequence::ITerm(osc)) => graphics::iterm(self.term, &osc),
+            None => {}
+        }
+        parser.advance(self, byte);
+    }
+
+    /// 1-based cursor position for CPR; a cursor waiting to wrap reports
+    /// the last column, as xterm does.
+    fn cursor_report(&self) -> (usize, usize) {
+        let col = self.term.cursor_col.min(self.term.cols().saturating_sub(1));
+        (self.term.cursor_row + 1, col + 1)
+    }
+
+    fn params_to_vec(params: &Params) -> Vec<i64> {
+        let mut out = Vec::new();
+        for p in params.iter() {
+            if p.is_empty() {
+                out.push(0);
+            } else {
+                out.push(p[0] as i64);
+            }
+        }
+        out
+    }
+
+    fn first_or(params: &[i64], default: usize) -> usize {
+        let v = params.first().copied().unwrap_or(default as i64);
+        if v <= 0 {
+            default
+        } else {
+            v as usize
+        }
+    }
+}
+
+impl Perform for VteHandler<'_> {
+    fn print(&mut self, c: char) {
+        self.term.put_char(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.term.line_feed(),
+            b'\r' => self.term.carriage_return(),
+            8 => self.term.backspace(),
+            b'\t' => self.term.tab(),
+            0x07 => self.term.bell = true,  // BEL
+            0x0c => self.term.clear_all(),
+            0x0e => self.term.shift_out = true,  // SO: use G1
+            0x0f => self.term.shift_out = false, // SI: back to G0
+            _ => log::debug!("unhandled control: {byte:#04x}"),
+        }
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        // XTGETTCAP: DCS + q <hex names> ST
+        if intermediates == [b'+'] && action == 'q' {
+            self.term.dcs = Some(Vec::new());
+            return;
+        }
+        log::debug!("unhandled DCS: {}", csi_repr(&Self::params_to_vec(params), intermediates, action));
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(buf) = self.term.dcs.as_mut().filter(|b| b.len() < MAX_DCS_LEN) {
+            buf.push(byte);
+        }
+    }
+
+    fn unhook(&mut self) {
+        if let Some(payload) = self.term.dcs.take() {
+            let reply = terminfo::xtgettcap_reply(self.term.term_name(), &payload);
+            self.term.reply_buf.extend_from_slice(&reply);
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if params.len() >= 2 {
+            if let Ok(cmd) = std::str::from_utf8(params[0]) {
+                match cmd {
+                    "0" | "2" => {
+                        if let Ok(title) = std::str::from_utf8(params[1]) {
+                            self.term.append_osc_title(title);
+                        }
+                    }
+                    "7" => {
+                        if let Ok(uri) = std::str::from_utf8(params[1]) {
+                            self.term.set_cwd_from_osc(uri);
+                        }
+                    }
+                    // Hyperlink: `8;params;URI`, an empty URI ends it. URIs
+                    // may contain `;`, which split them into more params.
+                    "8" => {
+                        let uri = params.get(2..).unwrap_or_default().join(&b';');
+                        self.term.link = match std::str::from_utf8(&uri) {
+                            Ok("") | Err(_) => 0,
+                            Ok(uri) => self.term.links.open(&String::from_utf8_lossy(params[1]), uri),
+                        };
+                    }
+                    // ConEmu reuses OSC 9 with numeric subcommands (9;4 is progress)
+                    "9" if params.len() > 2 && params[1].iter().all(u8::is_ascii_digit) => {
+                        log::debug!("unhandled OSC 9;{}", String::from_utf8_lossy(params[1]));
+                    }
+                    "9" => {
+                        let body = String::from_utf8_lossy(&params[1..].join(&b';')).into_owned();
+                        self.term.notify(Notification::simple(&body));
+                    }
+                    "99" => {
+                        let payload = params.get(2..).unwrap_or_default().join(&b';');
+                        match notify::kitty(&mut self.term.pending_notification, params[1], &payload) {
+                            Kitty::Show(n) => self.term.notify(n),
+                            Kitty::Query(reply) => self.term.reply_buf.extend_from_slice(reply.as_bytes()),
+                            Kitty::Pending | Kitty::Ignored => {}
+                        }
+                    }
+                    // Inline images: `advance` already saw the whole payload
+                    "1337" if params[1].starts_with(b"File=") => {}
+                    "22" => {
+                        let shape = String::from_utf8_lossy(params[1]);
+                        self.term.pointer_shape = shape.chars().filter(|c| !c.is_control()).take(32).collect();
+                    }
+                    "133" => match params[1] {
+                        b"A" => {
+                            // Back at the prompt: undo cursor and pointer shapes the last program left
+                            self.term.cursor_style = self.term.default_cursor_style;
+                            self.term.pointer_shape.clear();
+                            self.term.add_mark(MarkKind::Prompt);
+                        }
+                        b"B" => self.term.add_mark(MarkKind::Command),
+                        b"C" => self.term.add_mark(MarkKind::Output),
+                        b"D" => self.term.add_mark(MarkKind::Finished),
+                        other => log::debug!("unhandled OSC 133 {}", String::from_utf8_lossy(other)),
+                    },
+                    _ => log::debug!("unhandled OSC {cmd}"),
+                }
+            }
+        }
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        _ignore: bool,
+        action: char,
+    ) {
+        let p = Self::params_to_vec(params);
+
+        // DECSET: CSI ? Pm h (enable private modes)
+        if action == 'h' && intermediates == [b'?'] {
+            let features = self.term.features();
+            for &mode in &p {
+                match mode {
+                    1000 | 1002 | 1003 if features.mouse => self.term.mouse_mode = mode as u16,
+                    1006 if features.mouse => self.term.mouse_sgr = true,
+                    2004 if features.bracketed_paste => self.term.bracketed_paste = true,
+                    1049 if features.alt_screen => self.term.enter_alt_screen(true),
+                    47 | 1047 if features.alt_screen => self.term.enter_alt_screen(false),
+                    1000 | 1002 | 1003 | 1006 | 2004 | 1049 | 47 | 1047 => {
+                        log::debug!("DECSET {mode} not in TERM={}", self.term.term_name());
+                    }
+                    _ => log::debug!("unhandled DECSET {mode}"),
+                }
+            }
+            return;
+        }
+
+        // DECRST: CSI ? Pm l (disable private modes)
+        if action == 'l' && intermediates == [b'?'] {
+            for &mode in &p {
+                match mode {
+                    1000 | 1002 | 1003 if self.term.mouse_mode == mode as u16 => {
+                        self.term.mouse_mode = 0;
+                    }
+                    1006 => self.term.mouse_sgr = false,
+                    2004 => self.term.bracketed_paste = false,
+                    1049 if self.term.features().alt_screen => self.term.exit_alt_screen(true),
+                    47 | 1047 if self.term.features().alt_screen => self.term.exit_alt_screen(false),
+                    _ => log::debug!("unhandled DECRST {mode}"),
+                }
+            }
+            return;
+        }
+
+        // DECSCUSR: cursor style (CSI Ps SP q)
+        if action == 'q' && intermediates == [b' '] {
+            let style = p.first().copied().unwrap_or(0);
+            self.term.cursor_style = match style {
+                0 => self.term.default_cursor_style,
+                1..=2 => crate::terminal::CursorStyle::Block,
+                3..=4 => crate::terminal::CursorStyle::Underline,
+                5..=6 => crate::terminal::CursorStyle::Beam,
+                _ => crate::terminal::CursorStyle::Block,
+            };
+            return;
+        }
+
+        match action {
+            'A' => self.term.move_rel(-(Self::first_or(&p, 1) as isize), 0),
+            'B' => self.term.move_rel(Self::first_or(&p, 1) as isize, 0),
+            'C' => self.term.move_rel(0, Self::first_or(&p, 1) as isize),
+            'D' => self.term.move_rel(0, -(Self::first_or(&p, 1) as isize)),
+            'E' => {
+                let n = Self::first_or(&p, 1);
+                self.term.move_rel(n as isize, 0);
+                self.term.carriage_return();
+            }
+            'F' => {
+                let n = Self::first_or(&p, 1);
+                self.term.move_rel(-(n as isize), 0);
+                self.term.carriage_return();
+            }
+            'G' => self
+                .term
+                .set_cursor_col(Self::first_or(&p, 1).saturating_sub(1)),
+            'H' | 'f' => {
+                let row = p.first().copied().unwrap_or(1).max(1) as usize - 1;
+                let col = p.get(1).copied().unwrap_or(1).max(1) as usize - 1;
+                self.term.move_cursor(row, col);
+            }
+            'J' => self
+                .term
+                .erase_in_display(p.first().copied().unwrap_or(0) as usize),
+            'K' => self
+                .term
+                .erase_in_line(p.first().copied().unwrap_or(0) as usize),
+            'L' => self.term.insert_lines(Self::first_or(&p, 1)),
+            'M' => self.term.delete_lines(Self::first_or(&p, 1)),
+            '@' => self.term.insert_blank_chars(Self::first_or(&p, 1)),
+            'P' => self.term.delete_chars(Self::first_or(&p, 1)),
+            'S' => self.term.scroll_up_lines(Self::first_or(&p, 1)),
+            'T' => self.term.scroll_down_lines(Self::first_or(&p, 1)),
+            'd' => self
+                .term
+                .set_cursor_row(Self::first_or(&p, 1).saturating_sub(1)),
+            'X' => self.term.erase_chars(Self::first_or(&p, 1)),
+            'h' => {
+                // SM: set mode (non-private)
+                // Most common: mode 4 = insert mode (IRM)
+            }
+            'l' => {
+                // RM: reset mode (non-private)
+            }
+            'm' => self.term.sgr(&p),
+            // DECSTR: soft terminal reset
+            'p' if intermediates == [b'!'] => self.term.soft_reset(),
+            // DA: device attributes
+            'c' if intermediates.is_empty() && p.first().copied().unwrap_or(0) == 0 => {
+                let reply = terminfo::primary_da(self.term.term_name());
+                self.term.reply_buf.extend_from_slice(reply);
+            }
+            'c' if intermediates == [b'>'] && p.first().copied().unwrap_or(0) == 0 => {
+                if let Some(reply) = terminfo::secondary_da(self.term.term_name()) {
+                    self.term.reply_buf.extend_from_slice(reply.as_bytes());
+                }
+            }
+            // XTVERSION
+            'q' if intermediates == [b'>'] && p.first().copied().unwrap_or(0) == 0 => {
+                self.term.reply_buf.extend_from_slice(terminfo::xtversion().as_bytes());
+            }
+            // DSR: Device Status Report
+            'n' if intermediates.is_empty() => {
+                match p.first().copied().unwrap_or(0) {
+                    5 => {
+                        // Status report → "OK"
+                        self.term.reply_buf.extend_from_slice(b"\x1b[0n");
+                    }
+                    6 => {
+                        // Cursor position report
+                        let (r, c) = self.cursor_report();
+                        let reply = format!("\x1b[{};{}R", r, c);
+                        self.term.reply_buf.extend_from_slice(reply.as_bytes());
+                    }
+                    _ => {}
+                }
+            }
+            'n' if intermediates == [b'?'] && p.first() == Some(&6) => {
+                // DECXCPR: cursor position with the page number
+                let (r, c) = self.cursor_report();
+                self.term.reply_buf.extend_from_slice(format!("\x1b[?{r};{c};1R").as_bytes());
+            }
+            'r' => {
+                // DECSTBM: Set scrolling region
+                let top = p.first().copied().unwrap_or(1).max(1) as usize - 1;
+                let bottom = p.get(1).copied().unwrap_or(self.term.rows() as i64).max(1) as usize;
+                self.term.set_scroll_region(top, bottom);
+            }
+            's' => {
+                // Save cursor position
+                self.term.save_cursor();
+            }
+            't' => {
+                // Window manipulation: only the pixel size reports image
+                // tools use are answered
+                let (cw, ch) = self.term.cell_size;
+                let reply = match p.first().copied().unwrap_or(0) {
+                    14 => format!("\x1b[4;{};{}t", self.term.rows() * ch, self.term.cols() * cw),
+                    16 => format!("\x1b[6;{ch};{cw}t"),
+                    _ => return,
+                };
+                self.term.reply_buf.extend_from_slice(reply.as_bytes());
+            }
+            'u' => {
+                // Restore cursor position
+                self.term.restore_cursor();
+            }
+            _ => log::debug!("unhandled CSI: {}", csi_repr(&p, intermediates, action)),
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        match byte {
+            b'D' => self.term.line_feed(),
+            b'E' => self.term.next_line(),
+            b'M' => self.term.reverse_index(),
+            b'c' => {
+                self.term.clear_and_reset();
+                self.term.soft_reset();
+            }
+            // Designate G0 / G1: `0` is DEC Special Graphics; `B` (ASCII),
+            // `A` (UK) and the rest are treated as ASCII
+            _ if matches!(intermediates, [b'('] | [b')']) => {
+                let charset = if byte == b'0' { Charset::DecSpecial } else { Charset::Ascii };
+                self.term.charsets[(intermediates[0] == b')') as usize] = charset;
+            }
+            _ => log::debug!(
+                "unhandled ESC {}{}",
+                String::from_utf8_lossy(intermediates),
+                byte as char
+            ),
+        }
+    }
+}
+
+/// Readable form of a sequence for debug logs, e.g. `? 2026 h`.
+fn csi_repr(params: &[i64], intermediates: &[u8], action: char) -> String {
+    let params = params
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(";");
+    format!("{} {params} {action}", String::from_utf8_lossy(intermediates))
+        .trim_start()
+        .to_string()
+}
@@ -0,0 +1,60 @@
+use moterm_core::harness::Harness;
+use moterm_core::terminal::Pos;
+
+#[test]
+fn primary_screen_is_restored() {
+    let mut h = Harness::new(10, 3);
+    h.feed(b"shell$ ");
+    h.feed(b"\x1b[?1049h\x1b[Hvim");
+    assert_eq!(h.screen(), "vim\n\n\n");
+    h.feed(b"\x1b[?1049l");
+    assert_eq!(h.screen(), "shell$\n\n\n");
+    assert_eq!(h.cursor(), (0, 7));
+}
+
+#[test]
+fn alt_screen_output_skips_scrollback() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b[?1049h1\r\n2\r\n3\r\n4");
+    assert_eq!(h.term.grid.scrollback_len(), 0);
+    h.feed(b"\x1b[?1049l");
+    assert_eq!(h.term.grid.scrollback_len(), 0);
+}
+
+#[test]
+fn selection_and_view_scroll_reset_on_switch() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"a\r\nb\r\nc\r\nd");
+    h.term.set_view_scroll(1);
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.feed(b"\x1b[?1049h");
+    assert!(h.term.selection.is_none());
+    assert_eq!(h.term.view_scroll, 0);
+    h.term.set_view_scroll(1);
+    assert_eq!(h.term.view_scroll, 0, "no history scrolling on the alt screen");
+}
+
+#[test]
+fn select_all_covers_only_alt_grid() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"old1\r\nold2\r\nold3");
+    h.feed(b"\x1b[?1049h\x1b[Htop\r\nbottom");
+    h.term.select_all();
+    assert_eq!(h.term.selection_text().unwrap(), "top\nbottom");
+}
+
+#[test]
+fn alt_screen_history_is_capped_and_dropped_on_exit() {
+    let mut h = Harness::new(10, 2);
+    h.term.alt_history = 2;
+    h.feed(b"p1\r\np2\r\n$ ");
+    h.feed(b"\x1b[?1049h1\r\n2\r\n3\r\n4\r\n5");
+    // Primary scrollback, then the last two rows scrolled off the alt screen
+    assert_eq!(h.scrollback(), "p1\n2\n3\n");
+    assert_eq!(h.term.max_view_scroll(), 2);
+    h.term.set_view_scroll(10);
+    assert_eq!(h.term.view_scroll, 2);
+    h.feed(b"\x1b[?1049l");
+    assert_eq!(h.scrollback(), "p1\n");
+    assert_eq!(h.screen(), "p2\n$\n");
+}
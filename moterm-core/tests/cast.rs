@@ -0,0 +1,51 @@
+use moterm_core::cast::{json_string, CastWriter};
+
+#[test]
+fn escapes_control_characters() {
+    assert_eq!(json_string("a\"b\\\x1b[0m\r\n"), r#""a\"b\\\u001b[0m\r\n""#);
+}
+
+#[test]
+fn holds_split_utf8_until_complete() {
+    let mut buf = Vec::new();
+    {
+        let mut w = CastWriter::new(&mut buf, 80, 24).unwrap();
+        let bytes = "中".as_bytes();
+        w.output(&bytes[..1]).unwrap();
+        w.output(&bytes[1..]).unwrap();
+        w.resize(100, 30).unwrap();
+    }
+    let text = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(r#"{"version": 2, "width": 80, "height": 24"#));
+    assert!(lines[1].ends_with(r#", "o", "中"]"#));
+    assert!(lines[2].ends_with(r#", "r", "100x30"]"#));
+}
+
+#[test]
+fn round_trips_through_parse() {
+    use moterm_core::cast::parse_cast;
+    let mut buf = Vec::new();
+    {
+        let mut w = CastWriter::new(&mut buf, 80, 24).unwrap();
+        w.output("a\"\\\x1b[1m中😀\r\n".as_bytes()).unwrap();
+        w.resize(100, 30).unwrap();
+    }
+    let cast = parse_cast(&String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!((cast.width, cast.height), (80, 24));
+    assert_eq!(cast.events.len(), 2);
+    assert_eq!(cast.events[0].kind, 'o');
+    assert_eq!(cast.events[0].data, "a\"\\\x1b[1m中😀\r\n");
+    assert_eq!(cast.events[1].kind, 'r');
+    assert_eq!(cast.events[1].data, "100x30");
+}
+
+#[test]
+fn decodes_surrogate_pairs() {
+    use moterm_core::cast::parse_cast;
+    let cast = parse_cast("{\"version\": 2, \"width\": 2, \"height\": 1}\n[0.5, \"o\", \"\\ud83d\\ude00x\"]\nnot an event\n").unwrap();
+    assert_eq!(cast.events.len(), 1);
+    assert_eq!(cast.events[0].time, 0.5);
+    assert_eq!(cast.events[0].data, "😀x");
+}
@@ -0,0 +1,19 @@
+use moterm_core::harness::Harness;
+
+#[test]
+fn dec_special_graphics_draws_boxes() {
+    let mut h = Harness::new(10, 3);
+    h.feed(b"\x1b(0lqqk\r\nx  x\r\nmqqj\x1b(B");
+    assert_eq!(h.screen(), "┌──┐\n│  │\n└──┘\n");
+    h.feed(b"\x1b[1;6Hq");
+    assert_eq!(h.screen(), "┌──┐ q\n│  │\n└──┘\n");
+}
+
+#[test]
+fn shift_out_selects_g1() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b)0a\x0eqx\x0fq");
+    assert_eq!(h.screen(), "a─│q\n\n");
+    h.feed(b"\x0e\x1bcq");
+    assert_eq!(h.screen(), "q\n\n");
+}
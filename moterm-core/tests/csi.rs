@@ -0,0 +1,229 @@
+use moterm_core::harness::Harness;
+
+#[test]
+fn plain_text_and_newlines() {
+    let mut h = Harness::new(10, 3);
+    h.feed(b"one\r\ntwo\r\nthree");
+    assert_eq!(h.screen(), "one\ntwo\nthree\n");
+    assert_eq!(h.cursor(), (2, 5));
+}
+
+#[test]
+fn soft_wrap_at_right_margin() {
+    let mut h = Harness::new(5, 3);
+    h.feed(b"abcdefg");
+    assert_eq!(h.screen(), "abcde\nfg\n\n");
+    assert!(h.term.grid[0].wrapped);
+    assert!(!h.term.grid[1].wrapped);
+}
+
+#[test]
+fn cursor_movement() {
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[3;4HX"); // CUP row 3 col 4
+    h.feed(b"\x1b[AY"); // up
+    h.feed(b"\x1b[2DZ"); // back 2
+    h.feed(b"\x1b[10;10H"); // clamped
+    assert_eq!(h.screen(), "\n   ZY\n   X\n\n\n");
+    assert_eq!(h.cursor(), (4, 9));
+}
+
+#[test]
+fn erase_in_line_and_display() {
+    let mut h = Harness::new(6, 3);
+    h.feed(b"aaaaaa\r\nbbbbbb\r\ncccccc");
+    h.feed(b"\x1b[2;3H\x1b[K"); // erase to end of row 2
+    assert_eq!(h.screen(), "aaaaaa\nbb\ncccccc\n");
+    h.feed(b"\x1b[1K"); // erase to start, inclusive
+    assert_eq!(h.screen(), "aaaaaa\n\ncccccc\n");
+    h.feed(b"\x1b[J"); // erase below
+    assert_eq!(h.screen(), "aaaaaa\n\n\n");
+    h.feed(b"\x1b[2J");
+    assert_eq!(h.screen(), "\n\n\n");
+}
+
+#[test]
+fn insert_and_delete_chars() {
+    let mut h = Harness::new(8, 1);
+    h.feed(b"abcdef\x1b[1;3H\x1b[2@");
+    assert_eq!(h.screen(), "ab  cdef\n");
+    h.feed(b"\x1b[3P");
+    assert_eq!(h.screen(), "abdef\n");
+    h.feed(b"\x1b[2X");
+    assert_eq!(h.screen(), "ab  f\n");
+}
+
+#[test]
+fn sgr_colors() {
+    let mut h = Harness::new(12, 1);
+    h.feed(b"\x1b[31ma\x1b[44mb\x1b[0mc\x1b[38;5;200md\x1b[48;2;1;2;3me\x1b[39;49mf");
+    assert_eq!(
+        h.attrs(),
+        "0: 0..1 fg=1\n0: 1..2 fg=1 bg=4\n0: 3..4 fg=200\n0: 4..5 fg=200 bg=#010203\n"
+    );
+}
+
+#[test]
+fn wide_chars_occupy_two_cells() {
+    let mut h = Harness::new(5, 2);
+    h.feed_str("a中b");
+    assert_eq!(h.screen(), "a中b\n\n");
+    assert_eq!(h.cursor(), (0, 4));
+    assert!(h.term.grid[0].cells[2].wide_cont);
+}
+
+#[test]
+fn device_status_reports() {
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[5n");
+    assert_eq!(h.take_replies(), b"\x1b[0n");
+    h.feed(b"\x1b[3;7H\x1b[6n");
+    assert_eq!(h.take_replies(), b"\x1b[3;7R");
+    h.feed(b"\x1b[?6n");
+    assert_eq!(h.take_replies(), b"\x1b[?3;7;1R");
+    // Waiting to wrap after the last column
+    h.feed(b"\x1b[1;1H0123456789\x1b[6n");
+    assert_eq!(h.take_replies(), b"\x1b[1;10R");
+}
+
+#[test]
+fn device_attributes_follow_term_name() {
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[c");
+    assert_eq!(h.take_replies(), b"\x1b[?62;22c");
+    h.feed(b"\x1b[>c");
+    assert!(h.take_replies().starts_with(b"\x1b[>0;"));
+    h.term.set_term_name("vt100");
+    h.feed(b"\x1b[0c\x1b[>c");
+    assert_eq!(h.take_replies(), b"\x1b[?1;2c");
+}
+
+#[test]
+fn xtversion_reports_name_and_version() {
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[>q\x1b[>0q");
+    let one = format!("\x1bP>|moterm {}\x1b\\", env!("CARGO_PKG_VERSION"));
+    assert_eq!(h.take_replies(), [one.as_bytes(), one.as_bytes()].concat());
+}
+
+#[test]
+fn xtgettcap_answers_from_term_name() {
+    let mut h = Harness::new(10, 5);
+    // "colors;RGB;foo"
+    h.feed(b"\x1bP+q636F6C6F7273;524742;666F6F\x1b\\");
+    assert_eq!(
+        h.take_replies(),
+        b"\x1bP1+r636F6C6F7273=323536\x1b\\\x1bP1+r524742=382F382F38\x1b\\\x1bP0+r666F6F\x1b\\"
+    );
+    h.term.set_term_name("xterm");
+    h.feed(b"\x1bP+q636F6C6F7273;524742\x1b\\");
+    assert_eq!(h.take_replies(), b"\x1bP1+r636F6C6F7273=38\x1b\\\x1bP0+r524742\x1b\\");
+}
+
+#[test]
+fn vt100_ignores_colors_and_xterm_modes() {
+    use moterm_core::color::ColorSpec;
+    let mut h = Harness::new(10, 5);
+    h.term.set_term_name("vt100");
+    h.feed(b"\x1b[38;5;196;48;2;1;2;3;31;1m");
+    assert_eq!((h.term.style.fg, h.term.style.bg), (ColorSpec::DefaultFg, ColorSpec::DefaultBg));
+    // Parameters of the ignored colors aren't read as attributes
+    assert!(h.term.style.bold && !h.term.style.dim);
+    h.feed(b"\x1b[?1000h\x1b[?1006h\x1b[?2004h\x1b[?1049h");
+    assert_eq!(h.term.mouse_mode, 0);
+    assert!(!h.term.mouse_sgr && !h.term.bracketed_paste && !h.term.alt_screen);
+}
+
+#[test]
+fn eight_color_term_ignores_extended_colors() {
+    use moterm_core::color::ColorSpec;
+    let mut h = Harness::new(10, 5);
+    h.term.set_term_name("xterm");
+    h.feed(b"\x1b[38;5;196m\x1b[48;2;1;2;3m");
+    assert_eq!((h.term.style.fg, h.term.style.bg), (ColorSpec::DefaultFg, ColorSpec::DefaultBg));
+    h.feed(b"\x1b[31;102m");
+    assert_eq!((h.term.style.fg, h.term.style.bg), (ColorSpec::Indexed(1), ColorSpec::Indexed(10)));
+    h.term.set_term_name("xterm-256color");
+    h.feed(b"\x1b[38;5;196;48;2;1;2;3m");
+    assert_eq!((h.term.style.fg, h.term.style.bg), (ColorSpec::Indexed(196), ColorSpec::Rgb(1, 2, 3)));
+}
+
+#[test]
+fn private_modes() {
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[?1002h\x1b[?1006h\x1b[?2004h");
+    assert_eq!(h.term.mouse_mode, 1002);
+    assert!(h.term.mouse_sgr);
+    assert!(h.term.bracketed_paste);
+    h.feed(b"\x1b[?1002l\x1b[?1006l\x1b[?2004l");
+    assert_eq!(h.term.mouse_mode, 0);
+    assert!(!h.term.mouse_sgr);
+    assert!(!h.term.bracketed_paste);
+}
+
+#[test]
+fn cursor_style_decscusr() {
+    use moterm_core::terminal::CursorStyle;
+    let mut h = Harness::new(10, 5);
+    h.feed(b"\x1b[6 q");
+    assert_eq!(h.term.cursor_style, CursorStyle::Beam);
+    h.feed(b"\x1b[4 q");
+    assert_eq!(h.term.cursor_style, CursorStyle::Underline);
+    h.feed(b"\x1b[0 q");
+    assert_eq!(h.term.cursor_style, CursorStyle::Block);
+}
+
+#[test]
+fn configured_cursor_style_is_restored() {
+    use moterm_core::terminal::CursorStyle;
+    let mut h = Harness::new(10, 5);
+    h.term.set_default_cursor_style(CursorStyle::Beam);
+    h.feed(b"\x1b[2 q\x1b[0 q");
+    assert_eq!(h.term.cursor_style, CursorStyle::Beam);
+    // vim leaves a block cursor; the next prompt mark restores the default
+    h.feed(b"\x1b[2 q\x1b]133;A\x07");
+    assert_eq!(h.term.cursor_style, CursorStyle::Beam);
+    h.feed(b"\x1b[4 q\x1b[31m\x1b[2;3r\x1b[!p");
+    assert_eq!(h.term.cursor_style, CursorStyle::Beam);
+    assert_eq!((h.term.scroll_top, h.term.scroll_bottom), (0, 5));
+    assert_eq!(h.term.style.fg, moterm_core::color::ColorSpec::DefaultFg);
+}
+
+#[test]
+fn sgr_bold_maps_to_bright_only_when_enabled() {
+    use moterm_core::color::ColorSpec;
+    let mut h = Harness::new(6, 1);
+    h.feed(b"\x1b[1;31ma\x1b[22mb\x1b[1;38;5;100mc");
+    assert_eq!(h.attrs(), "0: 0..1 fg=1 bold\n0: 1..2 fg=1\n0: 2..3 fg=100 bold\n");
+    let style = |c: usize| h.term.grid[0].cells[c].style;
+    assert_eq!(style(0).draw_fg(true), ColorSpec::Indexed(9));
+    assert_eq!(style(0).draw_fg(false), ColorSpec::Indexed(1));
+    assert_eq!(style(1).draw_fg(true), ColorSpec::Indexed(1));
+    assert_eq!(style(2).draw_fg(true), ColorSpec::Indexed(100));
+}
+
+#[test]
+fn nbsp_and_soft_hyphen_width_is_configurable() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("a\u{a0}b\u{ad}c|");
+    assert_eq!(h.cursor(), (0, 6));
+    h.term.collapse_invisible = true;
+    h.feed_str("\r\na\u{a0}b\u{ad}c|");
+    assert_eq!(h.cursor(), (1, 4));
+    assert_eq!(h.screen(), "a\u{a0}b\u{ad}c|\nabc|\n");
+}
+
+#[test]
+fn sgr_text_attributes_and_resets() {
+    let mut h = Harness::new(8, 2);
+    h.feed(b"\x1b[2;3;4ma\x1b[5;7;8;9mb\x1b[22;23;24;25mc\x1b[27;28;29md\x1b[1;4m\x1b[0me");
+    assert_eq!(
+        h.attrs(),
+        "0: 0..1 dim italic underline\n\
+         0: 1..2 dim italic underline blink inverse hidden strike\n\
+         0: 2..3 inverse hidden strike\n"
+    );
+    // Erasing keeps colors but not attributes
+    h.feed(b"\r\n\x1b[4;7;41m\x1b[K");
+    assert_eq!(h.attrs().lines().nth(3), Some("1: 0..8 bg=1"));
+}
@@ -0,0 +1,25 @@
+use moterm_core::grid::Damage;
+use moterm_core::harness::Harness;
+
+#[test]
+fn writing_damages_only_the_touched_rows() {
+    let mut h = Harness::new(10, 4);
+    assert_eq!(h.term.take_damage(), Damage::Full);
+    h.feed_str("\x1b[3;1Hhi");
+    assert_eq!(h.term.take_damage(), Damage::Rows(vec![2]));
+    assert_eq!(h.term.take_damage(), Damage::Rows(vec![]));
+    h.feed_str("\x1b[2;4r\x1b[4;1H\n");
+    assert_eq!(h.term.take_damage(), Damage::Rows(vec![1, 2, 3]));
+}
+
+#[test]
+fn scrolling_and_resizing_damage_everything() {
+    let mut h = Harness::new(10, 3);
+    h.term.take_damage();
+    h.feed_str("a\r\nb\r\nc\r\n");
+    assert_eq!(h.term.take_damage(), Damage::Full);
+    h.term.resize(8, 3);
+    assert_eq!(h.term.take_damage(), Damage::Full);
+    h.feed_str("\x1b[?1049h");
+    assert_eq!(h.term.take_damage(), Damage::Full);
+}
@@ -0,0 +1,61 @@
+use moterm_core::color::Rgb;
+use moterm_core::export::{export, export_with, Format};
+use moterm_core::harness::Harness;
+
+#[test]
+fn text_includes_scrollback_and_joins_wrapped_rows() {
+    let mut h = Harness::new(5, 2);
+    h.feed_str("one\r\ntwo\r\nabcdefg\r\n");
+    assert_eq!(export(&h.term, Format::Text), "one\ntwo\nabcdefg\n");
+}
+
+#[test]
+fn html_escapes_and_colors() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("a<b \x1b[31mred\x1b[0m");
+    let html = export(&h.term, Format::Html);
+    assert!(html.contains("a&lt;b <span style=\"color:#"), "{html}");
+    assert!(html.contains(">red</span>\n"), "{html}");
+}
+
+#[test]
+fn rows_keep_first_write_time_and_export_stamps_lines() {
+    let mut h = Harness::new(5, 4);
+    h.term.clock = 100;
+    h.feed_str("one\r\n");
+    h.term.clock = 200;
+    h.feed_str("abcdefg\r\n");
+    h.feed_str("\x1b[1;1Hx");
+    assert_eq!(h.term.grid[0].time, 100);
+    assert_eq!(h.term.grid[1].time, 200);
+    assert_eq!(h.term.grid[2].time, 200);
+    let stamp = |t: u64| format!("[{t}] ");
+    assert_eq!(
+        export_with(&h.term, Format::Text, Some(&stamp)),
+        "[100] xne\n[200] abcdefg\n"
+    );
+    h.feed_str("\x1b[2J");
+    assert_eq!(h.term.grid[0].time, 0);
+}
+
+#[test]
+fn html_uses_the_terminal_palette() {
+    let mut h = Harness::new(10, 2);
+    h.term.palette.bg = Rgb::new(0x20, 0x21, 0x22);
+    h.term.palette.ansi[1] = Rgb::new(0xaa, 0x00, 0x00);
+    h.feed_str("\x1b[31mred\x1b[0m \x1b[38;5;196mx");
+    let html = export(&h.term, Format::Html);
+    assert!(html.contains("background:#202122;"), "{html}");
+    assert!(html.contains("color:#aa0000;"), "{html}");
+    // Only 0–15 come from the palette
+    assert!(html.contains("color:#ff0000;"), "{html}");
+}
+
+#[test]
+fn parse_hex_colors() {
+    assert_eq!(Rgb::parse_hex("#1e1e2e"), Some(Rgb::new(0x1e, 0x1e, 0x2e)));
+    assert_eq!(Rgb::parse_hex("FFaa00"), Some(Rgb::new(0xff, 0xaa, 0x00)));
+    assert_eq!(Rgb::parse_hex("#fff"), None);
+    assert_eq!(Rgb::parse_hex("#gg0000"), None);
+    assert_eq!(Rgb::parse_hex("#é0000"), None);
+}
@@ -0,0 +1,127 @@
+use moterm_core::graphics::ImageSlice;
+use moterm_core::harness::Harness;
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&vec![0x80; (width * height * 3) as usize]).unwrap();
+    drop(writer);
+    out
+}
+
+fn slices(h: &Harness, row: usize) -> Vec<ImageSlice> {
+    h.term.grid[row].images.clone()
+}
+
+#[test]
+fn kitty_transmit_and_display() {
+    let mut h = Harness::new(20, 5);
+    h.term.cell_size = (4, 8);
+    h.feed_str("ab");
+    // 8x16 pixels: 2x2 cells at 4x8
+    let data = base64(&[0xff; 8 * 16 * 4]);
+    h.feed(format!("\x1b_Ga=T,f=32,s=8,v=16,i=7;{data}\x1b\\").as_bytes());
+    assert_eq!(h.take_replies(), b"\x1b_Gi=7;OK\x1b\\");
+    let image = h.term.images.get(7).unwrap();
+    assert_eq!((image.width, image.height), (8, 16));
+    assert_eq!(slices(&h, 0), [ImageSlice { id: 7, col: 2, cols: 2, row: 0, rows: 2 }]);
+    assert_eq!(slices(&h, 1), [ImageSlice { id: 7, col: 2, cols: 2, row: 1, rows: 2 }]);
+    assert_eq!(h.cursor(), (1, 4));
+    // The parser isn't left inside the sequence
+    h.feed_str("\r\nx");
+    assert_eq!(h.screen(), "ab\n\nx\n\n\n");
+}
+
+#[test]
+fn kitty_chunks_and_put() {
+    let mut h = Harness::new(20, 5);
+    let data = base64(&[0u8; 2 * 2 * 3]);
+    let (a, b) = data.split_at(8);
+    h.feed(format!("\x1b_Ga=t,f=24,s=2,v=2,i=3,m=1;{a}\x1b\\\x1b_Gm=0;{b}\x1b\\").as_bytes());
+    assert_eq!(h.take_replies(), b"\x1b_Gi=3;OK\x1b\\");
+    assert!(slices(&h, 0).is_empty());
+    h.feed(b"\x1b_Ga=p,i=3,c=3,r=1,q=1\x1b\\");
+    assert!(h.take_replies().is_empty());
+    assert_eq!(slices(&h, 0), [ImageSlice { id: 3, col: 0, cols: 3, row: 0, rows: 1 }]);
+    h.feed(b"\x1b_Ga=p,i=9\x1b\\");
+    assert!(h.take_replies().starts_with(b"\x1b_Gi=9;ENOENT"));
+}
+
+#[test]
+fn kitty_png_and_delete() {
+    let mut h = Harness::new(20, 5);
+    h.term.cell_size = (10, 10);
+    let data = base64(&png(10, 20));
+    h.feed(format!("\x1b_Gf=100,a=T,q=2;{data}\x1b\\").as_bytes());
+    assert_eq!(h.term.images.len(), 1);
+    assert_eq!(slices(&h, 1).len(), 1);
+    h.feed(b"\x1b_Ga=d,d=A\x1b\\");
+    assert!(slices(&h, 0).is_empty() && slices(&h, 1).is_empty());
+    assert!(h.term.images.is_empty());
+}
+
+#[test]
+fn iterm_inline_png() {
+    let mut h = Harness::new(20, 5);
+    h.term.cell_size = (10, 10);
+    let data = base64(&png(30, 20));
+    h.feed(format!("\x1b]1337;File=name=eC5wbmc=;size=99;inline=1:{data}\x07").as_bytes());
+    assert_eq!(slices(&h, 0), [ImageSlice { id: slices(&h, 0)[0].id, col: 0, cols: 3, row: 0, rows: 2 }]);
+    assert_eq!(h.cursor(), (1, 3));
+    // width in cells keeps the aspect ratio
+    h.feed(format!("\r\n\x1b]1337;File=width=6;inline=1:{data}\x1b\\").as_bytes());
+    assert_eq!((slices(&h, 2)[0].cols, slices(&h, 2)[0].rows), (6, 4));
+    // Downloads aren't shown
+    h.feed(format!("\x1b]1337;File=name=eC5wbmc=:{data}\x07").as_bytes());
+    assert_eq!(h.term.images.len(), 2);
+}
+
+#[test]
+fn images_scroll_and_are_erased_with_their_rows() {
+    let mut h = Harness::new(10, 3);
+    h.feed(b"\x1b_Ga=T,f=24,s=1,v=1,c=1,r=1;AAAA\x1b\\\r\n\n\n");
+    assert_eq!(h.term.grid.line(0).unwrap().images.len(), 1);
+    h.feed(b"\x1b_Ga=T,f=24,s=1,v=1,c=1,r=1;AAAA\x1b\\\x1b[2J");
+    assert!((0..3).all(|r| slices(&h, r).is_empty()));
+}
+
+#[test]
+fn pixel_size_reports() {
+    let mut h = Harness::new(80, 24);
+    h.term.cell_size = (9, 18);
+    h.feed(b"\x1b[16t\x1b[14t");
+    assert_eq!(h.take_replies(), b"\x1b[6;18;9t\x1b[4;432;720t");
+}
+
+#[test]
+fn huge_extents_do_not_overflow() {
+    let mut h = Harness::new(20, 5);
+    h.term.cell_size = (10, 10);
+    let data = base64(&png(30, 20));
+    // Shrunk to the terminal's width, or dropped when that can't be computed
+    h.feed(format!("\x1b]1337;File=width=4294967295;inline=1:{data}\x07").as_bytes());
+    h.feed(format!("\r\x1b]1337;File=width=4294967295;height=18446744073709551615;inline=1:{data}\x07").as_bytes());
+    h.feed(format!("\r\x1b]1337;File=width=18446744073709551615;preserveAspectRatio=0;inline=1:{data}\x07").as_bytes());
+    assert!((0..5).flat_map(|r| slices(&h, r)).all(|s| s.cols <= 20));
+    h.feed(b"\x1b_Ga=T,f=24,s=1,v=1,c=4294967295,r=4294967295,q=2;AAAA\x1b\\");
+    h.feed(b"\x1b_Ga=T,f=24,s=1,v=1,c=4294967295,q=2;AAAA\x1b\\");
+}
@@ -0,0 +1,48 @@
+use moterm_core::layout::{Direction, Layout, Rect};
+
+#[test]
+fn splits_share_the_area_around_a_divider() {
+    let mut layout = Layout::Pane(1);
+    assert!(layout.split(1, 2, true));
+    assert!(layout.split(2, 3, false));
+    let (panes, dividers) = layout.rects(Rect::new(0, 10, 101, 51));
+    assert_eq!(
+        panes,
+        vec![
+            (1, Rect::new(0, 10, 50, 51)),
+            (2, Rect::new(51, 10, 50, 25)),
+            (3, Rect::new(51, 36, 50, 25)),
+        ]
+    );
+    assert_eq!(dividers, vec![Rect::new(50, 10, 1, 51), Rect::new(51, 35, 50, 1)]);
+}
+
+#[test]
+fn removing_a_pane_gives_its_space_to_the_sibling() {
+    let mut layout = Layout::Pane(1);
+    layout.split(1, 2, true);
+    layout.split(1, 3, false);
+    assert_eq!(layout.panes(), vec![1, 3, 2]);
+    assert_eq!(layout.remove(2), Some(1));
+    assert_eq!(layout.remove(3), Some(1));
+    assert_eq!(layout, Layout::Pane(1));
+    assert_eq!(layout.remove(1), None);
+    assert_eq!(layout.remove(7), None);
+}
+
+#[test]
+fn neighbor_follows_direction() {
+    // 1 | 2
+    //   | -
+    //   | 3
+    let mut layout = Layout::Pane(1);
+    layout.split(1, 2, true);
+    layout.split(2, 3, false);
+    let area = Rect::new(0, 0, 101, 51);
+    assert_eq!(layout.neighbor(area, 1, Direction::Right), Some(2));
+    assert_eq!(layout.neighbor(area, 3, Direction::Left), Some(1));
+    assert_eq!(layout.neighbor(area, 2, Direction::Down), Some(3));
+    assert_eq!(layout.neighbor(area, 3, Direction::Up), Some(2));
+    assert_eq!(layout.neighbor(area, 1, Direction::Left), None);
+    assert_eq!(layout.neighbor(area, 2, Direction::Right), None);
+}
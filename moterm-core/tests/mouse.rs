@@ -0,0 +1,31 @@
+use moterm_core::mouse::{self, encode};
+
+#[test]
+fn sgr_release_keeps_button_and_modifiers() {
+    let btn = mouse::BUTTON_RIGHT | mouse::MOD_CTRL;
+    assert_eq!(encode(btn, 4, 1, true, true), b"\x1b[<18;5;2M");
+    assert_eq!(encode(btn, 4, 1, false, true), b"\x1b[<18;5;2m");
+}
+
+#[test]
+fn normal_release_reports_button_three_with_modifiers() {
+    let btn = mouse::BUTTON_MIDDLE | mouse::MOD_SHIFT;
+    assert_eq!(encode(btn, 0, 0, true, false), [0x1b, b'[', b'M', 32 + 5, 33, 33]);
+    assert_eq!(encode(btn, 0, 0, false, false), [0x1b, b'[', b'M', 32 + 7, 33, 33]);
+}
+
+#[test]
+fn drag_sets_motion_bit() {
+    let btn = mouse::BUTTON_LEFT + mouse::MOTION;
+    assert_eq!(encode(btn, 9, 2, true, true), b"\x1b[<32;10;3M");
+}
+
+#[test]
+fn modifier_bits_combine() {
+    assert_eq!(mouse::modifier_bits(false, false, false), 0);
+    assert_eq!(mouse::modifier_bits(true, true, true), mouse::MOD_SHIFT | mouse::MOD_META | mouse::MOD_CTRL);
+    assert_eq!(
+        mouse::encode(mouse::BUTTON_LEFT | mouse::modifier_bits(false, false, true), 0, 0, true, true),
+        b"\x1b[<16;1;1M"
+    );
+}
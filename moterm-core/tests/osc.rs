@@ -0,0 +1,189 @@
+use moterm_core::notify;
+use moterm_core::terminal::MarkKind;
+use moterm_core::harness::Harness;
+
+#[test]
+fn window_title() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]0;hello\x07");
+    assert_eq!(h.term.title, "hello");
+    assert!(h.term.title_changed);
+    h.feed(b"\x1b]2;world\x1b\\");
+    assert_eq!(h.term.title, "world");
+}
+
+#[test]
+fn title_is_sanitized_and_capped() {
+    let mut h = Harness::new(10, 2);
+    h.term.append_osc_title("build\n50%\x1b[31m\u{7f}");
+    assert_eq!(h.term.title, "build 50%[31m");
+    h.feed(format!("\x1b]2;{}\x07", "x".repeat(1000)).as_bytes());
+    assert_eq!(h.term.title.len(), 256);
+}
+
+#[test]
+fn repeated_title_is_not_a_change() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]2;same\x07");
+    h.term.title_changed = false;
+    h.feed(b"\x1b]2;same\x07");
+    assert!(!h.term.title_changed);
+}
+
+#[test]
+fn working_directory() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]7;file://host/Users/me/my%20dir\x07");
+    assert_eq!(h.term.cwd.as_deref(), Some("/Users/me/my dir"));
+}
+
+#[test]
+fn unknown_osc_is_ignored() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]1337;whatever\x07ok");
+    assert_eq!(h.screen(), "ok\n\n");
+}
+
+#[test]
+fn prompt_marks_navigate_between_commands() {
+    let mut h = Harness::new(10, 3);
+    for cmd in ["ls", "pwd", "id"] {
+        h.feed_str(&format!("\x1b]133;A\x07$ \x1b]133;B\x07{cmd}\r\n\x1b]133;C\x07out\r\n\x1b]133;D;0\x07"));
+    }
+    let prompts: Vec<usize> = (0..h.term.total_lines())
+        .filter(|&r| h.term.line_at_global(r).unwrap().has_mark(MarkKind::Prompt))
+        .collect();
+    assert_eq!(prompts, [0, 2, 4]);
+    assert_eq!(h.term.prev_prompt(4), Some(2));
+    assert_eq!(h.term.prev_prompt(0), None);
+    assert_eq!(h.term.next_prompt(2), Some(4));
+    assert_eq!(h.term.next_prompt(4), None);
+
+    h.term.scroll_view_to_row(2);
+    assert_eq!(h.term.visible_start_global_row(), 2);
+}
+
+#[test]
+fn prompt_marks_are_dropped_with_erased_rows() {
+    let mut h = Harness::new(10, 3);
+    h.feed(b"\x1b]133;A\x07$ ");
+    assert!(h.term.grid[0].has_mark(MarkKind::Prompt));
+    h.feed(b"\x1b[2J");
+    assert!(h.term.grid[0].marks.is_empty());
+}
+
+#[test]
+fn last_command_line_and_output() {
+    let mut h = Harness::new(12, 6);
+    h.feed_str("\x1b]133;A\x07$ \x1b]133;B\x07echo a\r\n\x1b]133;C\x07a\r\n\x1b]133;D;0\x07");
+    h.feed_str("\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07x\r\ny\r\n\x1b]133;D;0\x07");
+    h.feed_str("\x1b]133;A\x07$ \x1b]133;B\x07");
+    let t = &h.term;
+    let (a, b) = t.last_command_line().unwrap();
+    assert_eq!(t.text_between(a, b).unwrap(), "ls");
+    let (a, b) = t.last_command_output().unwrap();
+    assert_eq!(t.text_between(a, b).unwrap(), "x\ny");
+}
+
+#[test]
+fn running_command_output_ends_at_cursor() {
+    let mut h = Harness::new(12, 4);
+    h.feed_str("\x1b]133;A\x07$ \x1b]133;B\x07top\r\n\x1b]133;C\x07busy");
+    let (a, b) = h.term.last_command_output().unwrap();
+    assert_eq!(h.term.text_between(a, b).unwrap(), "busy");
+    assert_eq!(Harness::new(4, 2).term.last_command_output(), None);
+}
+
+#[test]
+fn osc_9_notification_keeps_semicolons() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]9;build done; 3 warnings\x07\x1b]9;4;1;50\x07");
+    let n: Vec<_> = h.term.notifications.iter().map(|n| n.body.as_str()).collect();
+    assert_eq!(n, ["build done; 3 warnings"]);
+}
+
+#[test]
+fn osc_99_chunks_buttons_and_reports() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]99;i=job:d=0:a=report;Deploy\x1b\\");
+    assert!(h.term.notifications.is_empty());
+    // Body in base64: "ok to ship?"
+    h.feed(b"\x1b]99;i=job:d=0:p=body:e=1;b2sgdG8gc2hpcD8=\x1b\\");
+    h.feed("\x1b]99;i=job:p=buttons;Yes\u{2028}No\x1b\\".as_bytes());
+    let n = h.term.notifications.pop().expect("shown once done");
+    assert_eq!((n.id.as_str(), n.title.as_str(), n.body.as_str()), ("job", "Deploy", "ok to ship?"));
+    assert_eq!(n.buttons, ["Yes", "No"]);
+    assert!(n.report && n.focus && !n.only_unfocused);
+    assert_eq!(notify::activation_report(&n.id, Some(2)), b"\x1b]99;i=job;2\x1b\\");
+    assert_eq!(notify::activation_report(&n.id, None), b"\x1b]99;i=job;\x1b\\");
+}
+
+#[test]
+fn osc_99_query_and_unsupported_payloads() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]99;i=q:p=?;\x1b\\\x1b]99;p=icon;abc\x1b\\");
+    assert!(h.take_replies().starts_with(b"\x1b]99;i=q:p=?;a=focus,report:"));
+    assert!(h.term.notifications.is_empty());
+    h.feed(b"\x1b]99;o=unfocused;hi\x1b\\");
+    let n = &h.term.notifications[0];
+    assert_eq!((n.id.as_str(), n.title.as_str(), n.only_unfocused), ("0", "hi", true));
+}
+
+#[test]
+fn pointer_shape_resets_when_the_program_exits() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"\x1b]22;pointer\x07");
+    assert_eq!(h.term.pointer_shape, "pointer");
+    h.feed(b"\x1b]133;A\x07");
+    assert_eq!(h.term.pointer_shape, "");
+    h.feed(b"\x1b[?1049h\x1b]22;crosshair\x1b\\");
+    assert_eq!(h.term.pointer_shape, "crosshair");
+    h.feed(b"\x1b[?1049l");
+    assert_eq!(h.term.pointer_shape, "");
+}
+
+#[test]
+fn osc_8_links_cells_until_closed() {
+    let mut h = Harness::new(20, 2);
+    h.feed(b"a\x1b]8;;https://example.com/?q=1;2\x1b\\link\x1b]8;;\x1b\\b");
+    assert_eq!(h.term.link_at(0, 0), None);
+    for col in 1..5 {
+        assert_eq!(h.term.link_at(0, col), Some("https://example.com/?q=1;2"));
+    }
+    assert_eq!(h.term.link_at(0, 5), None);
+}
+
+#[test]
+fn osc_8_ids_join_pieces_of_one_link() {
+    let mut h = Harness::new(20, 2);
+    h.feed(b"\x1b]8;id=x;file:///a\x07ab\x1b]8;;\x07 \x1b]8;id=x;file:///a\x07cd\x1b]8;;\x07");
+    let ids: Vec<u32> = h.term.line_at_global(0).unwrap().cells[..5].iter().map(|c| c.link).collect();
+    assert_eq!(ids, [1, 1, 0, 1, 1]);
+    h.feed(b"\x1b]8;;file:///a\x07e\x1b]8;;file:///a\x07f\x1b]8;;\x07");
+    assert_eq!(h.term.line_at_global(0).unwrap().cells[5].link, 2);
+    assert_eq!(h.term.line_at_global(0).unwrap().cells[6].link, 2);
+}
+
+#[test]
+fn dropped_links_stop_resolving() {
+    let mut links = moterm_core::hyperlink::Hyperlinks::default();
+    let first = links.open("", "https://first");
+    for i in 0..moterm_core::hyperlink::MAX_LINKS {
+        links.open("", &format!("https://{i}"));
+    }
+    assert_eq!(links.get(first), None);
+    let last = links.open("", "https://last");
+    assert_eq!(links.get(last), Some("https://last"));
+    links.clear();
+    assert_eq!(links.get(last), None);
+    assert_eq!(links.get(0), None);
+}
+
+#[test]
+fn osc_8_link_covers_both_halves_of_wide_chars() {
+    let mut h = Harness::new(10, 2);
+    h.feed("\x1b]8;;https://例子.example\x07例\x1b]8;;\x07".as_bytes());
+    assert_eq!(h.term.link_at(0, 0), Some("https://例子.example"));
+    assert_eq!(h.term.link_at(0, 1), Some("https://例子.example"));
+    assert_eq!(h.term.link_at(0, 2), None);
+}
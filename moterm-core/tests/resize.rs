@@ -0,0 +1,103 @@
+use moterm_core::harness::Harness;
+
+#[test]
+fn shrink_rewraps_long_lines() {
+    let mut h = Harness::new(6, 2);
+    h.feed(b"abcdef\r\nxy");
+    h.term.resize(3, 2);
+    assert_eq!(h.scrollback(), "abc\n");
+    assert_eq!(h.screen(), "def\nxy\n");
+    assert_eq!(h.cursor(), (1, 2));
+}
+
+#[test]
+fn narrow_then_widen_restores_lines() {
+    let mut h = Harness::new(8, 3);
+    h.feed(b"abcdefgh\r\n$ ls");
+    h.term.resize(3, 3);
+    assert_eq!(h.scrollback(), "abc\ndef\ngh\n");
+    assert_eq!(h.screen(), "$ l\ns\n\n");
+    h.feed(b"x");
+    h.term.resize(10, 3);
+    assert_eq!(h.screen(), "abcdefgh\n$ lsx\n\n");
+    assert_eq!(h.cursor(), (1, 5));
+}
+
+#[test]
+fn reflow_keeps_wide_chars_whole() {
+    let mut h = Harness::new(6, 2);
+    h.feed_str("a中文字");
+    h.term.resize(4, 2);
+    // 文 no longer fits after "a中" and moves to the next row
+    assert_eq!(h.screen(), "a中\n文字\n");
+    h.term.resize(8, 2);
+    assert_eq!(h.screen(), "a中文字\n\n");
+    assert_eq!(h.term.selection_text(), None);
+}
+
+#[test]
+fn reflow_moves_prompt_marks_with_text() {
+    let mut h = Harness::new(6, 3);
+    h.feed(b"abcdef\x1b]133;A\x07gh\r\n");
+    h.term.resize(3, 3);
+    let marked: Vec<(usize, usize)> = (0..h.term.total_lines())
+        .filter_map(|r| Some((r, h.term.line_at_global(r)?.marks.first()?.col)))
+        .collect();
+    // The prompt started before "g", which now begins a row
+    assert_eq!(marked, [(h.term.grid.scrollback_len() + 1, 0)]);
+}
+
+#[test]
+fn grow_pads_with_blanks() {
+    let mut h = Harness::new(3, 1);
+    h.feed(b"abc");
+    h.term.resize(5, 3);
+    assert_eq!(h.screen(), "abc\n\n\n");
+    assert_eq!((h.term.cols(), h.term.rows()), (5, 3));
+}
+
+#[test]
+fn resize_resets_scroll_region() {
+    let mut h = Harness::new(5, 5);
+    h.feed(b"\x1b[2;4r");
+    h.term.resize(5, 8);
+    assert_eq!((h.term.scroll_top, h.term.scroll_bottom), (0, 8));
+}
+
+#[test]
+fn shrink_rows_keeps_cursor_line_and_grow_restores() {
+    let mut h = Harness::new(5, 4);
+    h.feed(b"a\r\nb\r\nc\r\nd");
+    h.term.resize(5, 2);
+    assert_eq!(h.screen(), "c\nd\n");
+    assert_eq!(h.scrollback(), "a\nb\n");
+    assert_eq!(h.cursor(), (1, 1));
+    h.term.resize(5, 4);
+    assert_eq!(h.screen(), "a\nb\nc\nd\n");
+    assert_eq!(h.cursor(), (3, 1));
+}
+
+#[test]
+fn resize_keeps_top_line_while_scrolled_back() {
+    let mut h = Harness::new(5, 3);
+    for i in 0..10 {
+        h.feed_str(&format!("{i}\r\n"));
+    }
+    h.term.set_view_scroll(4);
+    let top = h.term.visible_start_global_row();
+    h.term.resize(5, 2);
+    assert_eq!(h.term.visible_start_global_row(), top);
+    h.term.resize(5, 6);
+    assert_eq!(h.term.visible_start_global_row(), top);
+}
+
+#[test]
+fn shrink_under_alt_screen_keeps_prompt_at_cursor() {
+    let mut h = Harness::new(5, 4);
+    h.feed(b"a\r\nb\r\nc\r\n$ ");
+    h.feed(b"\x1b[?1049hfull");
+    h.term.resize(5, 2);
+    h.feed(b"\x1b[?1049l");
+    assert_eq!(h.screen(), "c\n$\n");
+    assert_eq!(h.cursor(), (1, 2));
+}
@@ -0,0 +1,68 @@
+use moterm_core::harness::Harness;
+
+fn check(h: &Harness) {
+    if let Err(e) = h.check_invariants() {
+        panic!("invariant violated: {e}");
+    }
+}
+
+#[test]
+fn wide_char_on_one_column_grid() {
+    let mut h = Harness::new(1, 2);
+    h.feed_str("中文");
+    check(&h);
+    assert_eq!(h.screen(), "中\n文\n");
+}
+
+#[test]
+fn edits_at_pending_wrap_position() {
+    let mut h = Harness::new(3, 2);
+    // Cursor sits past the last column after "abc"
+    h.feed(b"abc\x1b[2@\x1b[2P\x1b[5X\x1b[1K\x1b[K");
+    check(&h);
+    h.feed(b"\x1b[999;999H\x1b[65535@\x1b[65535P\x1b[65535X\x1b[65535L\x1b[65535M");
+    check(&h);
+}
+
+/// Same loop as the `vte` fuzz target, over a fixed pseudo-random corpus
+/// biased towards escape sequences, so it runs under `cargo test`.
+#[test]
+fn random_sequences_keep_invariants() {
+    const PIECES: &[&[u8]] = &[
+        b"\x1b[", b"\x1b]", b"\x1bP", b"\x07", b"\x1b\\", b";", b"?", b"!", b" ",
+        b"9999", b"0", b"1", b"H", b"J", b"K", b"@", b"P", b"X", b"L", b"M", b"r",
+        b"m", b"q", b"p", b"h", b"l", b"1049", b"\r\n", b"\t", b"\x08", b"\x1bM",
+        b"\x1bc", b"\x1b7", b"\x1b8", "中".as_bytes(), "é".as_bytes(), b"x",
+    ];
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize
+    };
+    for _ in 0..200 {
+        let (cols, rows) = (1 + next() % 8, 1 + next() % 5);
+        let mut h = Harness::new(cols, rows);
+        let mut data = Vec::new();
+        for _ in 0..next() % 300 {
+            data.extend_from_slice(PIECES[next() % PIECES.len()]);
+        }
+        let mid = data.len() / 2;
+        h.feed(&data[..mid]);
+        check(&h);
+        h.term.resize(1 + next() % 8, 1 + next() % 5);
+        h.feed(&data[mid..]);
+        check(&h);
+        let _ = h.take_replies();
+    }
+}
+
+#[test]
+fn scrollback_rows_fit_after_narrowing() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("0123456789\r\nabcdefghij\r\nxyz\r\n");
+    assert!(h.term.grid.scrollback_len() > 0);
+    h.term.resize(4, 2);
+    check(&h);
+}
@@ -0,0 +1,151 @@
+use moterm_core::harness::Harness;
+
+#[test]
+fn line_feed_at_bottom_pushes_scrollback() {
+    let mut h = Harness::new(5, 2);
+    h.feed(b"1\r\n2\r\n3\r\n4");
+    assert_eq!(h.screen(), "3\n4\n");
+    assert_eq!(h.scrollback(), "1\n2\n");
+}
+
+#[test]
+fn set_scroll_region_homes_cursor() {
+    let mut h = Harness::new(5, 5);
+    h.feed(b"\x1b[3;3H\x1b[2;4r");
+    assert_eq!((h.term.scroll_top, h.term.scroll_bottom), (1, 4));
+    assert_eq!(h.cursor(), (0, 0));
+}
+
+#[test]
+fn invalid_scroll_region_is_ignored() {
+    let mut h = Harness::new(5, 5);
+    h.feed(b"\x1b[4;2r");
+    assert_eq!((h.term.scroll_top, h.term.scroll_bottom), (0, 5));
+}
+
+#[test]
+fn reverse_index_scrolls_region_down() {
+    let mut h = Harness::new(5, 4);
+    h.feed(b"a\r\nb\r\nc\r\nd");
+    h.feed(b"\x1b[2;3r\x1b[2;1H\x1bM");
+    assert_eq!(h.screen(), "a\n\nb\nd\n");
+}
+
+#[test]
+fn full_reset_clears_everything() {
+    let mut h = Harness::new(5, 2);
+    h.feed(b"1\r\n2\r\n3\x1bc");
+    assert_eq!(h.screen(), "\n\n");
+    assert_eq!(h.scrollback(), "");
+    assert_eq!(h.cursor(), (0, 0));
+}
+
+#[test]
+fn clear_and_reset() {
+    let mut h = Harness::new(10, 2);
+    h.feed(b"a\r\nb\r\n\x1b[41mred");
+    h.term.clear_and_reset();
+    assert_eq!(h.term.grid.scrollback_len(), 0);
+    assert_eq!(h.screen(), "\n\n");
+    assert_eq!(h.cursor(), (0, 0));
+    h.feed(b"x");
+    assert_eq!(h.attrs(), "");
+}
+
+#[test]
+fn scrollback_is_capped_and_keeps_newest_lines() {
+    use moterm_core::terminal::SCROLLBACK_LIMIT;
+    let mut h = Harness::new(6, 2);
+    for i in 0..SCROLLBACK_LIMIT + 10 {
+        h.feed_str(&format!("{i}\r\n"));
+    }
+    assert_eq!(h.term.grid.scrollback_len(), SCROLLBACK_LIMIT);
+    assert!(h.scrollback().starts_with("9\n10\n"));
+    assert_eq!(h.screen(), format!("{}\n\n", SCROLLBACK_LIMIT + 9));
+}
+
+#[test]
+fn scrollback_limit_is_per_terminal() {
+    use moterm_core::terminal::{Pos, Terminal};
+    let mut h = Harness::new(6, 2);
+    h.term = Terminal::with_scrollback(6, 2, 0);
+    h.feed_str("a\r\nb\r\nc\r\n");
+    assert_eq!(h.term.grid.scrollback_len(), 0);
+
+    h.term = Terminal::with_scrollback(6, 2, 3);
+    h.feed_str("ab\r\ncd\r\nef\r\ngh\r\n");
+    assert_eq!(h.scrollback(), "ab\ncd\nef\n");
+    // Blank tails are dropped in scrollback but still read as blanks
+    assert_eq!(h.term.grid.line(0).unwrap().cells.len(), 2);
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 1, col: 5 });
+    assert_eq!(h.term.selection_text().as_deref(), Some("ab\ncd"));
+    h.term.set_scrollback_limit(1);
+    h.feed_str("ij\r\n");
+    assert_eq!(h.scrollback(), "gh\n");
+}
+
+#[test]
+fn evicted_and_settled_rows() {
+    use moterm_core::terminal::Terminal;
+    let mut h = Harness::new(6, 2);
+    h.term = Terminal::with_scrollback(6, 2, 3);
+    h.feed_str("a\r\nb\r\nc\r\nd\r\ne\r\n");
+    assert_eq!((h.term.evicted_rows, h.term.settled_rows()), (1, 3));
+    // The alternate screen's own history isn't settled
+    h.term.alt_history = 5;
+    h.feed_str("\x1b[?1049h\x1b[Hx\r\ny\r\nz\r\n");
+    assert_eq!((h.term.evicted_rows, h.term.settled_rows()), (3, 1));
+    h.feed_str("\x1b[?1049l\x1b[3J");
+    assert_eq!((h.term.evicted_rows, h.term.settled_rows()), (4, 0));
+}
+
+#[test]
+fn erase_display_3_clears_only_scrollback() {
+    let mut h = Harness::new(5, 2);
+    h.feed(b"1\r\n2\r\n3\r\n4");
+    h.term.set_view_scroll(1);
+    h.feed(b"\x1b[3J");
+    assert_eq!(h.scrollback(), "");
+    assert_eq!(h.screen(), "3\n4\n");
+    assert_eq!(h.term.view_scroll, 0);
+    assert!(h.term.scrollback_cleared);
+}
+
+#[test]
+fn line_feed_scrolls_only_the_region() {
+    let mut h = Harness::new(5, 4);
+    h.feed(b"a\r\nb\r\nc\r\nstat");
+    h.feed(b"\x1b[1;3r\x1b[3;1H\r\nd");
+    assert_eq!(h.screen(), "b\nc\nd\nstat\n");
+    assert_eq!(h.scrollback(), "");
+    // Below the region the cursor stops at the last row without scrolling
+    h.feed(b"\x1b[4;1H\r\n\r\n");
+    assert_eq!(h.screen(), "b\nc\nd\nstat\n");
+}
+
+#[test]
+fn insert_and_delete_lines_stay_in_region() {
+    let mut h = Harness::new(5, 5);
+    h.feed(b"a\r\nb\r\nc\r\nd\r\ne");
+    h.feed(b"\x1b[2;4r\x1b[2;3H\x1b[L");
+    assert_eq!(h.screen(), "a\n\nb\nc\ne\n");
+    assert_eq!(h.cursor(), (1, 0));
+    h.feed(b"\x1b[2M");
+    assert_eq!(h.screen(), "a\nc\n\n\ne\n");
+    // Outside the region IL/DL do nothing
+    h.feed(b"\x1b[5;1H\x1b[M\x1b[1;1H\x1b[L");
+    assert_eq!(h.screen(), "a\nc\n\n\ne\n");
+    assert_eq!(h.scrollback(), "");
+}
+
+#[test]
+fn scroll_up_and_down_use_region() {
+    let mut h = Harness::new(5, 4);
+    h.feed(b"a\r\nb\r\nc\r\nd");
+    h.feed(b"\x1b[2;3r\x1b[S");
+    assert_eq!(h.screen(), "a\nc\n\nd\n");
+    h.feed(b"\x1b[2T");
+    assert_eq!(h.screen(), "a\n\n\nd\n");
+    assert_eq!(h.scrollback(), "");
+}
@@ -0,0 +1,65 @@
+use moterm_core::harness::Harness;
+use moterm_core::search::{MatchSpan, SearchState};
+use moterm_core::terminal::Pos;
+
+fn search(h: &Harness, query: &str) -> SearchState {
+    let mut s = SearchState::new();
+    s.query = query.into();
+    s.search(&h.term);
+    s
+}
+
+#[test]
+fn finds_matches_case_insensitively() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("Foo foo\r\nfOO");
+    let s = search(&h, "foo");
+    let starts: Vec<Pos> = s.matches.iter().map(|m| m.start).collect();
+    assert_eq!(
+        starts,
+        [Pos { row: 0, col: 0 }, Pos { row: 0, col: 4 }, Pos { row: 1, col: 0 }]
+    );
+}
+
+#[test]
+fn match_spans_a_soft_wrap() {
+    let mut h = Harness::new(4, 3);
+    h.feed_str("xxhello");
+    let s = search(&h, "hello");
+    assert_eq!(s.matches.len(), 1);
+    let m = &s.matches[0];
+    assert_eq!((m.start, m.end), (Pos { row: 0, col: 2 }, Pos { row: 1, col: 2 }));
+    assert!(s.is_highlighted(1, 0));
+    assert!(!s.is_highlighted(1, 3));
+}
+
+#[test]
+fn hard_newline_does_not_join_lines() {
+    let mut h = Harness::new(4, 3);
+    h.feed_str("xxhe\r\nllo");
+    assert!(search(&h, "hello").matches.is_empty());
+}
+
+#[test]
+fn columns_count_cells_not_bytes() {
+    let mut h = Harness::new(10, 1);
+    h.feed_str("é→ab");
+    let s = search(&h, "ab");
+    assert_eq!(s.matches[0].start, Pos { row: 0, col: 2 });
+}
+
+#[test]
+fn row_spans_cover_only_visible_rows() {
+    let mut h = Harness::new(4, 4);
+    h.feed_str("ab\r\nxxhello\r\nab");
+    let mut s = search(&h, "hello");
+    s.matches.extend(search(&h, "ab").matches);
+    s.matches.sort_by_key(|m| (m.start.row, m.start.col));
+    s.current = 1;
+    let span = |start, end, current| MatchSpan { start, end, current };
+    assert_eq!(
+        s.row_spans(1, 2, 4),
+        [vec![span(2, 4, true)], vec![span(0, 3, true)]]
+    );
+    assert_eq!(s.row_spans(3, 1, 4), [vec![span(0, 2, false)]]);
+}
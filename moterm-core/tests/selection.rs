@@ -0,0 +1,176 @@
+use moterm_core::harness::Harness;
+use moterm_core::terminal::{Pos, SCROLLBACK_LIMIT};
+
+fn fill_scrollback(h: &mut Harness) {
+    for i in 0..SCROLLBACK_LIMIT + 2 {
+        h.feed_str(&format!("{i}\r\n"));
+    }
+}
+
+#[test]
+fn selection_follows_text_when_scrollback_is_trimmed() {
+    let mut h = Harness::new(10, 2);
+    fill_scrollback(&mut h);
+    let row = h.term.grid.scrollback_len() - 1;
+    h.term.start_selection(Pos { row, col: 0 });
+    h.term.update_selection(Pos { row, col: 3 });
+    let before = h.term.selection_text();
+    assert_eq!(h.term.grid.scrollback_len(), SCROLLBACK_LIMIT);
+    h.feed_str("more\r\nlines\r\n");
+    assert_eq!(h.term.selection_text(), before);
+}
+
+#[test]
+fn selection_cleared_when_its_rows_are_evicted() {
+    let mut h = Harness::new(10, 2);
+    fill_scrollback(&mut h);
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 1, col: 3 });
+    h.feed_str("x\r\n");
+    assert!(h.term.selection.is_none());
+}
+
+#[test]
+fn clearing_scrollback_shifts_screen_selection() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("a\r\nb\r\nc\r\nkeep");
+    let row = h.term.grid.scrollback_len() + 1;
+    h.term.start_selection(Pos { row, col: 0 });
+    h.term.update_selection(Pos { row, col: 3 });
+    h.term.clear_scrollback();
+    assert_eq!(h.term.selection_text().as_deref(), Some("keep"));
+}
+
+#[test]
+fn erase_display_3_keeps_screen_selection() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("a\r\nb\r\nc\r\nkeep");
+    let row = h.term.grid.scrollback_len() + 1;
+    h.term.start_selection(Pos { row, col: 0 });
+    h.term.update_selection(Pos { row, col: 3 });
+    h.feed_str("\x1b[3J");
+    assert_eq!(h.term.selection_text().as_deref(), Some("keep"));
+    h.feed_str("\x1b[2J");
+    assert!(h.term.selection.is_none());
+}
+
+#[test]
+fn overwriting_selected_cells_clears_selection() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("50%");
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 0, col: 2 });
+    h.feed_str("\r60%");
+    assert!(h.term.selection.is_none());
+}
+
+#[test]
+fn rewriting_identical_text_keeps_selection() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("same");
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 0, col: 3 });
+    h.feed_str("\rsame");
+    assert_eq!(h.term.selection_text().as_deref(), Some("same"));
+}
+
+#[test]
+fn erasing_selected_line_clears_selection() {
+    let mut h = Harness::new(10, 2);
+    h.feed_str("one\r\ntwo");
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 0, col: 2 });
+    // Erase the unselected line: selection stays
+    h.feed(b"\x1b[2K");
+    assert!(h.term.selection.is_some());
+    h.feed(b"\x1b[A\x1b[2K");
+    assert!(h.term.selection.is_none());
+}
+
+#[test]
+fn copy_joins_soft_wrapped_rows() {
+    let mut h = Harness::new(4, 3);
+    h.feed_str("ab  cdef\r\nxy");
+    h.term.start_selection(Pos { row: 0, col: 0 });
+    h.term.update_selection(Pos { row: 2, col: 3 });
+    assert_eq!(h.term.selection_text().as_deref(), Some("ab  cdef\nxy"));
+}
+
+#[test]
+fn copy_keeps_wide_chars_whole_across_wraps() {
+    let mut h = Harness::new(5, 3);
+    h.feed_str("ab中文字\r\nx");
+    // 文 doesn't fit in the last column of row 0 and wraps; start on 中's right half
+    h.term.start_selection(Pos { row: 0, col: 3 });
+    h.term.update_selection(Pos { row: 2, col: 0 });
+    assert_eq!(h.term.selection_text().as_deref(), Some("中文字\nx"));
+}
+
+#[test]
+fn keyboard_extension_scrolls_to_keep_focus_visible() {
+    let mut h = Harness::new(4, 2);
+    h.feed_str("a\r\nb\r\nc\r\nd\r\ne");
+    // Rows: a b c (scrollback), d e (screen)
+    h.term.start_selection(Pos { row: 4, col: 0 });
+    h.term.extend_selection(-3, 0);
+    assert_eq!(h.term.selection.as_ref().unwrap().focus, Pos { row: 1, col: 0 });
+    assert_eq!(h.term.visible_range(), (1, 2));
+    h.term.extend_selection(0, -1);
+    assert_eq!(h.term.selection.as_ref().unwrap().focus, Pos { row: 0, col: 3 });
+    assert_eq!(h.term.view_scroll, 3);
+    h.term.extend_selection(-10, 0);
+    assert_eq!(h.term.selection.as_ref().unwrap().focus, Pos { row: 0, col: 0 });
+    h.term.extend_selection(2, 0);
+    assert_eq!(h.term.visible_range(), (1, 2));
+    assert_eq!(h.term.selection_text().as_deref(), Some("c\nd\ne"));
+}
+
+#[test]
+fn line_selection_covers_wrapped_rows_up_to_the_content() {
+    let mut h = Harness::new(5, 4);
+    h.feed_str("abcdefg\r\nxy");
+    h.term.select_line_at_view(1);
+    let (a, b) = h.term.selection.as_ref().unwrap().normalized();
+    assert_eq!((a, b), (Pos { row: 0, col: 0 }, Pos { row: 1, col: 1 }));
+    assert_eq!(h.term.selection_text().as_deref(), Some("abcdefg"));
+    h.term.select_line_at_view(2);
+    assert_eq!(h.term.selection.as_ref().unwrap().normalized().1, Pos { row: 2, col: 1 });
+}
+
+#[test]
+fn double_click_on_blanks_selects_the_run() {
+    let mut h = Harness::new(12, 2);
+    h.feed_str("ab    cd");
+    h.term.select_word_at_view(0, 4);
+    let (a, b) = h.term.selection.as_ref().unwrap().normalized();
+    assert_eq!((a.col, b.col), (2, 5));
+    h.term.select_word_at_view(0, 0);
+    assert_eq!(h.term.selection_text().as_deref(), Some("ab"));
+}
+
+#[test]
+fn expand_selection_grows_by_semantic_steps() {
+    let mut h = Harness::new(40, 3);
+    h.feed_str("$ echo (\"foo.bar baz\") x");
+    h.term.select_word_at_view(0, 10);
+    let mut steps = Vec::new();
+    for _ in 0..5 {
+        h.term.expand_selection();
+        steps.push(h.term.selection_text().unwrap_or_default());
+    }
+    assert_eq!(
+        steps,
+        [
+            "foo.bar baz",
+            "\"foo.bar baz\"",
+            "(\"foo.bar baz\")",
+            "$ echo (\"foo.bar baz\") x",
+            "$ echo (\"foo.bar baz\") x",
+        ]
+    );
+
+    h.feed_str("\r\ncat src/a.rs:12");
+    h.term.select_word_at_view(1, 8);
+    h.term.expand_selection();
+    assert_eq!(h.term.selection_text().as_deref(), Some("src/a.rs:12"));
+}
@@ -0,0 +1,63 @@
+use moterm_core::url::{detect_links, Link, LinkKind};
+
+fn targets(line: &str) -> Vec<(String, LinkKind)> {
+    detect_links(line)
+        .into_iter()
+        .map(|Link { target, kind, .. }| (target, kind))
+        .collect()
+}
+
+fn path(line: Option<usize>, column: Option<usize>) -> LinkKind {
+    LinkKind::Path { line, column }
+}
+
+#[test]
+fn urls_strip_trailing_punctuation() {
+    assert_eq!(
+        targets("see https://x.com/a?b=1)."),
+        vec![("https://x.com/a?b=1".to_string(), LinkKind::Url)]
+    );
+}
+
+#[test]
+fn paths_and_file_urls() {
+    assert_eq!(
+        targets("see /tmp/a.txt, ~/x/y. file:///a%20b/c and/or"),
+        vec![
+            ("/tmp/a.txt".to_string(), path(None, None)),
+            ("~/x/y".to_string(), path(None, None)),
+            ("/a b/c".to_string(), path(None, None)),
+        ]
+    );
+}
+
+#[test]
+fn file_line_column_references() {
+    assert_eq!(
+        targets("--> src/main.rs:12:5 at foo.rs:3 12:30:45"),
+        vec![
+            ("src/main.rs".to_string(), path(Some(12), Some(5))),
+            ("foo.rs".to_string(), path(Some(3), None)),
+        ]
+    );
+}
+
+#[test]
+fn emails() {
+    assert_eq!(
+        targets("Author: Jane <jane.doe+x@example.co.uk> npm@8.1.0"),
+        vec![("jane.doe+x@example.co.uk".to_string(), LinkKind::Email)]
+    );
+}
+
+#[test]
+fn bare_hosts_get_http_scheme() {
+    assert_eq!(
+        targets("localhost:3000/ 127.0.0.1:8080/p www.example.com. localhost 999.1.1.1"),
+        vec![
+            ("http://localhost:3000/".to_string(), LinkKind::Url),
+            ("http://127.0.0.1:8080/p".to_string(), LinkKind::Url),
+            ("http://www.example.com".to_string(), LinkKind::Url),
+        ]
+    );
+}
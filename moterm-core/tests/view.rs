@@ -0,0 +1,64 @@
+use moterm_core::input::{Key, Modifiers};
+use moterm_core::mouse;
+use moterm_core::{Host, MotermView};
+
+#[derive(Default)]
+struct FakeHost {
+    written: Vec<u8>,
+    size: Option<(u16, u16)>,
+    title: Option<String>,
+    bells: usize,
+}
+
+impl Host for FakeHost {
+    fn write(&mut self, bytes: &[u8]) {
+        self.written.extend_from_slice(bytes);
+    }
+    fn resize(&mut self, cols: u16, rows: u16) {
+        self.size = Some((cols, rows));
+    }
+    fn set_title(&mut self, title: &str) {
+        self.title = Some(title.into());
+    }
+    fn bell(&mut self) {
+        self.bells += 1;
+    }
+}
+
+#[test]
+fn feed_passes_replies_title_and_bell_to_host() {
+    let mut host = FakeHost::default();
+    let mut view = MotermView::new(10, 3);
+    view.feed(b"\x1b]2;vim\x07\x07\x1b[6n", &mut host);
+    assert_eq!(host.written, b"\x1b[1;1R");
+    assert_eq!(host.title.as_deref(), Some("vim"));
+    assert_eq!(host.bells, 1);
+    view.resize(20, 5, &mut host);
+    assert_eq!(host.size, Some((20, 5)));
+}
+
+#[test]
+fn input_is_encoded_for_the_program() {
+    let mut host = FakeHost::default();
+    let mut view = MotermView::new(10, 3);
+    let ctrl = Modifiers { ctrl: true, ..Modifiers::default() };
+    let alt = Modifiers { alt: true, ..Modifiers::default() };
+    assert!(view.key(Key::Char('c'), ctrl, &mut host));
+    assert!(!view.key(Key::Char('c'), Modifiers::default(), &mut host));
+    view.key(Key::Up, ctrl, &mut host);
+    view.text("é", alt, &mut host);
+    view.feed(b"\x1b[?2004h", &mut host);
+    view.paste("a\nb", &mut host);
+    assert_eq!(host.written, "\x03\x1b[1;5A\x1bé\x1b[200~a\nb\x1b[201~".as_bytes());
+}
+
+#[test]
+fn mouse_is_reported_only_while_tracked() {
+    let mut host = FakeHost::default();
+    let mut view = MotermView::new(10, 3);
+    let none = Modifiers::default();
+    assert!(!view.mouse(mouse::BUTTON_LEFT, 1, 2, true, none, &mut host));
+    view.feed(b"\x1b[?1000h\x1b[?1006h", &mut host);
+    assert!(view.mouse(mouse::BUTTON_LEFT, 1, 2, true, none, &mut host));
+    assert_eq!(host.written, b"\x1b[<0;3;2M");
+}
@@ -0,0 +1,22 @@
+# moterm shell integration for bash: OSC 7 (working directory) and
+# OSC 133 (prompt marks). Installed by `moterm --install-shell-integration`.
+[[ "$TERM_PROGRAM" == "moterm" && -z "$MOTERM_SHELL_INTEGRATION" ]] || return 0
+MOTERM_SHELL_INTEGRATION=1
+
+_moterm_prompt() {
+  local ret=$?
+  printf '\e]133;D;%s\a\e]7;file://%s%s\a\e]133;A\a' "$ret" "$HOSTNAME" "$PWD"
+  return $ret
+}
+
+# DEBUG fires before every command; only the first one after a prompt
+# starts the output
+_moterm_preexec() {
+  [[ -n "$_moterm_armed" && -z "$COMP_LINE" && "$BASH_COMMAND" != _moterm_prompt* ]] || return
+  _moterm_armed=
+  printf '\e]133;C\a'
+}
+
+PROMPT_COMMAND="_moterm_prompt${PROMPT_COMMAND:+; $PROMPT_COMMAND}; _moterm_armed=1"
+trap '_moterm_preexec' DEBUG
+PS1="$PS1\[\e]133;B\a\]"
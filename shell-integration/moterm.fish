@@ -0,0 +1,20 @@
+# moterm shell integration for fish: OSC 7 (working directory) and
+# OSC 133 (prompt marks). Installed by `moterm --install-shell-integration`.
+if test "$TERM_PROGRAM" = moterm; and not set -q MOTERM_SHELL_INTEGRATION
+    set -g MOTERM_SHELL_INTEGRATION 1
+
+    function __moterm_prompt --on-event fish_prompt
+        printf '\e]133;D;%s\a\e]7;file://%s%s\a\e]133;A\a' $status (hostname) "$PWD"
+    end
+
+    function __moterm_preexec --on-event fish_preexec
+        printf '\e]133;C\a'
+    end
+
+    # Mark where the command line starts, after the user's prompt
+    functions -c fish_prompt __moterm_user_prompt
+    function fish_prompt
+        __moterm_user_prompt
+        printf '\e]133;B\a'
+    end
+end
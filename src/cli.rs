@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use crate::i18n::{tr, Msg};
+
+/// Command-line options.
+#[derive(Debug)]
+pub struct Args {
+    /// Record the session to an asciinema .cast file from startup
+    pub record: Option<PathBuf>,
+    /// Play back a .cast file instead of starting a shell
+    pub replay: Option<PathBuf>,
+    /// Playback speed multiplier for --replay
+    pub speed: f64,
+    /// Log level override (error/warn/info/debug/trace); else `MOTERM_LOG`
+    pub log_level: Option<log::LevelFilter>,
+    /// Also write logs to ~/Library/Logs/moterm.log
+    pub log_file: bool,
+    /// Config file instead of ~/.config/moterm/config.toml
+    pub config: Option<PathBuf>,
+    /// Directory the shell starts in
+    pub working_directory: Option<PathBuf>,
+    /// `-e`: program and arguments to run instead of the shell
+    pub command: Vec<String>,
+    /// Keep the first window open after its program exits
+    pub hold: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            record: None,
+            replay: None,
+            speed: 1.0,
+            log_level: None,
+            log_file: false,
+            config: None,
+            working_directory: None,
+            command: Vec::new(),
+            hold: false,
+        }
+    }
+}
+
+pub enum Command {
+    Run(Args),
+    Version,
+    Help,
+    InstallShellIntegration,
+}
+
+pub fn parse() -> Result<Command, String> {
+    let mut args = Args::default();
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "-v" | "--version" => return Ok(Command::Version),
+            "-h" | "--help" => return Ok(Command::Help),
+            "--install-shell-integration" => return Ok(Command::InstallShellIntegration),
+            "--record" => {
+                args.record = Some(PathBuf::from(value(&mut it, &arg)?));
+            }
+            "--replay" => {
+                args.replay = Some(PathBuf::from(value(&mut it, &arg)?));
+            }
+            "--speed" => {
+                let v = value(&mut it, &arg)?;
+                args.speed = v.parse().map_err(|_| invalid(&arg, &v))?;
+            }
+            "--log-level" => {
+                let v = value(&mut it, &arg)?;
+                args.log_level = Some(v.parse().map_err(|_| invalid(&arg, &v))?);
+            }
+            "--log-file" => args.log_file = true,
+            "--config" => {
+                args.config = Some(PathBuf::from(value(&mut it, &arg)?));
+            }
+            "--working-directory" => {
+                args.working_directory = Some(PathBuf::from(value(&mut it, &arg)?));
+            }
+            "--hold" => args.hold = true,
+            // Everything after -e belongs to the command
+            "-e" | "--command" => {
+                args.command = it.by_ref().collect();
+                if args.command.is_empty() {
+                    return Err(tr(Msg::CliMissingValue).replace("{arg}", &arg));
+                }
+            }
+            other => return Err(tr(Msg::CliUnknownArg).replace("{arg}", other)),
+        }
+    }
+    Ok(Command::Run(args))
+}
+
+fn value(it: &mut impl Iterator<Item = String>, arg: &str) -> Result<String, String> {
+    it.next()
+        .ok_or_else(|| tr(Msg::CliMissingValue).replace("{arg}", arg))
+}
+
+fn invalid(arg: &str, value: &str) -> String {
+    tr(Msg::CliInvalidValue)
+        .replace("{arg}", arg)
+        .replace("{value}", value)
+}
+
+pub fn print_help() {
+    println!("moterm {} — {}", env!("CARGO_PKG_VERSION"), tr(Msg::HelpAbout));
+    println!();
+    println!("USAGE: moterm [OPTIONS] [-e COMMAND [ARGS...]]");
+    println!();
+    println!("OPTIONS:");
+    println!("  --record <FILE>  {}", tr(Msg::HelpRecord));
+    println!("  --replay <FILE>  {}", tr(Msg::HelpReplay));
+    println!("  --speed <N>      {}", tr(Msg::HelpSpeed));
+    println!("  --log-level <L>  {}", tr(Msg::HelpLogLevel));
+    println!("  --log-file       {}", tr(Msg::HelpLogFile));
+    println!("  --config <FILE>  {}", tr(Msg::HelpConfig));
+    println!("  --working-directory <DIR>");
+    println!("                   {}", tr(Msg::HelpWorkingDirectory));
+    println!("  -e, --command <COMMAND> [ARGS...]");
+    println!("                   {}", tr(Msg::HelpCommand));
+    println!("  --hold           {}", tr(Msg::HelpHold));
+    println!("  --install-shell-integration");
+    println!("                   {}", tr(Msg::HelpShellIntegration));
+    println!("  -v, --version    {}", tr(Msg::HelpVersion));
+    println!("  -h, --help       {}", tr(Msg::HelpHelp));
+    println!();
+    println!("CONFIG: ~/.config/moterm/config.toml");
+    println!("REPO:   https://github.com/longzhi/moterm");
+}
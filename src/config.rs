@@ -1,14 +1,31 @@
+use crate::color::{Palette, Rgb};
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Most files (the config and its imports) checked for edits.
+const MAX_CONFIG_FILES: usize = 32;
 
 #[derive(Deserialize, Clone, Debug, Default)]
 #[serde(default)]
 pub struct Config {
+    /// UI language: "auto" (system locale), "en" or "zh"
+    pub language: String,
     pub font: FontConfig,
     pub window: WindowConfig,
     pub cursor: CursorConfig,
     pub colors: ColorConfig,
+    pub editor: EditorConfig,
+    pub links: LinksConfig,
+    pub bell: BellConfig,
+    pub privacy: PrivacyConfig,
+    pub logging: LoggingConfig,
+    pub scrollback: ScrollbackConfig,
+    pub scrolling: ScrollingConfig,
+    pub mouse: MouseConfig,
+    pub env: EnvConfig,
+    pub compat: CompatConfig,
+    pub hints: Vec<HintConfig>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -16,6 +33,16 @@ pub struct Config {
 pub struct FontConfig {
     pub family: Option<String>,
     pub size: f32,
+    /// Sizes for Cmd+Ctrl+1/2/3
+    pub presets: FontPresets,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FontPresets {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -23,19 +50,278 @@ pub struct FontConfig {
 pub struct WindowConfig {
     pub width: u32,
     pub height: u32,
+    /// Native macOS tabs: "automatic" (System Settings), "preferred", "disallowed"
+    pub tabbing: String,
+    /// Resize in whole-cell steps so the grid always fills the window
+    pub resize_increments: bool,
+    /// Cmd+Enter fullscreen: "native" (own Space, animated) or "simple"
+    pub fullscreen: String,
+    /// Title when the app hasn't set one, e.g. "{command} — {cwd}"
+    pub title_format: String,
+    /// Reserve a bottom row for moterm's own status (search bar, scroll
+    /// position, tabs, bell); the shell gets one row less
+    pub status_line: bool,
+    /// Reopen the windows and tabs (directories and titles) open at quit
+    pub restore_session: bool,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct CursorConfig {
     pub style: String,
+    pub blink: bool,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+/// `#rrggbb` values; unset ones come from the theme, then the built-in palette.
+/// Theme files in `~/.config/moterm/themes/` use the same keys.
+#[derive(Deserialize, Clone, Debug, Default)]
 #[serde(default)]
 pub struct ColorConfig {
-    pub background: String,
-    pub foreground: String,
+    /// Name of a theme file, e.g. "nord" for `themes/nord.toml`
+    pub theme: Option<String>,
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub cursor: Option<String>,
+    /// Text under a block cursor
+    pub cursor_text: Option<String>,
+    pub selection: Option<String>,
+    pub color0: Option<String>,
+    pub color1: Option<String>,
+    pub color2: Option<String>,
+    pub color3: Option<String>,
+    pub color4: Option<String>,
+    pub color5: Option<String>,
+    pub color6: Option<String>,
+    pub color7: Option<String>,
+    pub color8: Option<String>,
+    pub color9: Option<String>,
+    pub color10: Option<String>,
+    pub color11: Option<String>,
+    pub color12: Option<String>,
+    pub color13: Option<String>,
+    pub color14: Option<String>,
+    pub color15: Option<String>,
+    /// SGR 1 with colors 0–7 draws the bright variants (8–15)
+    pub draw_bold_text_with_bright_colors: bool,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct EditorConfig {
+    /// Command template for Cmd+click on `file:line:col` references.
+    /// Placeholders: {file}, {line}, {col}. Unset = open with default app.
+    pub command: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LinksConfig {
+    /// URL schemes opened on Cmd+click without a confirmation dialog
+    pub allowed_schemes: Vec<String>,
+    /// "Search web for selection" template; {query} is URL-encoded
+    pub search_engine: String,
+    /// Faint dotted underline under every URL and path on screen
+    pub underline: bool,
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            allowed_schemes: ["http", "https", "mailto", "file"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            search_engine: "https://www.google.com/search?q={query}".to_string(),
+            underline: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ScrollbackConfig {
+    /// Rows of history per shell: 0 keeps none, -1 keeps everything
+    pub lines: i64,
+}
+
+impl Default for ScrollbackConfig {
+    fn default() -> Self {
+        Self { lines: crate::terminal::SCROLLBACK_LIMIT as i64 }
+    }
+}
+
+impl ScrollbackConfig {
+    pub fn limit(&self) -> usize {
+        usize::try_from(self.lines).unwrap_or(usize::MAX)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ScrollingConfig {
+    /// Jump to the bottom when new output arrives while scrolled back
+    pub auto_scroll_on_output: bool,
+    /// Jump to the bottom when a key sends input while scrolled back
+    pub scroll_to_bottom_on_keypress: bool,
+    /// Timestamp gutter: "off", "relative" or "absolute" (Cmd+Shift+T cycles)
+    pub timestamps: String,
+    /// "shift": only Shift+PageUp/PageDown/Home/End scroll the view; "pager":
+    /// the plain keys do too while scrolled back
+    pub keyboard: String,
+    /// Rows scrolled off the alternate screen kept for Shift+PageUp; 0 keeps none
+    pub alt_screen_history: usize,
+    /// Scroll indicator on the right edge, with ticks at prompts and errors
+    pub indicator: bool,
+    /// Regexes marking a row as an error tick on the scroll indicator
+    pub error_patterns: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MouseConfig {
+    /// Max gap between clicks of a double/triple click; unset follows the system
+    pub double_click_ms: Option<u64>,
+    /// On the alternate screen without mouse reporting, send Left/Right for
+    /// horizontal scrolling (e.g. `less -S`)
+    pub horizontal_arrows: bool,
+    /// Modifier that turns the scroll wheel into font zoom: "cmd", "ctrl" or "off"
+    pub zoom_modifier: String,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            double_click_ms: None,
+            horizontal_arrows: false,
+            zoom_modifier: "cmd".into(),
+        }
+    }
+}
+
+impl Default for ScrollingConfig {
+    fn default() -> Self {
+        Self {
+            auto_scroll_on_output: false,
+            scroll_to_bottom_on_keypress: true,
+            timestamps: "off".into(),
+            keyboard: "shift".into(),
+            alt_screen_history: 0,
+            indicator: true,
+            error_patterns: vec![r"\berror\b".into(), r"\bFAILED\b".into(), "panicked at".into()],
+        }
+    }
+}
+
+/// Hide the window's content after a while without input.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Minutes without input before covering; 0 never covers
+    pub idle_minutes: u64,
+    /// "blur" (unreadable blocks) or "blank"
+    pub idle_cover: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Write each shell's output to a file in `directory`
+    pub session_logs: bool,
+    /// `~/` is expanded
+    pub directory: String,
+    /// "text" (escape sequences stripped) or "raw"
+    pub format: String,
+    /// Continue in a new file after this many megabytes
+    pub max_file_mb: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BellConfig {
+    /// Play the system beep
+    pub sound: bool,
+    /// Bounce the Dock icon when the window is in the background
+    pub bounce: bool,
+    /// Count background bells in a Dock badge until the window is focused
+    pub badge: bool,
+    /// Beep and bounce at most once per this many milliseconds; bells in
+    /// between still count towards the badge
+    pub min_interval_ms: u64,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            idle_minutes: 0,
+            idle_cover: "blur".to_string(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            session_logs: false,
+            directory: "~/moterm-logs".to_string(),
+            format: "text".to_string(),
+            max_file_mb: 10,
+        }
+    }
+}
+
+impl Default for BellConfig {
+    fn default() -> Self {
+        Self {
+            sound: true,
+            bounce: true,
+            badge: true,
+            min_interval_ms: 1000,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EnvConfig {
+    /// `TERM` for the shell; DA and XTGETTCAP replies follow it
+    #[serde(rename = "TERM")]
+    pub term: String,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            term: moterm_core::terminfo::DEFAULT_TERM.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CompatConfig {
+    /// NBSP and soft hyphen: "printable" (one cell each) or "collapse" (no cell)
+    pub invisible_chars: String,
+}
+
+impl Default for CompatConfig {
+    fn default() -> Self {
+        Self {
+            invisible_chars: "printable".into(),
+        }
+    }
+}
+
+/// `[[hints]]` entry: a regex plus what to do when it's Cmd+clicked.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HintConfig {
+    pub regex: String,
+    /// "open" (uses `url`), "copy", or "command" (uses `command`)
+    pub action: String,
+    /// URL template with {match}
+    pub url: Option<String>,
+    /// Command template with {match}
+    pub command: Option<String>,
 }
 
 // Config derives Default since all fields have Default impls
@@ -45,6 +331,17 @@ impl Default for FontConfig {
         Self {
             family: None,
             size: 14.0,
+            presets: FontPresets::default(),
+        }
+    }
+}
+
+impl Default for FontPresets {
+    fn default() -> Self {
+        Self {
+            small: 12.0,
+            medium: 18.0,
+            large: 28.0,
         }
     }
 }
@@ -54,6 +351,12 @@ impl Default for WindowConfig {
         Self {
             width: 960,
             height: 600,
+            tabbing: "automatic".to_string(),
+            resize_increments: false,
+            fullscreen: "native".to_string(),
+            title_format: String::new(),
+            status_line: false,
+            restore_session: false,
         }
     }
 }
@@ -62,44 +365,110 @@ impl Default for CursorConfig {
     fn default() -> Self {
         Self {
             style: "block".to_string(),
+            blink: true,
         }
     }
 }
 
-impl Default for ColorConfig {
-    fn default() -> Self {
-        Self {
-            background: "#1e1e2e".to_string(),
-            foreground: "#cdd6f4".to_string(),
+impl ColorConfig {
+    /// The built-in palette, overridden by the theme, then by these colors.
+    pub fn palette(&self) -> Palette {
+        let mut palette = Palette::default();
+        if let Some(name) = &self.theme {
+            match load_theme(name) {
+                Ok(theme) => theme.apply(&mut palette),
+                Err(e) => log::warn!("加载主题 {name} 失败: {e}"),
+            }
         }
+        self.apply(&mut palette);
+        palette
     }
+
+    fn apply(&self, palette: &mut Palette) {
+        let ansi = [
+            &self.color0, &self.color1, &self.color2, &self.color3,
+            &self.color4, &self.color5, &self.color6, &self.color7,
+            &self.color8, &self.color9, &self.color10, &self.color11,
+            &self.color12, &self.color13, &self.color14, &self.color15,
+        ];
+        let slots = [
+            (&self.background, &mut palette.bg),
+            (&self.foreground, &mut palette.fg),
+            (&self.cursor, &mut palette.cursor),
+            (&self.cursor_text, &mut palette.cursor_text),
+            (&self.selection, &mut palette.selection),
+        ];
+        let slots = slots.into_iter().chain(ansi.into_iter().zip(palette.ansi.iter_mut()));
+        for (value, slot) in slots {
+            let Some(value) = value else { continue };
+            match Rgb::parse_hex(value) {
+                Some(rgb) => *slot = rgb,
+                None => log::warn!("无效的颜色: {value}"),
+            }
+        }
+    }
+}
+
+/// Read `themes/<name>.toml` from the config directory.
+fn load_theme(name: &str) -> Result<ColorConfig, String> {
+    let path = config_dir().join("themes").join(format!("{name}.toml"));
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))
 }
 
 impl Config {
-    pub fn load() -> Self {
-        let path = config_path();
+    /// Load `path`, or the default config file without one.
+    pub fn load(path: Option<&Path>) -> Self {
+        let path = path.map_or_else(config_path, Path::to_path_buf);
         if !path.exists() {
-            eprintln!("配置文件不存在，使用默认配置: {}", path.display());
+            log::info!("配置文件不存在，使用默认配置: {}", path.display());
             return Config::default();
         }
-        match fs::read_to_string(&path) {
-            Ok(content) => match toml::from_str(&content) {
-                Ok(cfg) => {
-                    eprintln!("已加载配置: {}", path.display());
-                    cfg
-                }
-                Err(e) => {
-                    eprintln!("配置文件解析失败: {e}，使用默认配置");
-                    Config::default()
-                }
-            },
+        match Self::read(&path) {
+            Ok(cfg) => {
+                log::info!("已加载配置: {}", path.display());
+                cfg
+            }
             Err(e) => {
-                eprintln!("读取配置失败: {e}，使用默认配置");
+                log::warn!("{e}，使用默认配置");
                 Config::default()
             }
         }
     }
 
+    /// Re-read the config for a live reload. Unlike [`Config::load`], a
+    /// broken file is an error so the running config can be kept.
+    pub fn reload(path: Option<&Path>) -> Result<Self, String> {
+        let path = path.map_or_else(config_path, Path::to_path_buf);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Self::read(&path)
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let mut table = read_table(path, &mut Vec::new())?;
+        interpolate(&mut table);
+        toml::Value::Table(table).try_into().map_err(|e| format!("配置文件解析失败: {e}"))
+    }
+
+    /// When the config file or one of its imports was last modified, to
+    /// notice edits.
+    pub fn modified(path: Option<&Path>) -> Option<std::time::SystemTime> {
+        let mut files = vec![path.map_or_else(config_path, Path::to_path_buf)];
+        let mut i = 0;
+        while i < files.len() && files.len() < MAX_CONFIG_FILES {
+            let imports = parse_file(&files[i]).map(|t| import_paths(&files[i], t.get("import"))).unwrap_or_default();
+            for import in imports {
+                if !files.contains(&import) {
+                    files.push(import);
+                }
+            }
+            i += 1;
+        }
+        files.iter().filter_map(|f| fs::metadata(f).and_then(|m| m.modified()).ok()).max()
+    }
+
     pub fn initial_cursor_style(&self) -> crate::terminal::CursorStyle {
         match self.cursor.style.as_str() {
             "beam" | "bar" => crate::terminal::CursorStyle::Beam,
@@ -109,9 +478,177 @@ impl Config {
     }
 }
 
-fn config_path() -> PathBuf {
+/// `path` with the files in its `import` list merged in underneath: the
+/// imports in order, each overriding keys of the ones before, then the
+/// file's own keys. `chain` holds the files importing this one.
+fn read_table(path: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Table, String> {
+    if chain.iter().any(|p| p == path) {
+        return Err(format!("配置文件循环导入: {}", path.display()));
+    }
+    let mut own = parse_file(path)?;
+    let mut merged = toml::Table::new();
+    for import in import_paths(path, own.remove("import").as_ref()) {
+        if !import.exists() {
+            log::warn!("导入的配置文件不存在: {}", import.display());
+            continue;
+        }
+        chain.push(path.to_path_buf());
+        let imported = read_table(&import, chain);
+        chain.pop();
+        merge(&mut merged, imported?);
+    }
+    merge(&mut merged, own);
+    Ok(merged)
+}
+
+fn parse_file(path: &Path) -> Result<toml::Table, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取配置失败: {}: {e}", path.display()))?;
+    toml::from_str(&content).map_err(|e| format!("配置文件解析失败: {}: {e}", path.display()))
+}
+
+/// Files named by an `import` value (a string or list of strings):
+/// variables are expanded, `~/` is the home directory and relative paths
+/// start at `path`'s directory.
+fn import_paths(path: &Path, import: Option<&toml::Value>) -> Vec<PathBuf> {
+    let names: Vec<&str> = match import {
+        Some(toml::Value::String(s)) => vec![s],
+        Some(toml::Value::Array(list)) => list.iter().filter_map(toml::Value::as_str).collect(),
+        Some(other) => {
+            log::warn!("import 应为文件列表: {other}");
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    names
+        .into_iter()
+        .map(|name| {
+            let name = expand_env(name);
+            match (name.strip_prefix("~/"), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => dir.join(&name),
+            }
+        })
+        .collect()
+}
+
+/// Path and command values that expand environment variables, as
+/// (table, key); a list of tables such as `[[hints]]` expands in each.
+/// Everything else, regexes in particular, is taken literally.
+const EXPANDED: &[(&str, &str)] = &[("logging", "directory"), ("editor", "command"), ("hints", "command")];
+
+/// Expand environment variables in the [`EXPANDED`] values, so one config
+/// works for several users and machines. `import` expands on its own.
+fn interpolate(table: &mut toml::Table) {
+    for &(section, key) in EXPANDED {
+        let tables: Vec<&mut toml::Table> = match table.get_mut(section) {
+            Some(toml::Value::Table(t)) => vec![t],
+            Some(toml::Value::Array(list)) => list.iter_mut().filter_map(toml::Value::as_table_mut).collect(),
+            _ => continue,
+        };
+        for t in tables {
+            if let Some(toml::Value::String(s)) = t.get_mut(key) {
+                *s = expand_env(s);
+            }
+        }
+    }
+}
+
+/// Replace `${NAME}` with the environment variable, or `${NAME:-default}`
+/// with `default` when it is unset or empty. An unset variable without a
+/// default expands to nothing; `$${` is a literal `${` and an unclosed
+/// `${` is kept as written.
+fn expand_env(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            out.push_str(before);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let inner = &rest[start + 2..start + len];
+        let (name, default) = match inner.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner, None),
+        };
+        match (std::env::var(name).ok().filter(|v| !v.is_empty()), default) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => log::warn!("配置中的环境变量未设置: {name}"),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Merge `over` into `base`: tables merge key by key, anything else
+/// (including arrays such as `[[hints]]`) replaces what was there.
+fn merge(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// `~/.config/moterm`
+pub fn config_dir() -> PathBuf {
     dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("~/.config"))
         .join("moterm")
-        .join("config.toml")
+}
+
+fn config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_set_and_unset_variables() {
+        std::env::set_var("MOTERM_TEST_DIR", "/tmp/moterm");
+        std::env::remove_var("MOTERM_TEST_UNSET");
+        assert_eq!(expand_env("${MOTERM_TEST_DIR}/logs"), "/tmp/moterm/logs");
+        assert_eq!(expand_env("${MOTERM_TEST_UNSET}/logs"), "/logs");
+        assert_eq!(expand_env("${MOTERM_TEST_UNSET:-~/logs}"), "~/logs");
+        assert_eq!(expand_env("${MOTERM_TEST_DIR:-~/logs}"), "/tmp/moterm");
+        assert_eq!(expand_env("a ${unclosed"), "a ${unclosed");
+    }
+
+    #[test]
+    fn double_dollar_escapes() {
+        std::env::set_var("MOTERM_TEST_ESCAPED", "no");
+        assert_eq!(expand_env("$${MOTERM_TEST_ESCAPED} $5"), "${MOTERM_TEST_ESCAPED} $5");
+    }
+
+    #[test]
+    fn only_paths_and_commands_expand() {
+        std::env::set_var("MOTERM_TEST_EDITOR", "hx");
+        let mut table: toml::Table = toml::from_str(
+            r#"
+            [editor]
+            command = "${MOTERM_TEST_EDITOR} {file}"
+            [[hints]]
+            regex = "x{2}\\${MOTERM_TEST_EDITOR}"
+            command = "${MOTERM_TEST_EDITOR}"
+            "#,
+        )
+        .unwrap();
+        interpolate(&mut table);
+        assert_eq!(table["editor"]["command"].as_str(), Some("hx {file}"));
+        assert_eq!(table["hints"][0]["command"].as_str(), Some("hx"));
+        assert_eq!(table["hints"][0]["regex"].as_str(), Some("x{2}\\${MOTERM_TEST_EDITOR}"));
+    }
 }
@@ -0,0 +1,135 @@
+//! Panic hook: writes a crash report with the most recent PTY input and
+//! terminal state, then tells the user where it is.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::i18n::{tr, Msg};
+
+/// How much recent PTY output is kept for the report.
+const INPUT_LIMIT: usize = 64 * 1024;
+
+static RECENT_INPUT: Mutex<VecDeque<u8>> = Mutex::new(VecDeque::new());
+/// The fields of the last recorded [`State`], in declaration order, plus
+/// one that is set once anything was recorded. Atomics rather than a lock,
+/// since this is written for every chunk of output.
+static STATE: [AtomicUsize; 11] = [const { AtomicUsize::new(0) }; 11];
+
+/// Terminal state of the pane that last produced output, formatted only
+/// when a report is written.
+#[derive(Clone, Copy)]
+pub struct State {
+    pub pane: u64,
+    pub cols: usize,
+    pub rows: usize,
+    pub cursor: (usize, usize),
+    pub scroll_region: (usize, usize),
+    pub alt_screen: bool,
+    pub mouse_mode: u16,
+    pub scrollback: usize,
+}
+
+impl State {
+    fn fields(self) -> [usize; 10] {
+        [
+            self.pane as usize,
+            self.cols,
+            self.rows,
+            self.cursor.0,
+            self.cursor.1,
+            self.scroll_region.0,
+            self.scroll_region.1,
+            self.alt_screen as usize,
+            self.mouse_mode as usize,
+            self.scrollback,
+        ]
+    }
+
+    fn load() -> Option<Self> {
+        let [pane, cols, rows, cr, cc, top, bottom, alt, mouse, scrollback, recorded] =
+            std::array::from_fn(|i| STATE[i].load(Ordering::Relaxed));
+        (recorded != 0).then_some(State {
+            pane: pane as u64,
+            cols,
+            rows,
+            cursor: (cr, cc),
+            scroll_region: (top, bottom),
+            alt_screen: alt != 0,
+            mouse_mode: mouse as u16,
+            scrollback,
+        })
+    }
+}
+
+/// Remember PTY output before it's parsed.
+pub fn record_input(data: &[u8]) {
+    if let Ok(mut buf) = RECENT_INPUT.lock() {
+        buf.extend(data);
+        let excess = buf.len().saturating_sub(INPUT_LIMIT);
+        buf.drain(..excess);
+    }
+}
+
+/// Remember the terminal state for the report.
+pub fn record_state(state: State) {
+    for (field, value) in STATE.iter().zip(state.fields().into_iter().chain([1])) {
+        field.store(value, Ordering::Relaxed);
+    }
+}
+
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(format!("Library/Logs/moterm-crash-{}.txt", crate::local_timestamp()));
+        let report = build_report(&info.to_string());
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        match std::fs::write(&path, report) {
+            Ok(()) => {
+                log::error!("崩溃报告已保存: {}", path.display());
+                show_dialog(&path.display().to_string());
+            }
+            Err(e) => log::error!("写入崩溃报告失败 {}: {e}", path.display()),
+        }
+    }));
+}
+
+fn build_report(panic: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "moterm {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(out, "\n{panic}");
+    let _ = writeln!(out, "\n{}", std::backtrace::Backtrace::force_capture());
+    if let Some(s) = State::load() {
+        let _ = writeln!(
+            out,
+            "Terminal state:\npane {} {}x{} cursor {},{} scroll region {:?} alt_screen {} mouse {} scrollback {}",
+            s.pane, s.cols, s.rows, s.cursor.0, s.cursor.1, s.scroll_region, s.alt_screen, s.mouse_mode, s.scrollback,
+        );
+    }
+    // try_lock: the panic may have happened while this was held
+    if let Ok(buf) = RECENT_INPUT.try_lock() {
+        let bytes: Vec<u8> = buf.iter().copied().collect();
+        let _ = writeln!(out, "\nLast {} bytes of PTY output:", bytes.len());
+        out.push_str(&bytes.escape_ascii().to_string());
+        out.push('\n');
+    }
+    out
+}
+
+fn show_dialog(path: &str) {
+    let shown = path.replace('\\', "\\\\").replace('"', "\\\"");
+    let text = tr(Msg::CrashDialog).replace("{path}", &shown);
+    let ok = tr(Msg::Ok);
+    let script = format!(
+        r#"display dialog "{text}" buttons {{"{ok}"}} default button "{ok}" with icon stop with title "Moterm""#
+    );
+    let _ = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .output();
+}
@@ -4,8 +4,59 @@ use std::path::{Path, PathBuf};
 use fontdue::{Font, FontSettings};
 
 use crate::config::Config;
+use crate::i18n::{tr, Msg};
 
-pub fn load_monospace_font(cfg: &Config) -> Result<(Font, PathBuf), String> {
+/// Style of a cell's text, picking which face of the family draws it.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum Face {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+}
+
+impl Face {
+    pub fn of(bold: bool, italic: bool) -> Self {
+        match (bold, italic) {
+            (false, false) => Face::Regular,
+            (true, false) => Face::Bold,
+            (false, true) => Face::Italic,
+            (true, true) => Face::BoldItalic,
+        }
+    }
+}
+
+/// The configured family's regular face plus whichever styled faces exist.
+#[derive(Clone)]
+pub struct FontFaces {
+    pub regular: Font,
+    pub bold: Option<Font>,
+    pub italic: Option<Font>,
+    pub bold_italic: Option<Font>,
+}
+
+impl FontFaces {
+    /// The face for `face`, or the closest one that was found.
+    pub fn get(&self, face: Face) -> &Font {
+        let found = match face {
+            Face::Regular => None,
+            Face::Bold => self.bold.as_ref(),
+            Face::Italic => self.italic.as_ref(),
+            Face::BoldItalic => self.bold_italic.as_ref().or(self.bold.as_ref()).or(self.italic.as_ref()),
+        };
+        found.unwrap_or(&self.regular)
+    }
+}
+
+/// File name suffixes and fontconfig style of the bold, italic and
+/// bold-italic faces.
+const VARIANTS: [(&[&str], &str); 3] = [
+    (&["Bold"], "bold"),
+    (&["Italic", "Oblique"], "italic"),
+    (&["BoldItalic", "BoldOblique"], "bold:italic"),
+];
+
+pub fn load_monospace_font(cfg: &Config) -> Result<(FontFaces, PathBuf), String> {
     // If user specified a font family in config, try to find it
     let home = std::env::var("HOME").unwrap_or_default();
 
@@ -31,6 +82,14 @@ pub fn load_monospace_font(cfg: &Config) -> Result<(Font, PathBuf), String> {
         "/System/Library/Fonts/Monaco.ttf",
     ];
 
+    // Linux: ask fontconfig for the family, then for any monospace font
+    #[cfg(not(target_os = "macos"))]
+    {
+        let family = cfg.font.family.as_deref();
+        let found = family.and_then(|f| fc_match(&format!("{f}:style=Regular"), Some(f)));
+        custom_paths.extend(found.or_else(|| fc_match("monospace:style=Regular", None)));
+    }
+
     let mut all_candidates: Vec<&str> = custom_paths.iter().map(|s| s.as_str()).collect();
     all_candidates.push(nerd_font.as_str());
     all_candidates.extend(system_fonts.iter());
@@ -42,8 +101,10 @@ pub fn load_monospace_font(cfg: &Config) -> Result<(Font, PathBuf), String> {
         }
         match fs::read(path) {
             Ok(bytes) => {
-                if let Ok(font) = Font::from_bytes(bytes, FontSettings::default()) {
-                    return Ok((font, path.to_path_buf()));
+                if let Ok(regular) = Font::from_bytes(bytes, FontSettings::default()) {
+                    let [bold, italic, bold_italic] = load_variants(cfg, path);
+                    let faces = FontFaces { regular, bold, italic, bold_italic };
+                    return Ok((faces, path.to_path_buf()));
                 }
             }
             Err(_) => continue,
@@ -52,16 +113,74 @@ pub fn load_monospace_font(cfg: &Config) -> Result<(Font, PathBuf), String> {
 
     let embedded: &[u8] = include_bytes!("embedded_fallback_font.bin");
     if !embedded.is_empty() {
-        if let Ok(font) = Font::from_bytes(embedded, FontSettings::default()) {
-            return Ok((font, PathBuf::from("<embedded>")));
+        if let Ok(regular) = Font::from_bytes(embedded, FontSettings::default()) {
+            let faces = FontFaces { regular, bold: None, italic: None, bold_italic: None };
+            return Ok((faces, PathBuf::from("<embedded>")));
         }
     }
 
-    Err("无法加载系统等宽字体；当前仓库未提供可用嵌入字体。".to_string())
+    Err(tr(Msg::NoFont).to_string())
+}
+
+/// Bold, italic and bold-italic faces to go with the regular face at
+/// `regular`: files named like it with the style suffix, then on Linux
+/// what fontconfig has for the configured family.
+fn load_variants(cfg: &Config, regular: &Path) -> [Option<Font>; 3] {
+    let stem = regular.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let base = stem.strip_suffix("-Regular").unwrap_or(stem);
+    let ext = regular.extension().and_then(|e| e.to_str()).unwrap_or("ttf");
+    std::array::from_fn(|i| {
+        let (suffixes, _style) = VARIANTS[i];
+        let mut candidates: Vec<PathBuf> = suffixes
+            .iter()
+            .map(|suffix| regular.with_file_name(format!("{base}-{suffix}.{ext}")))
+            .collect();
+        #[cfg(not(target_os = "macos"))]
+        if let Some(f) = cfg.font.family.as_deref() {
+            candidates.extend(fc_match(&format!("{f}:{_style}"), Some(f)).map(PathBuf::from));
+        }
+        #[cfg(target_os = "macos")]
+        let _ = cfg;
+        candidates.iter().filter(|p| p.as_path() != regular && p.exists()).find_map(|p| {
+            let font = Font::from_bytes(fs::read(p).ok()?, FontSettings::default()).ok()?;
+            log::info!("字体变体: {}", p.display());
+            Some(font)
+        })
+    })
+}
+
+/// Path of the font fontconfig picks for `pattern`. With `family`, only if
+/// it is that family rather than fontconfig's closest substitute.
+#[cfg(not(target_os = "macos"))]
+fn fc_match(pattern: &str, family: Option<&str>) -> Option<String> {
+    let output = std::process::Command::new("fc-match")
+        .args(["--format=%{family}\n%{file}", pattern])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (families, file) = text.split_once('\n')?;
+    if let Some(family) = family {
+        if !families.split(',').any(|f| f.eq_ignore_ascii_case(family)) {
+            return None;
+        }
+    }
+    Some(file.to_string()).filter(|f| !f.is_empty())
 }
 
 /// Load CJK fallback fonts from the system
 pub fn load_fallback_fonts() -> Vec<Font> {
+    #[cfg(not(target_os = "macos"))]
+    let found: Vec<String> = {
+        let mut found: Vec<String> = [":lang=zh", ":lang=ja", ":charset=2192 2713 2600"]
+            .iter()
+            .filter_map(|p| fc_match(p, None))
+            .collect();
+        found.dedup();
+        found
+    };
+    #[cfg(target_os = "macos")]
+    let found: Vec<String> = Vec::new();
+
     let cjk_candidates = [
         // macOS CJK fonts
         "/System/Library/Fonts/PingFang.ttc",
@@ -75,14 +194,14 @@ pub fn load_fallback_fonts() -> Vec<Font> {
     ];
 
     let mut fonts = Vec::new();
-    for p in cjk_candidates {
+    for p in found.iter().map(String::as_str).chain(cjk_candidates) {
         let path = Path::new(p);
         if !path.exists() {
             continue;
         }
         if let Ok(bytes) = fs::read(path) {
             if let Ok(font) = Font::from_bytes(bytes, FontSettings::default()) {
-                eprintln!("回退字体: {}", p);
+                log::info!("回退字体: {}", p);
                 fonts.push(font);
                 if fonts.len() >= 3 {
                     break; // CJK + symbols
@@ -0,0 +1,142 @@
+//! User-defined hint patterns: a regex paired with an action, checked on
+//! Cmd+click and in keyboard hint mode before the built-in URL/path
+//! detection.
+
+use regex_lite::Regex;
+
+use crate::config::HintConfig;
+
+pub enum HintAction {
+    /// Open a URL template, e.g. `https://jira.example.com/browse/{match}`
+    Open(String),
+    /// Copy the matched text to the clipboard
+    Copy,
+    /// Run a command template, e.g. `git show {match}`
+    Command(String),
+}
+
+pub struct HintPattern {
+    pub regex: Regex,
+    pub action: HintAction,
+}
+
+/// A hint match under the pointer.
+pub struct HintMatch<'a> {
+    pub text: String,
+    pub pattern: &'a HintPattern,
+}
+
+/// Compile the `[[hints]]` config entries, skipping invalid ones.
+pub fn compile(entries: &[HintConfig]) -> Vec<HintPattern> {
+    let mut out = Vec::new();
+    for entry in entries {
+        let regex = match Regex::new(&entry.regex) {
+            Ok(r) => r,
+            Err(e) => {
+                log::warn!("hint 正则无效 ({}): {e}", entry.regex);
+                continue;
+            }
+        };
+        let action = match (entry.action.as_str(), &entry.url, &entry.command) {
+            ("open", Some(url), _) => HintAction::Open(url.clone()),
+            ("copy", _, _) => HintAction::Copy,
+            ("command", _, Some(cmd)) => HintAction::Command(cmd.clone()),
+            (action, _, _) => {
+                log::warn!("hint 动作无效或缺少模板: {action} ({})", entry.regex);
+                continue;
+            }
+        };
+        out.push(HintPattern { regex, action });
+    }
+    out
+}
+
+/// Every user hint in `line` as char columns [start, end), by config
+/// order and then position.
+pub fn hints_in<'a>(line: &str, patterns: &'a [HintPattern]) -> Vec<(usize, usize, HintMatch<'a>)> {
+    let mut out = Vec::new();
+    for pattern in patterns {
+        for m in pattern.regex.find_iter(line) {
+            // Regex offsets are bytes; terminal columns are chars
+            let start = line[..m.start()].chars().count();
+            let end = start + m.as_str().chars().count();
+            out.push((start, end, HintMatch { text: m.as_str().to_string(), pattern }));
+        }
+    }
+    out
+}
+
+/// Find the first user hint covering `col`. Earlier config entries win.
+pub fn hint_at<'a>(line: &str, col: usize, patterns: &'a [HintPattern]) -> Option<HintMatch<'a>> {
+    hints_in(line, patterns)
+        .into_iter()
+        .find(|&(start, end, _)| col >= start && col < end)
+        .map(|(_, _, hint)| hint)
+}
+
+/// Keys labelling targets in keyboard hint mode, home row first
+const LABEL_KEYS: &[u8] = b"asdfjklghqweruiopzxcvbnmty";
+
+/// Labels for `n` targets in keyboard hint mode: single letters while
+/// they suffice, else two letters each, so no label is the start of
+/// another. At most 676 are given.
+pub fn labels(n: usize) -> Vec<String> {
+    let keys = LABEL_KEYS.iter().map(|&k| k as char);
+    if n <= LABEL_KEYS.len() {
+        return keys.take(n).map(String::from).collect();
+    }
+    keys.clone()
+        .flat_map(|a| keys.clone().map(move |b| format!("{a}{b}")))
+        .take(n)
+        .collect()
+}
+
+impl HintMatch<'_> {
+    /// What a click would do, for the hover tooltip.
+    pub fn describe(&self) -> String {
+        match &self.pattern.action {
+            HintAction::Open(template) => template.replace("{match}", &self.text),
+            HintAction::Copy => self.text.clone(),
+            HintAction::Command(template) => template.replace("{match}", &self.text),
+        }
+    }
+
+    pub fn run(&self, ctx: &crate::url::OpenContext) {
+        match &self.pattern.action {
+            HintAction::Open(template) => {
+                crate::url::open_url_checked(&template.replace("{match}", &self.text), ctx);
+            }
+            HintAction::Copy => {
+                if let Err(e) = crate::clipboard::copy_to_clipboard(&self.text) {
+                    log::error!("复制失败: {e}");
+                }
+            }
+            HintAction::Command(template) => {
+                // Split before substituting so the match stays one argument
+                let argv: Vec<String> = template
+                    .split_whitespace()
+                    .map(|arg| arg.replace("{match}", &self.text))
+                    .collect();
+                if let Some((prog, args)) = argv.split_first() {
+                    let _ = std::process::Command::new(prog).args(args).spawn();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_distinct_and_prefix_free() {
+        assert_eq!(labels(3), ["a", "s", "d"]);
+        let many = labels(30);
+        assert_eq!(&many[..2], ["aa", "as"]);
+        for (i, a) in many.iter().enumerate() {
+            assert!(many.iter().enumerate().all(|(j, b)| i == j || !b.starts_with(a.as_str())));
+        }
+        assert_eq!(labels(1000).len(), 676);
+    }
+}
@@ -0,0 +1,205 @@
+//! User-facing strings (dialogs, menus, CLI help) in English and Chinese.
+//! Log messages are not translated.
+
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Zh,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Fix the language from the config's `language` ("auto", "en", "zh").
+/// Without a call, the system locale is used.
+pub fn init(setting: &str) {
+    let lang = match setting {
+        "en" => Lang::En,
+        "zh" => Lang::Zh,
+        _ => detect(),
+    };
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    *LANG.get_or_init(detect)
+}
+
+fn detect() -> Lang {
+    let env_locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|k| std::env::var(k).ok())
+        .find(|v| !v.is_empty() && v != "C" && v != "POSIX");
+    // Apps launched from Finder get no LANG; ask the system instead
+    #[cfg(target_os = "macos")]
+    let env_locale = env_locale.or_else(|| {
+        let out = std::process::Command::new("defaults")
+            .args(["read", "-g", "AppleLocale"])
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    });
+    match env_locale {
+        Some(l) if l.starts_with("zh") => Lang::Zh,
+        _ => Lang::En,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Msg {
+    Ok,
+    Cancel,
+    Close,
+    Open,
+    QuitConfirm,
+    OpenLinkConfirm,
+    ExportPrompt,
+    CrashDialog,
+    MenuOpenLink,
+    MenuCopyLink,
+    MenuCopy,
+    MenuSearchWeb,
+    MenuPaste,
+    SearchNoMatch,
+    PasteProgress,
+    CliUnknownArg,
+    CliMissingValue,
+    CliInvalidValue,
+    HelpAbout,
+    HelpRecord,
+    HelpReplay,
+    HelpSpeed,
+    HelpLogLevel,
+    HelpLogFile,
+    HelpShellIntegration,
+    HelpConfig,
+    HelpWorkingDirectory,
+    HelpCommand,
+    HelpHold,
+    ProcessExited,
+    ShellScriptWritten,
+    ShellAddSource,
+    ShellAlreadySourced,
+    ShellSourceAdded,
+    HelpVersion,
+    HelpHelp,
+    StartupFailed,
+    WindowFailed,
+    SurfaceFailed,
+    NoFont,
+    CreateDirFailed,
+    WriteFailed,
+    ReplayReadFailed,
+    RecordCreateFailed,
+    RecordWriteFailed,
+}
+
+/// Localized text for `msg`. Placeholders such as `{url}` are filled in by
+/// the caller.
+pub fn tr(msg: Msg) -> &'static str {
+    match lang() {
+        Lang::En => en(msg),
+        Lang::Zh => zh(msg),
+    }
+}
+
+fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Ok => "OK",
+        Msg::Cancel => "Cancel",
+        Msg::Close => "Close",
+        Msg::Open => "Open",
+        Msg::QuitConfirm => "A process is still running. Close Moterm anyway?",
+        Msg::OpenLinkConfirm => "Open this link?",
+        Msg::ExportPrompt => "Export terminal contents (.html keeps colors)",
+        Msg::CrashDialog => "Moterm quit unexpectedly.\\n\\nA crash report was saved to:\\n{path}",
+        Msg::MenuOpenLink => "Open Link",
+        Msg::MenuCopyLink => "Copy Link",
+        Msg::MenuCopy => "Copy",
+        Msg::MenuSearchWeb => "Search Web",
+        Msg::MenuPaste => "Paste",
+        Msg::SearchNoMatch => " (no matches)",
+        Msg::PasteProgress => "Pasting {pct}% · Esc to cancel",
+        Msg::CliUnknownArg => "unknown argument: {arg}",
+        Msg::CliMissingValue => "{arg} requires a value",
+        Msg::CliInvalidValue => "invalid value for {arg}: {value}",
+        Msg::HelpAbout => "A minimal terminal emulator",
+        Msg::HelpRecord => "Record the session to an asciinema .cast file",
+        Msg::HelpReplay => "Play back a .cast file (Space: pause, ←/→: seek 5s, ↑/↓: speed)",
+        Msg::HelpSpeed => "Playback speed for --replay (default 1.0)",
+        Msg::HelpLogLevel => "error, warn, info, debug or trace (env: MOTERM_LOG)",
+        Msg::HelpLogFile => "Also log to ~/Library/Logs/moterm.log",
+        Msg::HelpShellIntegration => "Install zsh/bash/fish scripts for prompt marks and cwd tracking",
+        Msg::HelpConfig => "Use this config file instead of ~/.config/moterm/config.toml",
+        Msg::HelpWorkingDirectory => "Start the shell in this directory",
+        Msg::HelpCommand => "Run this command (and the arguments after it) instead of the shell",
+        Msg::HelpHold => "Keep the window open after the command exits",
+        Msg::ProcessExited => "[Process exited with status {status}]",
+        Msg::ShellScriptWritten => "Wrote {path}",
+        Msg::ShellAddSource => "Load it from {rc}? [y/N] ",
+        Msg::ShellAlreadySourced => "{rc} already loads it",
+        Msg::ShellSourceAdded => "Updated {rc}; open a new tab to use it",
+        Msg::HelpVersion => "Print version",
+        Msg::HelpHelp => "Print this help",
+        Msg::StartupFailed => "moterm failed to start: {error}",
+        Msg::WindowFailed => "Could not create the window: {error}",
+        Msg::SurfaceFailed => "Could not set up drawing to the window: {error}",
+        Msg::CreateDirFailed => "Could not create {path}: {error}",
+        Msg::WriteFailed => "Could not write {path}: {error}",
+        Msg::ReplayReadFailed => "Could not read the recording {path}: {error}",
+        Msg::RecordCreateFailed => "Could not create the recording {path}: {error}",
+        Msg::RecordWriteFailed => "Could not write the recording: {error}",
+        Msg::NoFont => "Could not load a monospace font: none was found on the system and no embedded font is bundled.",
+    }
+}
+
+fn zh(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Ok => "确定",
+        Msg::Cancel => "取消",
+        Msg::Close => "关闭",
+        Msg::Open => "打开",
+        Msg::QuitConfirm => "有进程正在运行，确定要关闭 Moterm 吗？",
+        Msg::OpenLinkConfirm => "要打开这个链接吗？",
+        Msg::ExportPrompt => "导出终端内容（.html 保留颜色）",
+        Msg::CrashDialog => "Moterm 意外退出。\\n\\n崩溃报告已保存到：\\n{path}",
+        Msg::MenuOpenLink => "打开链接",
+        Msg::MenuCopyLink => "复制链接",
+        Msg::MenuCopy => "复制",
+        Msg::MenuSearchWeb => "搜索网页",
+        Msg::MenuPaste => "粘贴",
+        Msg::SearchNoMatch => " (无匹配)",
+        Msg::PasteProgress => "正在粘贴 {pct}% · Esc 取消",
+        Msg::CliUnknownArg => "未知参数: {arg}",
+        Msg::CliMissingValue => "{arg} 需要参数值",
+        Msg::CliInvalidValue => "{arg} 的值无效: {value}",
+        Msg::HelpAbout => "极简终端模拟器",
+        Msg::HelpRecord => "将会话录制为 asciinema .cast 文件",
+        Msg::HelpReplay => "回放 .cast 文件（空格：暂停，←/→：跳转 5 秒，↑/↓：速度）",
+        Msg::HelpSpeed => "--replay 的回放速度（默认 1.0）",
+        Msg::HelpLogLevel => "error、warn、info、debug 或 trace（环境变量：MOTERM_LOG）",
+        Msg::HelpLogFile => "同时写入 ~/Library/Logs/moterm.log",
+        Msg::HelpShellIntegration => "安装 zsh/bash/fish 脚本，用于提示符标记和工作目录跟踪",
+        Msg::HelpConfig => "使用此配置文件代替 ~/.config/moterm/config.toml",
+        Msg::HelpWorkingDirectory => "在此目录启动 shell",
+        Msg::HelpCommand => "运行此命令（及其后的参数）代替 shell",
+        Msg::HelpHold => "命令退出后保持窗口打开",
+        Msg::ProcessExited => "[进程已退出，状态 {status}]",
+        Msg::ShellScriptWritten => "已写入 {path}",
+        Msg::ShellAddSource => "在 {rc} 中加载它？[y/N] ",
+        Msg::ShellAlreadySourced => "{rc} 已经加载了它",
+        Msg::ShellSourceAdded => "已更新 {rc}，新开标签页后生效",
+        Msg::HelpVersion => "显示版本",
+        Msg::HelpHelp => "显示此帮助",
+        Msg::StartupFailed => "moterm 启动失败: {error}",
+        Msg::WindowFailed => "创建窗口失败: {error}",
+        Msg::SurfaceFailed => "无法绘制到窗口: {error}",
+        Msg::CreateDirFailed => "创建目录失败 {path}: {error}",
+        Msg::WriteFailed => "写入失败 {path}: {error}",
+        Msg::ReplayReadFailed => "读取录制文件失败 {path}: {error}",
+        Msg::RecordCreateFailed => "无法创建录制文件 {path}: {error}",
+        Msg::RecordWriteFailed => "写入录制文件失败: {error}",
+        Msg::NoFont => "无法加载系统等宽字体；当前仓库未提供可用嵌入字体。",
+    }
+}
@@ -0,0 +1,71 @@
+//! `log` backend: stderr, plus an optional log file.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct Logger {
+    file: Option<Mutex<File>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("[{} {}] {}\n", record.level(), record.target(), record.args());
+        let _ = std::io::stderr().write_all(line.as_bytes());
+        if let Some(file) = &self.file {
+            if let Ok(mut f) = file.lock() {
+                let _ = f.write_all(line.as_bytes());
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut f) = file.lock() {
+                let _ = f.flush();
+            }
+        }
+    }
+}
+
+/// Default log file location.
+pub fn log_file_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join("Library/Logs/moterm.log")
+}
+
+/// Install the logger. `level` wins over `MOTERM_LOG`; default is `info`.
+pub fn init(level: Option<LevelFilter>, to_file: bool) {
+    let level = level
+        .or_else(|| std::env::var("MOTERM_LOG").ok()?.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    let file = if to_file {
+        let path = log_file_path();
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        match File::options().create(true).append(true).open(&path) {
+            Ok(f) => Some(Mutex::new(f)),
+            Err(e) => {
+                eprintln!("无法打开日志文件 {}: {e}", path.display());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if log::set_boxed_logger(Box::new(Logger { file })).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -0,0 +1,247 @@
+//! Small AppKit calls winit doesn't expose. No-ops on other platforms.
+
+#[cfg(target_os = "macos")]
+use objc::runtime::Object;
+#[cfg(target_os = "macos")]
+use objc::{class, msg_send, sel, sel_impl};
+
+#[cfg(target_os = "macos")]
+unsafe fn ns_string(s: &str) -> *mut Object {
+    let s = std::ffi::CString::new(s).unwrap_or_default();
+    msg_send![class!(NSString), stringWithUTF8String: s.as_ptr()]
+}
+
+/// Set the Dock icon's badge; `None` removes it.
+#[cfg(target_os = "macos")]
+pub fn set_dock_badge(label: Option<&str>) {
+    unsafe {
+        let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let tile: *mut Object = msg_send![app, dockTile];
+        let ns_label: *mut Object = match label {
+            Some(l) => ns_string(l),
+            None => std::ptr::null_mut(),
+        };
+        let _: () = msg_send![tile, setBadgeLabel: ns_label];
+    }
+}
+
+/// Let the window join native tabs with other moterm windows.
+/// `mode`: "automatic" (follow System Settings), "preferred" or "disallowed".
+#[cfg(target_os = "macos")]
+pub fn set_tabbing(window: &winit::window::Window, mode: &str) {
+    use winit::platform::macos::WindowExtMacOS;
+
+    // NSWindowTabbingMode
+    let mode: isize = match mode {
+        "preferred" => 1,
+        "disallowed" => 2,
+        _ => 0,
+    };
+    unsafe {
+        let ns_window = window.ns_window() as *mut Object;
+        let _: () = msg_send![ns_window, setTabbingIdentifier: ns_string("moterm")];
+        let _: () = msg_send![ns_window, setTabbingMode: mode];
+    }
+}
+
+/// Set the native tab's title, bold for the active tab.
+#[cfg(target_os = "macos")]
+pub fn set_tab_title(window: &winit::window::Window, title: &str, bold: bool) {
+    use winit::platform::macos::WindowExtMacOS;
+
+    unsafe {
+        let ns_window = window.ns_window() as *mut Object;
+        let tab: *mut Object = msg_send![ns_window, tab];
+        if tab.is_null() {
+            return;
+        }
+        let size: f64 = msg_send![class!(NSFont), systemFontSize];
+        let font: *mut Object = if bold {
+            msg_send![class!(NSFont), boldSystemFontOfSize: size]
+        } else {
+            msg_send![class!(NSFont), systemFontOfSize: size]
+        };
+        // NSFontAttributeName
+        let attrs: *mut Object = msg_send![class!(NSDictionary), dictionaryWithObject: font forKey: ns_string("NSFont")];
+        let text: *mut Object = msg_send![class!(NSAttributedString), alloc];
+        let text: *mut Object = msg_send![text, initWithString: ns_string(title) attributes: attrs];
+        let _: () = msg_send![tab, setAttributedTitle: text];
+        let _: () = msg_send![text, release];
+    }
+}
+
+/// The user's double-click interval from System Settings.
+#[cfg(target_os = "macos")]
+pub fn double_click_interval() -> Option<std::time::Duration> {
+    let secs: f64 = unsafe { msg_send![class!(NSEvent), doubleClickInterval] };
+    std::time::Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Add a Window menu; AppKit fills in the tab items (Merge All Windows,
+/// Show Tab Bar, ...).
+#[cfg(target_os = "macos")]
+pub fn install_window_menu() {
+    unsafe {
+        let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let main_menu: *mut Object = msg_send![app, mainMenu];
+        if main_menu.is_null() {
+            return;
+        }
+        let title = ns_string("Window");
+        let menu: *mut Object = msg_send![class!(NSMenu), alloc];
+        let menu: *mut Object = msg_send![menu, initWithTitle: title];
+        let item: *mut Object = msg_send![class!(NSMenuItem), new];
+        let _: () = msg_send![item, setTitle: title];
+        let _: () = msg_send![item, setSubmenu: menu];
+        let _: () = msg_send![main_menu, addItem: item];
+        let _: () = msg_send![app, setWindowsMenu: menu];
+    }
+}
+
+/// Toggle fullscreen that just covers the screen: no own Space, no animation.
+#[cfg(target_os = "macos")]
+pub fn toggle_simple_fullscreen(window: &winit::window::Window) {
+    use winit::platform::macos::WindowExtMacOS;
+
+    window.set_simple_fullscreen(!window.simple_fullscreen());
+}
+
+/// Clicks on our notifications not yet handled: identifier and 1-based
+/// button (`None` for the notification itself).
+#[cfg(target_os = "macos")]
+static ACTIVATIONS: std::sync::Mutex<Vec<(String, Option<usize>)>> = std::sync::Mutex::new(Vec::new());
+
+/// Post a desktop notification. The first button is the action button, the
+/// rest are alternate actions; clicks come back from
+/// [`take_notification_activations`].
+#[cfg(target_os = "macos")]
+pub fn post_notification(identifier: &str, title: &str, body: &str, buttons: &[String]) {
+    unsafe {
+        let center: *mut Object = msg_send![class!(NSUserNotificationCenter), defaultUserNotificationCenter];
+        // nil when not running from an app bundle
+        if center.is_null() {
+            log::debug!("无法发送通知: 不在应用包中运行");
+            return;
+        }
+        let _: () = msg_send![center, setDelegate: notification_delegate()];
+        let n: *mut Object = msg_send![class!(NSUserNotification), new];
+        let _: () = msg_send![n, setIdentifier: ns_string(identifier)];
+        let _: () = msg_send![n, setTitle: ns_string(title)];
+        let _: () = msg_send![n, setInformativeText: ns_string(body)];
+        if let Some((first, rest)) = buttons.split_first() {
+            let _: () = msg_send![n, setHasActionButton: objc::runtime::YES];
+            let _: () = msg_send![n, setActionButtonTitle: ns_string(first)];
+            let actions: *mut Object = msg_send![class!(NSMutableArray), array];
+            for (i, label) in rest.iter().enumerate() {
+                let id = ns_string(&(i + 2).to_string());
+                let action: *mut Object =
+                    msg_send![class!(NSUserNotificationAction), actionWithIdentifier: id title: ns_string(label)];
+                let _: () = msg_send![actions, addObject: action];
+            }
+            let _: () = msg_send![n, setAdditionalActions: actions];
+        }
+        let _: () = msg_send![center, deliverNotification: n];
+        let _: () = msg_send![n, release];
+    }
+}
+
+/// Notification clicks since the last call.
+#[cfg(target_os = "macos")]
+pub fn take_notification_activations() -> Vec<(String, Option<usize>)> {
+    ACTIVATIONS.lock().map(|mut a| std::mem::take(&mut *a)).unwrap_or_default()
+}
+
+/// NSUserNotificationCenter delegate recording clicks into `ACTIVATIONS`.
+#[cfg(target_os = "macos")]
+fn notification_delegate() -> *mut Object {
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Sel, BOOL, YES};
+    use std::sync::OnceLock;
+
+    unsafe fn rust_string(ns: *mut Object) -> String {
+        if ns.is_null() {
+            return String::new();
+        }
+        let utf8: *const std::os::raw::c_char = msg_send![ns, UTF8String];
+        std::ffi::CStr::from_ptr(utf8).to_string_lossy().into_owned()
+    }
+    extern "C" fn did_activate(_: &Object, _: Sel, center: *mut Object, n: *mut Object) {
+        unsafe {
+            let identifier = rust_string(msg_send![n, identifier]);
+            // NSUserNotificationActivationType
+            let kind: isize = msg_send![n, activationType];
+            let button = match kind {
+                2 => Some(1),
+                4 => {
+                    let action: *mut Object = msg_send![n, additionalActivationAction];
+                    rust_string(msg_send![action, identifier]).parse().ok()
+                }
+                _ => None,
+            };
+            if let Ok(mut a) = ACTIVATIONS.lock() {
+                a.push((identifier, button));
+            }
+            let _: () = msg_send![center, removeDeliveredNotification: n];
+        }
+    }
+    // Show banners even while moterm is the active app
+    extern "C" fn should_present(_: &Object, _: Sel, _: *mut Object, _: *mut Object) -> BOOL {
+        YES
+    }
+
+    static DELEGATE: OnceLock<usize> = OnceLock::new();
+    *DELEGATE.get_or_init(|| unsafe {
+        let Some(mut decl) = ClassDecl::new("MotermNotificationDelegate", class!(NSObject)) else {
+            return 0;
+        };
+        decl.add_method(
+            sel!(userNotificationCenter:didActivateNotification:),
+            did_activate as extern "C" fn(&Object, Sel, *mut Object, *mut Object),
+        );
+        decl.add_method(
+            sel!(userNotificationCenter:shouldPresentNotification:),
+            should_present as extern "C" fn(&Object, Sel, *mut Object, *mut Object) -> BOOL,
+        );
+        let cls = decl.register();
+        let delegate: *mut Object = msg_send![cls, new];
+        delegate as usize
+    }) as *mut Object
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_dock_badge(_label: Option<&str>) {}
+
+#[cfg(not(target_os = "macos"))]
+pub fn post_notification(_identifier: &str, title: &str, body: &str, _buttons: &[String]) {
+    log::debug!("通知: {title} {body}");
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn take_notification_activations() -> Vec<(String, Option<usize>)> {
+    Vec::new()
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_tabbing(_window: &winit::window::Window, _mode: &str) {}
+
+#[cfg(not(target_os = "macos"))]
+pub fn install_window_menu() {}
+
+#[cfg(not(target_os = "macos"))]
+pub fn double_click_interval() -> Option<std::time::Duration> {
+    None
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_tab_title(_window: &winit::window::Window, _title: &str, _bold: bool) {}
+
+#[cfg(not(target_os = "macos"))]
+pub fn toggle_simple_fullscreen(window: &winit::window::Window) {
+    use winit::window::Fullscreen;
+
+    let next = match window.fullscreen() {
+        Some(_) => None,
+        None => Some(Fullscreen::Borderless(None)),
+    };
+    window.set_fullscreen(next);
+}
@@ -1,575 +1,477 @@
+mod cli;
 mod clipboard;
-mod color;
 mod config;
+mod crash;
 mod font;
+mod hints;
+mod i18n;
 mod input;
-mod mouse;
+mod logger;
+mod macos;
+mod output_log;
 mod pty;
 mod renderer;
-mod terminal;
-mod search;
-mod url;
-mod vte_handler;
+mod replay;
+mod session;
+mod shell_integration;
+mod signals;
+mod tab;
+mod window;
 
+use moterm_core::cast::CastWriter;
+use moterm_core::{color, export, graphics, grid, layout, mouse, notify, search, terminal, url, vte_handler};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use renderer::Renderer;
 use terminal::Terminal;
-use vte_handler::VteHandler;
-use winit::dpi::{LogicalSize, PhysicalPosition};
-use winit::event::{
-    ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta, WindowEvent,
-};
+use winit::dpi::PhysicalPosition;
+use winit::event::{Event, ModifiersState, StartCause, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoopBuilder};
-use winit::window::WindowBuilder;
+use winit::window::WindowId;
 
-use crate::pty::{PtyEvent, PtyHandle};
+use crate::i18n::{tr, Msg};
+use crate::pty::PtyHandle;
+use crate::window::{Action, Shared, TermWindow};
 
 #[derive(Debug, Clone)]
 enum AppEvent {
-    PtyOutput(Vec<u8>),
-    PtyExit,
+    PtyOutput(WindowId, layout::PaneId, Vec<u8>),
+    /// The pane's program exited, with its status if known
+    PtyExit(WindowId, layout::PaneId, Option<i32>),
+    /// SIGTERM or SIGHUP: flush and quit without asking
+    Terminate,
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.iter().any(|a| a == "--version" || a == "-v") {
-        println!("moterm {}", env!("CARGO_PKG_VERSION"));
-        return;
-    }
-    if args.iter().any(|a| a == "--help" || a == "-h") {
-        println!("moterm {} — A minimal terminal emulator", env!("CARGO_PKG_VERSION"));
-        println!();
-        println!("USAGE: moterm [OPTIONS]");
-        println!();
-        println!("OPTIONS:");
-        println!("  -v, --version    Print version");
-        println!("  -h, --help       Print this help");
-        println!();
-        println!("CONFIG: ~/.config/moterm/config.toml");
-        println!("REPO:   https://github.com/longzhi/moterm");
-        return;
-    }
-    if let Err(e) = run() {
-        eprintln!("moterm 启动失败: {e}");
+    let args = match cli::parse() {
+        Ok(cli::Command::Run(args)) => args,
+        Ok(cli::Command::Version) => {
+            println!("moterm {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Ok(cli::Command::Help) => {
+            cli::print_help();
+            return;
+        }
+        Ok(cli::Command::InstallShellIntegration) => {
+            if let Err(e) = shell_integration::install() {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            cli::print_help();
+            std::process::exit(2);
+        }
+    };
+    logger::init(args.log_level, args.log_file);
+    crash::install();
+    if let Err(e) = run(args) {
+        log::error!("{}", tr(Msg::StartupFailed).replace("{error}", &e));
         std::process::exit(1);
     }
 }
 
-fn run() -> Result<(), String> {
-    let cfg = config::Config::load();
+fn run(args: cli::Args) -> Result<(), String> {
+    let cfg = config::Config::load(args.config.as_deref());
+    i18n::init(&cfg.language);
 
     let (font, font_path) = font::load_monospace_font(&cfg)?;
-    eprintln!("使用字体: {}", font_path.display());
+    log::info!("使用字体: {}", font_path.display());
 
     let event_loop = EventLoopBuilder::<AppEvent>::with_user_event().build();
-    let window = WindowBuilder::new()
-        .with_title("moterm")
-        .with_inner_size(LogicalSize::new(
-            cfg.window.width as f64,
-            cfg.window.height as f64,
-        ))
-        .with_resizable(true)
-        .build(&event_loop)
-        .map_err(|e| format!("创建窗口失败: {e}"))?;
-
-    let scale_factor = window.scale_factor();
-    let font_size = (cfg.font.size * scale_factor as f32).max(8.0);
-    let fallback_fonts = font::load_fallback_fonts();
-    let mut renderer = Renderer::new(font, fallback_fonts, font_size);
-    let size = window.inner_size();
-    let (cols, rows) = renderer.grid_size_for_pixels(size.width as usize, size.height as usize);
-    let mut term = Terminal::new(cols, rows);
-    term.cursor_style = cfg.initial_cursor_style();
-
-    let proxy = event_loop.create_proxy();
-    let pty = PtyHandle::spawn(cols as u16, rows as u16, move |ev| {
-        let _ = match ev {
-            PtyEvent::Output(data) => proxy.send_event(AppEvent::PtyOutput(data)),
-            PtyEvent::Exit => proxy.send_event(AppEvent::PtyExit),
-        };
-    })?;
+    signals::install(event_loop.create_proxy());
+    let mut shared = Shared {
+        hint_patterns: hints::compile(&cfg.hints),
+        cfg,
+        font,
+        fallback_fonts: font::load_fallback_fonts(),
+        proxy: event_loop.create_proxy(),
+        working_directory: args.working_directory.clone(),
+        config_modified: config::Config::modified(args.config.as_deref()),
+        config_file: args.config.clone(),
+        register: Default::default(),
+    };
 
-    let mut parser = vte::Parser::new();
-    let mut dirty = true;
-    let mut search = search::SearchState::new();
-    let mut cursor_visible = true;
-    let mut cursor_blink_timer = std::time::Instant::now();
+    let player = match &args.replay {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| file_error(Msg::ReplayReadFailed, path, e))?;
+            let cast = moterm_core::cast::parse_cast(&text)?;
+            Some(replay::Player::new(cast, args.speed))
+        }
+        None => None,
+    };
+    let mut windows = HashMap::new();
+    // An explicit directory, command or replay starts fresh
+    let restore = shared.cfg.window.restore_session
+        && player.is_none()
+        && args.working_directory.is_none()
+        && args.command.is_empty();
+    let saved = if restore { session::load().windows } else { Vec::new() };
+    for (i, state) in saved.iter().enumerate() {
+        let cwd = state.tabs.first().and_then(|t| t.cwd.as_deref()).map(std::path::Path::new);
+        let record = if i == 0 { args.record.as_deref() } else { None };
+        match TermWindow::new(&event_loop, &shared, None, record, cwd, &[], false) {
+            Ok(mut w) => {
+                w.restore_tabs(&shared, state);
+                windows.insert(w.id(), w);
+            }
+            Err(e) => log::error!("{e}"),
+        }
+    }
+    if windows.is_empty() {
+        let first = TermWindow::new(
+            &event_loop,
+            &shared,
+            player,
+            args.record.as_deref(),
+            args.working_directory.as_deref(),
+            &args.command,
+            args.hold,
+        )?;
+        windows.insert(first.id(), first);
+    }
+    let mut session_saved = false;
     let mut modifiers = ModifiersState::empty();
-    let mut mouse_pos = PhysicalPosition::new(0.0f64, 0.0f64);
-    let mut selecting = false;
-    let mut last_click_time = std::time::Instant::now();
-    let mut click_count: u8 = 0;
+    let mut badge = 0usize;
 
-    let context = unsafe { softbuffer::Context::new(&window) }
-        .map_err(|e| format!("softbuffer context 创建失败: {e}"))?;
-    let mut surface = unsafe { softbuffer::Surface::new(&context, &window) }
-        .map_err(|e| format!("softbuffer surface 创建失败: {e}"))?;
-
-    event_loop.run(move |event, _, control_flow| {
-        // Blink cursor every 530ms
-        let blink_interval = std::time::Duration::from_millis(530);
-        *control_flow = ControlFlow::WaitUntil(std::time::Instant::now() + blink_interval);
+    event_loop.run(move |event, target, control_flow| {
+        // Sleep until the next cursor blink or replay event, if any
+        *control_flow = match windows.values().filter_map(|w| w.next_deadline()).min() {
+            Some(wake) => ControlFlow::WaitUntil(wake),
+            None => ControlFlow::Wait,
+        };
 
         match event {
-            Event::UserEvent(AppEvent::PtyOutput(data)) => {
-                {
-                    let mut performer = VteHandler::new(&mut term);
-                    for b in data {
-                        parser.advance(&mut performer, b);
-                    }
-                }
-                // Send any DSR replies back to PTY
-                if !term.reply_buf.is_empty() {
-                    let reply = std::mem::take(&mut term.reply_buf);
-                    write_pty(&pty, &reply);
-                }
-                if term.bell {
-                    term.bell = false;
-                    // Visual bell: briefly invert isn't easy without timer,
-                    // so we use macOS system beep
-                    #[cfg(target_os = "macos")]
-                    unsafe { libc::write(libc::STDOUT_FILENO, b"\x07".as_ptr() as _, 1); }
+            Event::NewEvents(StartCause::Init) => macos::install_window_menu(),
+            Event::UserEvent(AppEvent::PtyOutput(id, pane, data)) => {
+                if let Some(w) = windows.get_mut(&id) {
+                    w.handle_pane_output(pane, &data, &shared.cfg);
                 }
-                if term.title_changed {
-                    term.title_changed = false;
-                    window.set_title(if term.title.is_empty() { "moterm" } else { &term.title });
-                }
-                dirty = true;
-                window.request_redraw();
             }
-            Event::UserEvent(AppEvent::PtyExit) => {
-                *control_flow = ControlFlow::Exit;
+            Event::UserEvent(AppEvent::PtyExit(id, pane, status))
+                if windows.get_mut(&id).is_some_and(|w| w.pane_exited(pane, status, &shared.cfg)) =>
+            {
+                windows.remove(&id);
             }
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    if confirm_quit(&pty) {
-                        *control_flow = ControlFlow::Exit;
-                    }
-                }
-                WindowEvent::Resized(new_size) => {
-                    let (cols, rows) = renderer
-                        .grid_size_for_pixels(new_size.width as usize, new_size.height as usize);
-                    term.resize(cols, rows);
-                    if let Ok(pty) = pty.lock() {
-                        pty.resize(cols as u16, rows as u16);
-                    }
-                    // Immediately resize surface and fill with bg to prevent white flash
-                    let (w_nz, h_nz) = renderer::Renderer::nonzero_dims(new_size.width, new_size.height);
-                    if surface.resize(w_nz, h_nz).is_ok() {
-                        if let Ok(mut buffer) = surface.buffer_mut() {
-                            buffer.fill(crate::color::DEFAULT_BG.to_u32());
-                            let _ = buffer.present();
-                        }
-                    }
-                    dirty = true;
-                    window.request_redraw();
-                }
-                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    let (cols, rows) = renderer.grid_size_for_pixels(
-                        new_inner_size.width as usize,
-                        new_inner_size.height as usize,
-                    );
-                    term.resize(cols, rows);
-                    if let Ok(pty) = pty.lock() {
-                        pty.resize(cols as u16, rows as u16);
-                    }
-                    dirty = true;
-                    window.request_redraw();
-                }
-                WindowEvent::ModifiersChanged(m) => {
+            Event::UserEvent(AppEvent::Terminate) => {
+                flush_recordings(&mut windows);
+                save_session(&shared, &windows);
+                session_saved = true;
+                // Dropping the PTYs hangs up the shells
+                windows.clear();
+            }
+            Event::WindowEvent { window_id, event } => {
+                if let WindowEvent::ModifiersChanged(m) = event {
                     modifiers = m;
-                }
-                WindowEvent::ReceivedCharacter(ch) => {
-                    if search.active {
-                        if !ch.is_control() && !modifiers.logo() && !modifiers.ctrl() {
-                            search.push_char(ch);
-                            search.search(&term);
-                            dirty = true;
-                            window.request_redraw();
-                        }
-                        return;
-                    }
-                    if let Some(bytes) = input::map_received_char(ch, modifiers) {
-                        cursor_visible = true;
-                        cursor_blink_timer = std::time::Instant::now();
-                        write_pty(&pty, &bytes);
-                    }
-                }
-                WindowEvent::KeyboardInput { input, .. } => {
-                    if input.state != ElementState::Pressed {
-                        return;
-                    }
-                    if let Some(key) = input.virtual_keycode {
-                        // Search mode key handling
-                        if search.active && !modifiers.logo() {
-                            match key {
-                                winit::event::VirtualKeyCode::Escape => {
-                                    search.close();
-                                    dirty = true;
-                                    window.request_redraw();
-                                }
-                                winit::event::VirtualKeyCode::Back => {
-                                    search.pop_char();
-                                    search.search(&term);
-                                    dirty = true;
-                                    window.request_redraw();
-                                }
-                                winit::event::VirtualKeyCode::Return => {
-                                    search.next_match();
-                                    if let Some(m) = search.current_match() {
-                                        let vis_start = term.visible_start_global_row();
-                                        let vis_end = vis_start + term.rows();
-                                        if m.global_row < vis_start || m.global_row >= vis_end {
-                                            let total = term.total_lines();
-                                            let scroll = total.saturating_sub(m.global_row + term.rows());
-                                            term.view_scroll = scroll;
-                                        }
-                                    }
-                                    dirty = true;
-                                    window.request_redraw();
-                                }
-                                _ => {}
-                            }
-                            return;
-                        }
-                        if modifiers.logo() {
-                            match key {
-                                // Cmd+C: copy
-                                winit::event::VirtualKeyCode::C if term.selection_non_empty() => {
-                                    let text = term.selection_text_or_empty();
-                                    if let Err(e) = clipboard::copy_to_clipboard(&text) {
-                                        eprintln!("复制失败: {e}");
-                                    }
-                                    return;
-                                }
-                                // Cmd+V: paste (with bracketed paste support)
-                                winit::event::VirtualKeyCode::V => {
-                                    match clipboard::paste_from_clipboard() {
-                                        Ok(text) if !text.is_empty() => {
-                                            // Bracketed paste mode
-                                            write_pty(&pty, b"\x1b[200~");
-                                            write_pty(&pty, text.as_bytes());
-                                            write_pty(&pty, b"\x1b[201~");
-                                        }
-                                        Err(e) => eprintln!("粘贴失败: {e}"),
-                                        _ => {}
-                                    }
-                                    return;
-                                }
-                                // Cmd+N: new window
-                                winit::event::VirtualKeyCode::N => {
-                                    let exe = std::env::current_exe().unwrap_or_default();
-                                    let _ = std::process::Command::new(exe).spawn();
-                                    return;
-                                }
-                                // Cmd+Q: quit (with confirmation if child running)
-                                winit::event::VirtualKeyCode::Q => {
-                                    if confirm_quit(&pty) {
-                                        *control_flow = ControlFlow::Exit;
-                                    }
-                                    return;
-                                }
-                                // Cmd+= / Cmd++: zoom in
-                                winit::event::VirtualKeyCode::Equals => {
-                                    renderer.adjust_font_size(2.0);
-                                    let size = window.inner_size();
-                                    let (cols, rows) = renderer.grid_size_for_pixels(size.width as usize, size.height as usize);
-                                    term.resize(cols, rows);
-                                    if let Ok(pty) = pty.lock() { pty.resize(cols as u16, rows as u16); }
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+-: zoom out
-                                winit::event::VirtualKeyCode::Minus => {
-                                    renderer.adjust_font_size(-2.0);
-                                    let size = window.inner_size();
-                                    let (cols, rows) = renderer.grid_size_for_pixels(size.width as usize, size.height as usize);
-                                    term.resize(cols, rows);
-                                    if let Ok(pty) = pty.lock() { pty.resize(cols as u16, rows as u16); }
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+0: reset zoom
-                                winit::event::VirtualKeyCode::Key0 => {
-                                    let default_size = (cfg.font.size * scale_factor as f32).max(8.0);
-                                    renderer.set_font_size(default_size);
-                                    let size = window.inner_size();
-                                    let (cols, rows) = renderer.grid_size_for_pixels(size.width as usize, size.height as usize);
-                                    term.resize(cols, rows);
-                                    if let Ok(pty) = pty.lock() { pty.resize(cols as u16, rows as u16); }
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+K: clear scrollback
-                                winit::event::VirtualKeyCode::K => {
-                                    term.clear_scrollback();
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+A: select all
-                                winit::event::VirtualKeyCode::A => {
-                                    term.select_all();
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+F: toggle search
-                                winit::event::VirtualKeyCode::F => {
-                                    search.toggle();
-                                    dirty = true;
-                                    window.request_redraw();
-                                    return;
-                                }
-                                // Cmd+G: next search match
-                                winit::event::VirtualKeyCode::G => {
-                                    if search.active {
-                                        if modifiers.shift() {
-                                            search.prev_match();
-                                        } else {
-                                            search.next_match();
-                                        }
-                                        // Scroll to current match
-                                        if let Some(m) = search.current_match() {
-                                            let vis_start = term.visible_start_global_row();
-                                            let vis_end = vis_start + term.rows();
-                                            if m.global_row < vis_start || m.global_row >= vis_end {
-                                                let total = term.total_lines();
-                                                let scroll = total.saturating_sub(m.global_row + term.rows());
-                                                term.view_scroll = scroll;
-                                            }
-                                        }
-                                        dirty = true;
-                                        window.request_redraw();
-                                    }
-                                    return;
-                                }
-                                _ => {}
-                            }
-                        }
-
-                        match key {
-                            winit::event::VirtualKeyCode::PageUp if modifiers.shift() => {
-                                term.scroll_view_page(1);
-                                dirty = true;
-                                window.request_redraw();
-                            }
-                            winit::event::VirtualKeyCode::PageDown if modifiers.shift() => {
-                                term.scroll_view_page(-1);
-                                dirty = true;
-                                window.request_redraw();
-                            }
-                            winit::event::VirtualKeyCode::Home if modifiers.shift() => {
-                                term.set_view_scroll(term.max_view_scroll() as isize);
-                                dirty = true;
-                                window.request_redraw();
-                            }
-                            winit::event::VirtualKeyCode::End if modifiers.shift() => {
-                                term.scroll_view_to_bottom();
-                                dirty = true;
-                                window.request_redraw();
-                            }
-                            _ => {
-                                if let Some(bytes) = input::map_special_key(key, modifiers) {
-                                    write_pty(&pty, &bytes);
-                                }
-                            }
-                        }
-                    }
-                }
-                WindowEvent::CursorMoved { position, .. } => {
-                    mouse_pos = position;
-                    // Forward mouse motion in button-event (1002) or any-event (1003) mode
-                    if term.mouse_mode >= 1002 && selecting {
-                        if let Some((view_row, col)) = pixel_to_cell(&renderer, &window, mouse_pos) {
-                            let btn = mouse::BUTTON_LEFT + 32; // motion flag
-                            let bytes = if term.mouse_sgr {
-                                mouse::encode_sgr(btn, col, view_row, true)
-                            } else {
-                                mouse::encode_normal(btn, col, view_row)
-                            };
-                            write_pty(&pty, &bytes);
-                        }
-                    } else if selecting {
-                        if let Some((view_row, col)) = pixel_to_cell(&renderer, &window, mouse_pos)
-                        {
-                            term.set_selection_focus_from_view(view_row, col);
-                            dirty = true;
-                            window.request_redraw();
-                        }
+                    for w in windows.values_mut() {
+                        w.update_tooltip(modifiers, &shared);
                     }
+                    return;
                 }
-                WindowEvent::MouseInput {
-                    state,
-                    button,
-                    ..
-                } => {
-                    if let Some((view_row, col)) = pixel_to_cell(&renderer, &window, mouse_pos) {
-                        let btn = match button {
-                            MouseButton::Left => mouse::BUTTON_LEFT,
-                            MouseButton::Middle => mouse::BUTTON_MIDDLE,
-                            MouseButton::Right => mouse::BUTTON_RIGHT,
-                            _ => return,
-                        };
-
-                        // Forward mouse to application if mouse mode is active
-                        if term.mouse_mode > 0 && !modifiers.logo() {
-                            let pressed = state == ElementState::Pressed;
-                            let bytes = if term.mouse_sgr {
-                                mouse::encode_sgr(btn, col, view_row, pressed)
-                            } else if pressed {
-                                mouse::encode_normal(btn, col, view_row)
-                            } else {
-                                mouse::encode_normal(mouse::BUTTON_RELEASE, col, view_row)
-                            };
-                            write_pty(&pty, &bytes);
-                            return;
-                        }
-
-                        // Normal terminal selection behavior (only left button)
-                        if button == MouseButton::Left {
-                            match state {
-                                ElementState::Pressed => {
-                                    // Cmd+click: open URL
-                                    if modifiers.logo() {
-                                        if let Some(row) = term.visible_line(view_row) {
-                                            let line_text: String = row.cells.iter().map(|c| c.ch).collect();
-                                            for (start, end, u) in url::detect_urls(&line_text) {
-                                                if col >= start && col < end {
-                                                    url::open_url(&u);
-                                                    return;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    let now = std::time::Instant::now();
-                                    if now.duration_since(last_click_time).as_millis() < 400 {
-                                        click_count = (click_count + 1).min(3);
-                                    } else {
-                                        click_count = 1;
-                                    }
-                                    last_click_time = now;
-
-                                    match click_count {
-                                        2 => term.select_word_at_view(view_row, col),
-                                        3 => term.select_line_at_view(view_row),
-                                        _ => {
-                                            selecting = true;
-                                            term.start_selection_from_view(view_row, col);
-                                        }
-                                    }
-                                    dirty = true;
-                                    window.request_redraw();
-                                }
-                                ElementState::Released => {
-                                    selecting = false;
-                                }
-                            }
-                        }
-                    }
+                // Coming back from editing config.toml picks up the changes
+                if matches!(event, WindowEvent::Focused(true))
+                    && config::Config::modified(shared.config_file.as_deref()) != shared.config_modified
+                {
+                    reload_config(&mut shared, &mut windows);
                 }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    let lines = match delta {
-                        MouseScrollDelta::LineDelta(_, y) => y.round() as isize,
-                        MouseScrollDelta::PixelDelta(p) => {
-                            (p.y / renderer.atlas.cell_height as f64).round() as isize
-                        }
-                    };
-
-                    // Forward scroll to application if mouse mode is active
-                    if term.mouse_mode > 0 {
-                        if let Some((view_row, col)) = pixel_to_cell(&renderer, &window, mouse_pos) {
-                            let scroll_btn = if lines > 0 {
-                                mouse::BUTTON_SCROLL_UP
-                            } else {
-                                mouse::BUTTON_SCROLL_DOWN
-                            };
-                            for _ in 0..lines.unsigned_abs() {
-                                let bytes = if term.mouse_sgr {
-                                    mouse::encode_sgr(scroll_btn, col, view_row, true)
-                                } else {
-                                    mouse::encode_normal(scroll_btn, col, view_row)
-                                };
-                                write_pty(&pty, &bytes);
+                let Some(w) = windows.get_mut(&window_id) else {
+                    return;
+                };
+                match w.handle_event(event, modifiers, &shared) {
+                    Action::None => {}
+                    Action::ReloadConfig => reload_config(&mut shared, &mut windows),
+                    Action::NewWindow => {
+                        // Open where this window's shell is, as reported by OSC 7
+                        let cwd = w.cwd().map(PathBuf::from).or_else(|| shared.working_directory.clone());
+                        match TermWindow::new(target, &shared, None, None, cwd.as_deref(), &[], false) {
+                            Ok(w) => {
+                                windows.insert(w.id(), w);
                             }
+                            Err(e) => log::error!("{e}"),
                         }
-                        return;
                     }
-
-                    if lines != 0 {
-                        term.set_view_scroll(-lines);
-                        dirty = true;
-                        window.request_redraw();
+                    Action::Close => {
+                        windows.remove(&window_id);
                     }
-                }
-                _ => {}
-            },
-            Event::RedrawRequested(_) => {
-                if !dirty {
-                    return;
-                }
-                let size = window.inner_size();
-                let (w_nz, h_nz) = renderer::Renderer::nonzero_dims(size.width, size.height);
-                if let Err(e) = surface.resize(w_nz, h_nz) {
-                    eprintln!("surface resize 失败: {e}");
-                    *control_flow = ControlFlow::Exit;
-                    return;
-                }
-
-                renderer.cursor_visible = cursor_visible;
-                if search.active {
-                    renderer.render_with_search(&term, &search, size.width as usize, size.height as usize);
-                } else {
-                    renderer.render(&term, size.width as usize, size.height as usize);
-                }
-
-                match surface.buffer_mut() {
-                    Ok(mut buffer) => {
-                        let bg = crate::color::DEFAULT_BG.to_u32();
-                        if buffer.len() == renderer.canvas.pixels.len() {
-                            buffer.copy_from_slice(&renderer.canvas.pixels);
-                        } else {
-                            // Fill entire buffer with bg first, then copy canvas
-                            buffer.fill(bg);
-                            for (dst, src) in buffer
-                                .iter_mut()
-                                .zip(renderer.canvas.pixels.iter().copied())
-                            {
-                                *dst = src;
-                            }
-                        }
-                        if let Err(e) = buffer.present() {
-                            eprintln!("present 失败: {e}");
+                    Action::Quit => {
+                        let busy: Vec<String> = windows.values().flat_map(|w| w.busy_commands()).collect();
+                        if confirm_quit(&busy) {
                             *control_flow = ControlFlow::Exit;
-                            return;
                         }
                     }
-                    Err(e) => {
-                        eprintln!("获取绘制缓冲区失败: {e}");
+                }
+            }
+            Event::RedrawRequested(id) => {
+                if let Some(w) = windows.get_mut(&id) {
+                    if let Err(e) = w.redraw() {
+                        log::error!("{e}");
                         *control_flow = ControlFlow::Exit;
-                        return;
                     }
                 }
-                dirty = false;
+            }
+            Event::LoopDestroyed => {
+                flush_recordings(&mut windows);
+                if !session_saved {
+                    save_session(&shared, &windows);
+                }
             }
             Event::MainEventsCleared => {
-                let now = std::time::Instant::now();
-                if now.duration_since(cursor_blink_timer).as_millis() >= 530 {
-                    cursor_visible = !cursor_visible;
-                    cursor_blink_timer = now;
-                    dirty = true;
-                    window.request_redraw();
+                for w in windows.values_mut() {
+                    w.tick(&shared.cfg);
+                }
+                for (identifier, button) in macos::take_notification_activations() {
+                    if let Some(w) = windows.values_mut().find(|w| w.owns_notification(&identifier)) {
+                        w.notification_activated(&identifier, button, &shared.cfg);
+                    }
+                }
+                // Dock badge: bells received by windows in the background
+                let pending = windows.values().map(|w| w.pending_bells).sum();
+                if pending != badge {
+                    badge = pending;
+                    macos::set_dock_badge((badge > 0).then(|| badge.to_string()).as_deref());
                 }
             }
             _ => {}
         }
+        if windows.is_empty() {
+            *control_flow = ControlFlow::Exit;
+        }
     });
 }
 
+type Recorder = CastWriter<std::io::BufWriter<std::fs::File>>;
+
+/// Remember the open windows for `[window] restore_session`. Closing the
+/// last window leaves nothing to restore.
+fn save_session(shared: &Shared, windows: &HashMap<WindowId, TermWindow>) {
+    if !shared.cfg.window.restore_session {
+        return;
+    }
+    let windows = windows.values().filter(|w| w.player.is_none()).map(|w| w.session_state()).collect();
+    session::save(&session::Session { windows });
+}
+
+fn flush_recordings(windows: &mut HashMap<WindowId, TermWindow>) {
+    for w in windows.values_mut() {
+        if let Some((_, rec)) = &mut w.recorder {
+            if let Err(e) = rec.flush() {
+                log::error!("录制文件写入失败: {e}");
+            }
+        }
+    }
+}
+
+fn start_recording(path: &std::path::Path, cols: usize, rows: usize) -> Result<Recorder, String> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| file_error(Msg::RecordCreateFailed, path, e))?;
+    CastWriter::new(std::io::BufWriter::new(file), cols, rows)
+        .map_err(|e| tr(Msg::RecordWriteFailed).replace("{error}", &e.to_string()))
+}
+
+/// `msg` filled in with the file and the I/O error.
+fn file_error(msg: Msg, path: &std::path::Path, e: std::io::Error) -> String {
+    tr(msg).replace("{path}", &path.display().to_string()).replace("{error}", &e.to_string())
+}
+
+/// `~/moterm-20250224-153000.cast`
+fn default_recording_path() -> std::path::PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(format!("moterm-{}.cast", local_timestamp()))
+}
+
+/// Local time as `YYYYmmdd-HHMMSS`, for file names.
+fn local_timestamp() -> String {
+    let tm = local_tm(unix_now());
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// Local `HH:MM:SS` for a unix time.
+fn clock_time(secs: u64) -> String {
+    let tm = local_tm(secs);
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+fn local_tm(secs: u64) -> libc::tm {
+    let t = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&t, &mut tm) };
+    tm
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// What's under the pointer for Cmd+click and the context menu.
+enum ClickTarget<'a> {
+    /// URI of an explicit (OSC 8) hyperlink
+    Hyperlink(String),
+    Hint(hints::HintMatch<'a>),
+    Link(url::Link),
+}
+
+impl ClickTarget<'_> {
+    /// Where a Cmd+click goes, shown while hovering with Cmd held.
+    fn destination(&self) -> String {
+        match self {
+            ClickTarget::Hyperlink(uri) => uri.clone(),
+            ClickTarget::Hint(hint) => hint.describe(),
+            ClickTarget::Link(link) => link.target.clone(),
+        }
+    }
+
+    /// What "Copy Link" puts on the clipboard.
+    fn text(&self) -> String {
+        match self {
+            ClickTarget::Hyperlink(uri) => uri.clone(),
+            ClickTarget::Hint(hint) => hint.text.clone(),
+            ClickTarget::Link(link) => link.target.clone(),
+        }
+    }
+
+    fn open(&self, ctx: &url::OpenContext) {
+        match self {
+            ClickTarget::Hyperlink(uri) => url::open_url_checked(uri, ctx),
+            ClickTarget::Hint(hint) => hint.run(ctx),
+            ClickTarget::Link(link) => url::open_link(link, ctx),
+        }
+    }
+}
+
+fn click_target_at<'a>(
+    term: &Terminal,
+    view_row: usize,
+    col: usize,
+    hint_patterns: &'a [hints::HintPattern],
+) -> Option<ClickTarget<'a>> {
+    // Detect over the whole logical line so wrapped URLs open in full
+    let global_row = term.visible_global_row_for_view(view_row);
+    if global_row >= term.total_lines() {
+        return None;
+    }
+    // Links the program marked explicitly win over anything detected
+    if let Some(uri) = term.link_at(global_row, col) {
+        return Some(ClickTarget::Hyperlink(uri.to_string()));
+    }
+    let (line_text, first_row) = term.logical_line_text(global_row);
+    let offset = (global_row - first_row) * term.cols() + col;
+    if let Some(hint) = hints::hint_at(&line_text, offset, hint_patterns) {
+        return Some(ClickTarget::Hint(hint));
+    }
+    url::link_at(&line_text, offset).map(ClickTarget::Link)
+}
+
+/// Cells keyboard hint mode labels, as (global row, col): the first
+/// visible cell of each OSC 8 link, user hint and detected link on screen.
+/// Each is a cell where [`click_target_at`] finds that target.
+fn visible_targets(term: &Terminal, hint_patterns: &[hints::HintPattern]) -> Vec<(usize, usize)> {
+    let cols = term.cols();
+    let top = term.visible_start_global_row();
+    let bottom = (top + term.rows()).min(term.total_lines());
+    let mut out = Vec::new();
+    let mut row = top;
+    while row < bottom {
+        let (text, first) = term.logical_line_text(row);
+        let len = text.chars().count();
+        let link = |offset: usize| {
+            let cell = term.line_at_global(first + offset / cols).and_then(|l| l.cells.get(offset % cols));
+            cell.map_or(0, |c| c.link)
+        };
+        // Visible part of the line, and the cells an earlier target claims
+        let visible = (top.max(first) - first) * cols..(bottom - first) * cols;
+        let mut taken = vec![false; len];
+        let mut claim = |start: usize, end: usize, out: &mut Vec<(usize, usize)>| {
+            let at = start.max(visible.start);
+            if at < end.min(visible.end) && !taken[at] {
+                out.push((first + at / cols, at % cols));
+            }
+            taken[start..end].iter_mut().for_each(|t| *t = true);
+        };
+        let mut start = 0;
+        while start < len {
+            let id = link(start);
+            let end = (start + 1..len).find(|&o| link(o) != id).unwrap_or(len);
+            if id != 0 {
+                claim(start, end, &mut out);
+            }
+            start = end;
+        }
+        for (start, end, _) in hints::hints_in(&text, hint_patterns) {
+            claim(start, end, &mut out);
+        }
+        for l in url::detect_links(&text) {
+            claim(l.start, l.end, &mut out);
+        }
+        row = first + len.div_ceil(cols).max(1);
+    }
+    out
+}
+
+/// Re-read the config and apply it to every window. A file that fails to
+/// parse leaves the running config untouched.
+fn reload_config(shared: &mut Shared, windows: &mut HashMap<WindowId, TermWindow>) {
+    shared.config_modified = config::Config::modified(shared.config_file.as_deref());
+    let cfg = match config::Config::reload(shared.config_file.as_deref()) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            log::warn!("{e}，保留当前配置");
+            return;
+        }
+    };
+    if cfg.font.family != shared.cfg.font.family {
+        match font::load_monospace_font(&cfg) {
+            Ok((font, path)) => {
+                log::info!("使用字体: {}", path.display());
+                shared.font = font;
+            }
+            Err(e) => log::warn!("{e}"),
+        }
+    }
+    shared.hint_patterns = hints::compile(&cfg.hints);
+    shared.cfg = cfg;
+    for w in windows.values_mut() {
+        w.apply_config(shared);
+    }
+    log::info!("已重新加载配置");
+}
+
+fn open_context<'a>(term: &'a Terminal, cfg: &'a config::Config, reveal: bool) -> url::OpenContext<'a> {
+    url::OpenContext {
+        cwd: term.cwd.as_deref(),
+        editor: cfg.editor.command.as_deref(),
+        reveal,
+        allowed_schemes: &cfg.links.allowed_schemes,
+        confirm: confirm_open_url,
+    }
+}
+
+fn web_search_selection(term: &Terminal, cfg: &config::Config) {
+    let text = term.selection_text_or_empty();
+    if text.trim().is_empty() {
+        return;
+    }
+    let ctx = open_context(term, cfg, false);
+    url::open_url_checked(&url::search_url(&cfg.links.search_engine, &text), &ctx);
+}
+
 fn pixel_to_cell(
     renderer: &Renderer,
     window: &winit::window::Window,
@@ -581,60 +483,172 @@ fn pixel_to_cell(
     }
     let x = pos.x.max(0.0) as usize;
     let y = pos.y.max(0.0) as usize;
-    if x < renderer.padding_x || y < renderer.padding_y {
+    if x < renderer.grid_x() || y < renderer.grid_y() {
         return None;
     }
-    let gx = x - renderer.padding_x;
-    let gy = y - renderer.padding_y;
+    let gx = x - renderer.grid_x();
+    let gy = y - renderer.grid_y();
     Some((
         gy / renderer.atlas.cell_height,
         gx / renderer.atlas.cell_width,
     ))
 }
 
-fn confirm_quit(pty: &Arc<Mutex<PtyHandle>>) -> bool {
-    // Check if child process has sub-processes running
-    let has_children = if let Ok(pty) = pty.lock() {
-        let pid = pty.child_pid;
-        // Check if shell has child processes (commands running)
-        let output = std::process::Command::new("pgrep")
-            .args(["-P", &pid.to_string()])
-            .output();
-        matches!(output, Ok(o) if !o.stdout.is_empty())
-    } else {
-        false
-    };
+/// The program running in a shell, if the shell has child processes.
+fn busy_command(pty: &Arc<Mutex<PtyHandle>>) -> Option<String> {
+    let pty = pty.lock().ok()?;
+    let pid = pty.child_pid;
+    if pid <= 0 {
+        return None;
+    }
+    // Check if shell has child processes (commands running)
+    if !pty::has_children(pid) {
+        return None;
+    }
+    Some(pty.foreground_command().unwrap_or_else(|| "?".into()))
+}
 
-    if !has_children {
+/// Ask before closing shells that are running `busy` (one line each,
+/// e.g. "vim — src"); nothing to ask when it is empty.
+fn confirm_quit(busy: &[String]) -> bool {
+    if busy.is_empty() {
         return true;
     }
+    let list: String = busy.iter().map(|b| format!("\n• {b}")).collect();
+    let text = format!("{}\n{list}", tr(Msg::QuitConfirm));
+    confirm_close_dialog(&text, tr(Msg::Cancel), tr(Msg::Close))
+}
 
-    // Show macOS native confirmation dialog
+/// Show macOS native confirmation dialog
+#[cfg(target_os = "macos")]
+fn confirm_close_dialog(text: &str, cancel: &str, close: &str) -> bool {
+    let text = text.replace('\\', "\\\\").replace('"', "\\\"");
     let result = std::process::Command::new("osascript")
         .args([
             "-e",
-            r#"display dialog "有进程正在运行，确定要关闭 Moterm 吗？" buttons {"取消", "关闭"} default button "取消" with icon caution with title "Moterm""#,
+            &format!(
+                r#"display dialog "{text}" buttons {{"{cancel}", "{close}"}} default button "{cancel}" with icon caution with title "Moterm""#,
+            ),
         ])
         .output();
 
     match result {
         Ok(o) => {
             let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout.contains("关闭")
+            stdout.contains(close)
         }
         Err(_) => true, // If dialog fails, allow quit
     }
 }
 
+/// zenity, else kdialog; quit without asking when neither is installed.
+#[cfg(not(target_os = "macos"))]
+fn confirm_close_dialog(text: &str, cancel: &str, close: &str) -> bool {
+    use std::process::Command;
+
+    let zenity = Command::new("zenity")
+        .args(["--question", "--icon-name=dialog-warning", "--title=Moterm", "--no-markup"])
+        .arg(format!("--text={text}"))
+        .arg(format!("--ok-label={close}"))
+        .arg(format!("--cancel-label={cancel}"))
+        .status();
+    let status = zenity.or_else(|_| {
+        Command::new("kdialog")
+            .args(["--title", "Moterm", "--warningcontinuecancel", text])
+            .args(["--continue-label", close, "--cancel-label", cancel])
+            .status()
+    });
+    match status {
+        Ok(s) => s.success(),
+        Err(e) => {
+            log::warn!("无法显示退出确认对话框: {e}");
+            true
+        }
+    }
+}
+
+/// Ask for a destination and write scrollback + screen there; `.html`
+/// keeps colors, anything else is plain text. With `timestamps`, lines are
+/// prefixed with the time they were written.
+fn export_scrollback(term: &Terminal, timestamps: bool) {
+    let default_name = format!("moterm-{}.txt", local_timestamp());
+    let script = format!(
+        r#"POSIX path of (choose file name with prompt "{}" default name "{default_name}")"#,
+        tr(Msg::ExportPrompt)
+    );
+    let Ok(output) = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .output()
+    else {
+        return;
+    };
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if path.is_empty() {
+        return; // cancelled
+    }
+    let path = std::path::PathBuf::from(path);
+    let stamp = |t: u64| format!("[{}] ", clock_time(t));
+    let stamp: Option<export::Stamp> = if timestamps { Some(&stamp) } else { None };
+    let text = export::export_with(term, export::Format::from_path(&path), stamp);
+    match std::fs::write(&path, text) {
+        Ok(()) => log::info!("已导出: {}", path.display()),
+        Err(e) => log::error!("导出失败 {}: {e}", path.display()),
+    }
+}
+
+fn confirm_open_url(url: &str) -> bool {
+    let (cancel, open) = (tr(Msg::Cancel), tr(Msg::Open));
+    // AppleScript string literal: escape backslashes and quotes
+    let shown = url.replace('\\', "\\\\").replace('"', "\\\"");
+    let script = format!(
+        r#"display dialog "{}\n\n{shown}" buttons {{"{cancel}", "{open}"}} default button "{cancel}" with icon caution with title "Moterm""#,
+        tr(Msg::OpenLinkConfirm)
+    );
+    let result = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .output();
+
+    match result {
+        Ok(o) => String::from_utf8_lossy(&o.stdout).contains(open),
+        Err(_) => false, // If dialog fails, don't open
+    }
+}
+
+/// Clipboard text as PTY input, wrapped in bracketed-paste markers when the
+/// application asked for them.
+fn paste_bytes(bracketed: bool) -> Option<Vec<u8>> {
+    match clipboard::paste_from_clipboard() {
+        Ok(text) if !text.is_empty() => Some(paste_data(&text, bracketed)),
+        Err(e) => {
+            log::error!("粘贴失败: {e}");
+            None
+        }
+        _ => None,
+    }
+}
+
+/// `text` as sent to the shell, wrapped for bracketed paste mode.
+fn paste_data(text: &str, bracketed: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(text.len() + 12);
+    if bracketed {
+        data.extend_from_slice(b"\x1b[200~");
+    }
+    data.extend_from_slice(text.as_bytes());
+    if bracketed {
+        data.extend_from_slice(b"\x1b[201~");
+    }
+    data
+}
+
 fn write_pty(pty: &Arc<Mutex<PtyHandle>>, bytes: &[u8]) {
     match pty.lock() {
         Ok(pty) => {
             if let Err(e) = pty.write(bytes) {
-                eprintln!("写入 PTY 失败 ({} bytes): {}", bytes.len(), e);
+                log::error!("写入 PTY 失败 ({} bytes): {}", bytes.len(), e);
             }
         }
         Err(e) => {
-            eprintln!("PTY 锁失败: {e}");
+            log::error!("PTY 锁失败: {e}");
         }
     }
 }
@@ -0,0 +1,105 @@
+//! `[logging] session_logs`: each shell's output streamed to its own file,
+//! as plain text or raw bytes, starting a new file past `max_file_mb`.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config::LoggingConfig;
+use crate::layout::PaneId;
+
+pub struct OutputLog {
+    /// `<directory>/moterm-<time>-<pane>`; parts add `-2`, `-3` …
+    stem: PathBuf,
+    file: File,
+    part: u32,
+    written: u64,
+    max_bytes: u64,
+    /// Strips escape sequences in "text" format
+    text: Option<vte::Parser>,
+}
+
+/// Collects what would be printed, keeping newlines and tabs.
+struct Printed<'a>(&'a mut String);
+
+impl vte::Perform for Printed<'_> {
+    fn print(&mut self, c: char) {
+        self.0.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if matches!(byte, b'\n' | b'\t') {
+            self.0.push(byte as char);
+        }
+    }
+}
+
+impl OutputLog {
+    /// Start a log for a new pane, if enabled.
+    pub fn open(cfg: &LoggingConfig, pane: PaneId) -> Option<Self> {
+        if !cfg.session_logs {
+            return None;
+        }
+        let dir = match cfg.directory.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => PathBuf::from(&cfg.directory),
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::error!("无法创建日志目录 {}: {e}", dir.display());
+            return None;
+        }
+        let stem = dir.join(format!("moterm-{}-{pane}", crate::local_timestamp()));
+        let file = create(&stem, 1)?;
+        Some(Self {
+            stem,
+            file,
+            part: 1,
+            written: 0,
+            max_bytes: cfg.max_file_mb.max(1) * 1024 * 1024,
+            text: (cfg.format != "raw").then(vte::Parser::new),
+        })
+    }
+
+    pub fn write(&mut self, data: &[u8]) {
+        let mut printed = String::new();
+        let bytes = match &mut self.text {
+            Some(parser) => {
+                let mut sink = Printed(&mut printed);
+                for &b in data {
+                    parser.advance(&mut sink, b);
+                }
+                printed.as_bytes()
+            }
+            None => data,
+        };
+        if bytes.is_empty() {
+            return;
+        }
+        if self.written >= self.max_bytes {
+            match create(&self.stem, self.part + 1) {
+                Some(file) => {
+                    self.file = file;
+                    self.part += 1;
+                    self.written = 0;
+                }
+                None => return,
+            }
+        }
+        match self.file.write_all(bytes) {
+            Ok(()) => self.written += bytes.len() as u64,
+            Err(e) => log::error!("写入会话日志失败: {e}"),
+        }
+    }
+}
+
+fn create(stem: &std::path::Path, part: u32) -> Option<File> {
+    let mut name = stem.as_os_str().to_owned();
+    if part > 1 {
+        name.push(format!("-{part}"));
+    }
+    name.push(".log");
+    let path = PathBuf::from(name);
+    File::create(&path)
+        .map_err(|e| log::error!("无法创建会话日志 {}: {e}", path.display()))
+        .ok()
+}
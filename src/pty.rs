@@ -3,6 +3,8 @@ use std::ffi::CString;
 use std::fs::File;
 use std::io::Read;
 use std::os::fd::{FromRawFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -12,29 +14,50 @@ use polling::{Event, Events, Poller};
 #[derive(Debug, Clone)]
 pub enum PtyEvent {
     Output(Vec<u8>),
-    Exit,
+    /// The child closed the PTY; its exit status, if it could be reaped
+    Exit(Option<i32>),
 }
 
 pub struct PtyHandle {
     master_fd: RawFd,
     #[allow(dead_code)]
     pub child_pid: libc::pid_t,
+    /// The child was reaped: its pid may belong to another process now
+    reaped: Arc<AtomicBool>,
 }
 
 impl PtyHandle {
-    pub fn spawn<F>(cols: u16, rows: u16, mut emit: F) -> Result<Arc<Mutex<Self>>, String>
+    pub fn spawn<F>(
+        cols: u16,
+        rows: u16,
+        pixels: (u16, u16),
+        term: &str,
+        cwd: Option<&std::path::Path>,
+        command: &[String],
+        mut emit: F,
+    ) -> Result<Arc<Mutex<Self>>, String>
     where
         F: FnMut(PtyEvent) + Send + 'static,
     {
         let shell = env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
         let shell_c = CString::new(shell.clone()).map_err(|e| e.to_string())?;
+        let term_c = CString::new(term).map_err(|e| e.to_string())?;
+        let truecolor = moterm_core::terminfo::truecolor(term);
+        let command_c = command
+            .iter()
+            .map(|a| CString::new(a.as_str()).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cwd_c = match cwd {
+            Some(dir) => Some(CString::new(dir.as_os_str().as_bytes()).map_err(|e| e.to_string())?),
+            None => None,
+        };
 
         let mut master_fd: libc::c_int = -1;
         let mut ws = libc::winsize {
             ws_row: rows,
             ws_col: cols,
-            ws_xpixel: 0,
-            ws_ypixel: 0,
+            ws_xpixel: pixels.0,
+            ws_ypixel: pixels.1,
         };
 
         let pid = unsafe {
@@ -52,15 +75,33 @@ impl PtyHandle {
 
         if pid == 0 {
             unsafe {
-                let term = CString::new("xterm-256color").unwrap();
                 let name = CString::new("TERM").unwrap();
-                libc::setenv(name.as_ptr(), term.as_ptr(), 1);
+                libc::setenv(name.as_ptr(), term_c.as_ptr(), 1);
+                // Don't pass on a COLORTERM the configured TERM can't back up
+                let ct_name = CString::new("COLORTERM").unwrap();
+                if truecolor {
+                    let ct_val = CString::new("truecolor").unwrap();
+                    libc::setenv(ct_name.as_ptr(), ct_val.as_ptr(), 1);
+                } else {
+                    libc::unsetenv(ct_name.as_ptr());
+                }
                 let tp_name = CString::new("TERM_PROGRAM").unwrap();
                 let tp_val = CString::new("moterm").unwrap();
                 libc::setenv(tp_name.as_ptr(), tp_val.as_ptr(), 1);
                 let tpv_name = CString::new("TERM_PROGRAM_VERSION").unwrap();
                 let tpv_val = CString::new(env!("CARGO_PKG_VERSION")).unwrap();
                 libc::setenv(tpv_name.as_ptr(), tpv_val.as_ptr(), 1);
+                // A missing directory leaves the shell where moterm was started
+                if let Some(dir) = &cwd_c {
+                    libc::chdir(dir.as_ptr());
+                }
+                // `-e`: run the program itself
+                if let Some(program) = command_c.first() {
+                    let mut argv: Vec<_> = command_c.iter().map(|a| a.as_ptr()).collect();
+                    argv.push(std::ptr::null());
+                    libc::execvp(program.as_ptr(), argv.as_ptr());
+                    libc::_exit(127);
+                }
                 // Start as login shell (-l) so .zprofile/.zshrc are sourced
                 // and PATH includes Homebrew, fnm, etc.
                 let login_flag = CString::new("-l").unwrap();
@@ -83,18 +124,20 @@ impl PtyHandle {
             return Err("dup(master_fd) 失败".to_string());
         }
 
+        let reaped = Arc::new(AtomicBool::new(false));
+        let reaped_by_reader = reaped.clone();
         thread::spawn(move || {
             let mut file = unsafe { File::from_raw_fd(reader_fd) };
             let poller = match Poller::new() {
                 Ok(p) => p,
                 Err(_) => {
-                    emit(PtyEvent::Exit);
+                    emit(PtyEvent::Exit(None));
                     return;
                 }
             };
             let add_result = unsafe { poller.add(&file, Event::readable(1)) };
             if add_result.is_err() {
-                emit(PtyEvent::Exit);
+                emit(PtyEvent::Exit(None));
                 return;
             }
             let mut buf = vec![0u8; 8192];
@@ -110,35 +153,46 @@ impl PtyHandle {
                     loop {
                         match file.read(&mut buf) {
                             Ok(0) => {
-                                emit(PtyEvent::Exit);
+                                emit(PtyEvent::Exit(reap(pid, &reaped_by_reader)));
                                 return;
                             }
                             Ok(n) => emit(PtyEvent::Output(buf[..n].to_vec())),
                             Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
                             Err(_) => {
-                                emit(PtyEvent::Exit);
+                                emit(PtyEvent::Exit(reap(pid, &reaped_by_reader)));
                                 return;
                             }
                         }
                     }
                     // polling 在部分后端是 one-shot 语义，事件处理后需要重新 arm。
                     if poller.modify(&file, Event::readable(1)).is_err() {
-                        emit(PtyEvent::Exit);
+                        emit(PtyEvent::Exit(reap(pid, &reaped_by_reader)));
                         return;
                     }
                 }
             }
-            emit(PtyEvent::Exit);
+            emit(PtyEvent::Exit(reap(pid, &reaped_by_reader)));
         });
 
         Ok(Arc::new(Mutex::new(Self {
             master_fd,
             child_pid: pid,
+            reaped,
         })))
     }
 
+    /// A handle with no child process, used for `--replay`: writes and
+    /// resizes are dropped.
+    pub fn detached() -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            master_fd: -1,
+            child_pid: 0,
+            reaped: Arc::new(AtomicBool::new(false)),
+        }))
+    }
+
     pub fn write(&self, data: &[u8]) -> Result<(), String> {
-        if data.is_empty() {
+        if data.is_empty() || self.master_fd < 0 {
             return Ok(());
         }
         let mut written = 0usize;
@@ -169,23 +223,115 @@ impl PtyHandle {
         Ok(())
     }
 
-    pub fn resize(&self, cols: u16, rows: u16) {
+    /// One non-blocking write; returns how many bytes the PTY took (0 when
+    /// its buffer is full).
+    pub fn try_write(&self, data: &[u8]) -> Result<usize, String> {
+        if data.is_empty() || self.master_fd < 0 {
+            return Ok(data.len());
+        }
+        let n = unsafe {
+            libc::write(self.master_fd, data.as_ptr() as *const libc::c_void, data.len() as libc::size_t)
+        };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            return match err.kind() {
+                std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock => Ok(0),
+                _ => Err(format!("PTY 写入失败: {err}")),
+            };
+        }
+        Ok(n as usize)
+    }
+
+    /// Set the grid size, and its size in pixels for image tools.
+    pub fn resize(&self, cols: u16, rows: u16, pixels: (u16, u16)) {
+        if self.master_fd < 0 {
+            return;
+        }
         let ws = libc::winsize {
             ws_row: rows,
             ws_col: cols,
-            ws_xpixel: 0,
-            ws_ypixel: 0,
+            ws_xpixel: pixels.0,
+            ws_ypixel: pixels.1,
         };
         unsafe {
             libc::ioctl(self.master_fd, libc::TIOCSWINSZ, &ws);
         }
     }
+
+    /// Name of the terminal's foreground process (the shell or the
+    /// command it is running).
+    pub fn foreground_command(&self) -> Option<String> {
+        if self.master_fd < 0 {
+            return None;
+        }
+        let pgrp = unsafe { libc::tcgetpgrp(self.master_fd) };
+        if pgrp <= 0 {
+            return None;
+        }
+        process_name(pgrp)
+    }
+}
+
+/// Reap the child once the PTY has closed, waiting up to half a second
+/// for it to exit, and note that in `reaped`. Returns its exit code, or
+/// 128 + the signal that killed it as shells report it.
+fn reap(pid: libc::pid_t, reaped: &AtomicBool) -> Option<i32> {
+    let mut status = 0;
+    for _ in 0..50 {
+        let r = unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) };
+        if r == 0 {
+            thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+        reaped.store(r == pid, Ordering::Relaxed);
+        return match r == pid {
+            true if libc::WIFEXITED(status) => Some(libc::WEXITSTATUS(status)),
+            true if libc::WIFSIGNALED(status) => Some(128 + libc::WTERMSIG(status)),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Whether `pid` has child processes.
+#[cfg(target_os = "macos")]
+pub fn has_children(pid: libc::pid_t) -> bool {
+    let output = std::process::Command::new("pgrep")
+        .args(["-P", &pid.to_string()])
+        .output();
+    matches!(output, Ok(o) if !o.stdout.is_empty())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn has_children(pid: libc::pid_t) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/task/{pid}/children"))
+        .is_ok_and(|c| !c.trim().is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn process_name(pid: libc::pid_t) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let n = unsafe { libc::proc_name(pid, buf.as_mut_ptr().cast(), buf.len() as u32) };
+    (n > 0).then(|| String::from_utf8_lossy(&buf[..n as usize]).into_owned())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn process_name(pid: libc::pid_t) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(comm.trim_end().to_string()).filter(|c| !c.is_empty())
 }
 
 impl Drop for PtyHandle {
     fn drop(&mut self) {
-        unsafe {
-            libc::close(self.master_fd);
+        if self.child_pid > 0 && !self.reaped.load(Ordering::Relaxed) {
+            unsafe {
+                libc::kill(self.child_pid, libc::SIGHUP);
+            }
+        }
+        if self.master_fd >= 0 {
+            unsafe {
+                libc::close(self.master_fd);
+            }
         }
     }
 }
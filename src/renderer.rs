@@ -1,11 +1,19 @@
 use std::collections::{HashMap, VecDeque};
 use std::num::NonZeroU32;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use fontdue::{Font, Metrics};
+use regex_lite::Regex;
 
-use crate::color::{resolve_color, Rgb, CURSOR_BG, CURSOR_FG, DEFAULT_BG, SEARCH_BAR_BG, SEARCH_BG, SEARCH_CURRENT_BG, SELECTION_BG};
-use crate::terminal::Terminal;
+use crate::color::{resolve_color, Palette, Rgb, DEFAULT_BG, DEFAULT_FG, GUTTER_FG, COMMAND_MARK, ERROR_MARK, REC_INDICATOR, SEARCH_BAR_BG, SEARCH_BG, SEARCH_CURRENT_BG, SEARCH_CURRENT_LINE, SEARCH_FLASH_BG};
+use crate::font::{Face, FontFaces};
+use crate::graphics::{Image, ImageSlice};
+use crate::layout::Rect;
+use crate::search::MatchSpan;
+use crate::grid::Damage;
+use crate::terminal::{CursorStyle, MarkKind, Selection, Style, Terminal};
+use crate::url;
 
 #[derive(Clone)]
 pub struct GlyphBitmap {
@@ -13,8 +21,13 @@ pub struct GlyphBitmap {
     pub alpha: Vec<u8>,
 }
 
+/// Cache slots taken by the family's faces, one per `Face`
+const FACES: usize = 4;
+
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
 struct GlyphKey {
+    /// The face's slot for the primary family, past them a fallback font
+    font: usize,
     ch: char,
     px: u16,
 }
@@ -41,8 +54,19 @@ impl GlyphCache {
         self.order.push_back(key);
     }
 
-    pub fn get_or_insert(&mut self, font: &Font, ch: char, px: f32) -> &GlyphBitmap {
+    /// The glyph for `ch` from `faces` in `face`, or from the first fallback
+    /// font that has it when the family doesn't.
+    pub fn get_or_insert(&mut self, faces: &FontFaces, fallbacks: &[Font], face: Face, ch: char, px: f32) -> &GlyphBitmap {
+        let primary = faces.get(face);
+        let fallback = (primary.lookup_glyph_index(ch) == 0)
+            .then(|| fallbacks.iter().position(|f| f.lookup_glyph_index(ch) != 0))
+            .flatten();
+        let (font, index) = match fallback {
+            Some(i) => (&fallbacks[i], FACES + i),
+            None => (primary, face as usize),
+        };
         let key = GlyphKey {
+            font: index,
             ch,
             px: px.round() as u16,
         };
@@ -64,7 +88,7 @@ impl GlyphCache {
 }
 
 pub struct FontAtlas {
-    pub font: Font,
+    pub faces: FontFaces,
     pub fallback_fonts: Vec<Font>,
     pub px: f32,
     pub cell_width: usize,
@@ -76,36 +100,35 @@ pub struct FontAtlas {
 }
 
 impl FontAtlas {
-    pub fn new(font: Font, fallback_fonts: Vec<Font>, px: f32) -> Self {
-        let m = font.metrics('M', px);
-        let h = (m.height as i32 + 4).max(px.ceil() as i32 + 2) as usize;
-        let w = (m.advance_width.ceil() as i32 + 1).max((px * 0.55) as i32) as usize;
-        Self {
-            font,
+    pub fn new(faces: FontFaces, fallback_fonts: Vec<Font>, px: f32) -> Self {
+        let mut atlas = Self {
+            faces,
             fallback_fonts,
             px,
-            cell_width: w.max(1),
-            cell_height: h.max(1),
-            baseline: (px.ceil() as i32),
+            cell_width: 1,
+            cell_height: 1,
+            baseline: 0,
             line_gap: 0,
             cache: Arc::new(Mutex::new(GlyphCache::new(4096))),
-        }
+        };
+        atlas.set_px(px);
+        atlas
     }
 
-    /// Find which font has a glyph for this character
-    pub fn font_for_char(&self, ch: char) -> &Font {
-        // Check primary font first
-        if self.font.lookup_glyph_index(ch) != 0 {
-            return &self.font;
-        }
-        // Try fallbacks
-        for fb in &self.fallback_fonts {
-            if fb.lookup_glyph_index(ch) != 0 {
-                return fb;
-            }
-        }
-        // Default to primary
-        &self.font
+    /// Cell width and height in pixels at font size `px`.
+    pub fn cell_size_at(&self, px: f32) -> (usize, usize) {
+        let m = self.faces.regular.metrics('M', px);
+        let h = (m.height as i32 + 4).max(px.ceil() as i32 + 2) as usize;
+        let w = (m.advance_width.ceil() as i32 + 1).max((px * 0.55) as i32) as usize;
+        (w.max(1), h.max(1))
+    }
+
+    /// Draw at another size. Glyphs are keyed by size, so switching back
+    /// and forth reuses them.
+    pub fn set_px(&mut self, px: f32) {
+        (self.cell_width, self.cell_height) = self.cell_size_at(px);
+        self.px = px;
+        self.baseline = px.ceil() as i32;
     }
 }
 
@@ -149,6 +172,13 @@ impl PixelCanvas {
         }
     }
 
+    fn stroke_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: Rgb) {
+        self.fill_rect(x, y, w, 1, color);
+        self.fill_rect(x, y + h.saturating_sub(1), w, 1, color);
+        self.fill_rect(x, y, 1, h, color);
+        self.fill_rect(x + w.saturating_sub(1), y, 1, h, color);
+    }
+
     fn blend_pixel(&mut self, x: usize, y: usize, fg: Rgb, alpha: u8) {
         if x >= self.width || y >= self.height {
             return;
@@ -162,6 +192,148 @@ impl PixelCanvas {
         );
         self.pixels[idx] = fg.blend_over(bg, alpha).to_u32();
     }
+
+    /// The band of `image` that `slice` shows, at (x, y): the image is
+    /// scaled to `slice.cols`×`slice.rows` cells of `cell` size and cut off
+    /// at `right`.
+    fn draw_image_slice(&mut self, image: &Image, slice: &ImageSlice, x: usize, y: usize, cell: (usize, usize), right: usize) {
+        let (w, h) = (slice.cols * cell.0, slice.rows * cell.1);
+        if image.width == 0 || image.height == 0 || w == 0 || h == 0 {
+            return;
+        }
+        for dy in 0..cell.1 {
+            let sy = (slice.row * cell.1 + dy) * image.height / h;
+            for dx in 0..w.min(right.saturating_sub(x)) {
+                let sx = dx * image.width / w;
+                let i = (sy * image.width + sx) * 4;
+                let Some(p) = image.pixels.get(i..i + 4) else {
+                    return;
+                };
+                self.blend_pixel(x + dx, y + dy, Rgb::new(p[0], p[1], p[2]), p[3]);
+            }
+        }
+    }
+
+    /// Replace each `block`×`block` square with its average color.
+    fn pixelate(&mut self, block: usize) {
+        let block = block.max(1);
+        for y0 in (0..self.height).step_by(block) {
+            for x0 in (0..self.width).step_by(block) {
+                let (x1, y1) = ((x0 + block).min(self.width), (y0 + block).min(self.height));
+                let mut sum = [0usize; 3];
+                for y in y0..y1 {
+                    for &p in &self.pixels[y * self.width + x0..y * self.width + x1] {
+                        sum[0] += (p >> 16 & 0xff) as usize;
+                        sum[1] += (p >> 8 & 0xff) as usize;
+                        sum[2] += (p & 0xff) as usize;
+                    }
+                }
+                let n = (x1 - x0) * (y1 - y0);
+                let avg = Rgb::new((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8);
+                self.fill_rect(x0, y0, x1 - x0, y1 - y0, avg);
+            }
+        }
+    }
+}
+
+/// How an idle window hides its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cover {
+    /// Average each cell-sized block so text can't be read
+    Blur,
+    Blank,
+}
+
+impl Cover {
+    pub fn from_config(s: &str) -> Self {
+        match s {
+            "blank" => Cover::Blank,
+            _ => Cover::Blur,
+        }
+    }
+}
+
+/// Width of the timestamp gutter, in cells (8 for `HH:MM:SS` plus a gap).
+const GUTTER_COLS: usize = 9;
+/// How long the current search match flashes after a jump.
+pub const MATCH_FLASH: Duration = Duration::from_millis(300);
+/// Logical lines whose link columns are remembered between frames
+const LINK_CACHE_LINES: usize = 1024;
+
+/// Context menu drawn in the window, over everything else.
+pub struct Menu {
+    /// Where it was opened, in pixels; it's moved to stay inside the window
+    pub x: usize,
+    pub y: usize,
+    pub items: Vec<String>,
+    /// Entry under the pointer or picked with the arrow keys
+    pub selected: Option<usize>,
+}
+
+/// What the timestamp gutter left of the grid shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamps {
+    Off,
+    /// Age of the line, e.g. `5m`
+    Relative,
+    /// Local wall-clock time, `HH:MM:SS`
+    Absolute,
+}
+
+impl Timestamps {
+    pub fn from_config(s: &str) -> Self {
+        match s {
+            "relative" => Timestamps::Relative,
+            "absolute" => Timestamps::Absolute,
+            _ => Timestamps::Off,
+        }
+    }
+
+    /// Off → relative → absolute → off
+    pub fn next(self) -> Self {
+        match self {
+            Timestamps::Off => Timestamps::Relative,
+            Timestamps::Relative => Timestamps::Absolute,
+            Timestamps::Absolute => Timestamps::Off,
+        }
+    }
+}
+
+/// What a pane's rows were drawn with, besides their cells.
+#[derive(Clone, PartialEq)]
+struct PaneFrame {
+    rect: Rect,
+    px: f32,
+    view_scroll: usize,
+    total: usize,
+    selection: Option<Selection>,
+    palette: Palette,
+    cursor_style: CursorStyle,
+}
+
+/// Everything else a frame was drawn from. While it stays the same, only
+/// damaged rows and the cursor need redrawing.
+#[derive(Clone, PartialEq)]
+struct FrameKey {
+    width: usize,
+    height: usize,
+    px: f32,
+    padding: (usize, usize),
+    focused: bool,
+    recording: bool,
+    paste_progress: Option<f32>,
+    timestamps: Timestamps,
+    bold_is_bright: bool,
+    link_underline: bool,
+    command_block: Option<usize>,
+    /// Bumped when the scroll indicator ticks change
+    scroll_marks: Option<u64>,
+    tab_bar: Vec<(String, bool)>,
+    active_tab: usize,
+    status_line: bool,
+    status_bell: bool,
+    dividers: Vec<Rect>,
+    panes: Vec<PaneFrame>,
 }
 
 pub struct Renderer {
@@ -170,16 +342,96 @@ pub struct Renderer {
     pub padding_x: usize,
     pub padding_y: usize,
     pub cursor_visible: bool,
+    /// Unfocused windows draw a hollow, non-blinking cursor
+    pub focused: bool,
+    /// Draw the recording indicator
+    pub recording: bool,
+    /// Fraction of a large paste sent so far, shown in the corner
+    pub paste_progress: Option<f32>,
+    /// When the view last jumped to a search match; it flashes briefly
+    pub match_flash: Option<Instant>,
+    /// Link destination shown next to the hovered cell (view row, col)
+    pub tooltip: Option<(usize, usize, String)>,
+    pub menu: Option<Menu>,
+    /// Keyboard hint mode labels still to type, at (global row, col) of
+    /// the focused pane
+    pub hint_labels: Vec<(usize, usize, String)>,
+    pub timestamps: Timestamps,
+    /// Bold text in colors 0–7 is drawn with the bright variants
+    pub bold_is_bright: bool,
+    /// Global row of the prompt starting the highlighted command block
+    pub command_block: Option<usize>,
+    /// Font size from the config, restored by Cmd+0
+    pub default_px: f32,
+    /// Draw the scroll indicator when there is scrollback
+    pub scroll_indicator: bool,
+    /// Rows matching one of these get an error tick on the indicator
+    pub error_patterns: Vec<Regex>,
+    /// Indicator ticks for the focused pane; `None` rescans all its rows
+    /// on the next frame
+    pub scroll_marks: Option<ScrollMarks>,
+    /// Bumped whenever `scroll_marks` changes, for the frame key
+    marks_generation: u64,
+    /// Dotted underline under every detected URL and path, and OSC 8 link
+    /// (`[links]`)
+    pub link_underline: bool,
+    /// Link column ranges by logical line text, so unchanged lines skip
+    /// detection
+    link_cache: HashMap<String, Vec<(usize, usize)>>,
+    /// Tab labels and activity flags in bar order; empty with a single tab
+    pub tab_bar: Vec<(String, bool)>,
+    pub active_tab: usize,
+    /// Bottom row kept out of the grid for the search bar and status
+    pub status_line: bool,
+    /// A bell rang since the last key press; shown on the status line
+    pub status_bell: bool,
+    /// Area of the pane with keyboard focus, and the dividers between panes
+    pub pane_rect: Rect,
+    pub dividers: Vec<Rect>,
+    /// Set while the window is idle-covered (`[privacy]`)
+    pub cover: Option<Cover>,
+    /// Areas the last render changed; `None` when it redrew everything
+    pub damage: Option<Vec<Rect>>,
+    /// What the last frame was drawn from, and its cursor cell in each pane
+    last_frame: Option<FrameKey>,
+    last_cursors: Vec<Option<(usize, usize)>>,
 }
 
 impl Renderer {
-    pub fn new(font: Font, fallback_fonts: Vec<Font>, px: f32) -> Self {
+    pub fn new(faces: FontFaces, fallback_fonts: Vec<Font>, px: f32) -> Self {
         Self {
-            atlas: FontAtlas::new(font, fallback_fonts, px),
+            atlas: FontAtlas::new(faces, fallback_fonts, px),
             canvas: PixelCanvas::new(),
             padding_x: 4,
             padding_y: 4,
             cursor_visible: true,
+            focused: true,
+            recording: false,
+            paste_progress: None,
+            match_flash: None,
+            tooltip: None,
+            menu: None,
+            hint_labels: Vec::new(),
+            timestamps: Timestamps::Off,
+            bold_is_bright: false,
+            command_block: None,
+            default_px: px,
+            scroll_indicator: true,
+            error_patterns: Vec::new(),
+            scroll_marks: None,
+            marks_generation: 0,
+            link_underline: false,
+            link_cache: HashMap::new(),
+            tab_bar: Vec::new(),
+            active_tab: 0,
+            status_line: false,
+            status_bell: false,
+            pane_rect: Rect::default(),
+            dividers: Vec::new(),
+            cover: None,
+            damage: None,
+            last_frame: None,
+            last_cursors: Vec::new(),
         }
     }
 
@@ -189,120 +441,729 @@ impl Renderer {
     }
 
     pub fn set_font_size(&mut self, px: f32) {
-        let font = self.atlas.font.clone();
-        let fallbacks = self.atlas.fallback_fonts.clone();
-        self.atlas = FontAtlas::new(font, fallbacks, px);
+        self.atlas.set_px(px);
+    }
+
+    /// Run `draw` at the configured font size: the tab bar, status line
+    /// and search bar don't zoom with the focused pane.
+    fn at_default_size(&mut self, draw: impl FnOnce(&mut Self)) {
+        let px = self.atlas.px;
+        self.atlas.set_px(self.default_px);
+        draw(self);
+        self.atlas.set_px(px);
+    }
+
+    /// Height of a cell at the configured font size, for the window chrome.
+    fn chrome_cell_height(&self) -> usize {
+        self.atlas.cell_size_at(self.default_px).1
+    }
+
+    /// Switch to another font and configured size, dropping cached glyphs.
+    pub fn set_font(&mut self, faces: FontFaces, fallback_fonts: Vec<Font>, px: f32) {
+        self.atlas = FontAtlas::new(faces, fallback_fonts, px);
+        self.default_px = px;
+        self.last_frame = None;
+    }
+
+    pub fn reset_font_size(&mut self) {
+        self.set_font_size(self.default_px);
+    }
+
+    /// Cell width and height in pixels at the current font size.
+    pub fn cell_size(&self) -> (usize, usize) {
+        (self.atlas.cell_width, self.atlas.cell_height)
+    }
+
+    pub fn search_bar_height(&self) -> usize {
+        self.chrome_cell_height() + 4
+    }
+
+    fn status_line_height(&self) -> usize {
+        if self.status_line {
+            self.search_bar_height()
+        } else {
+            0
+        }
+    }
+
+    /// Box with the tooltip text below the hovered cell (above it on the
+    /// last row), kept inside the window and cut with `…` if too long.
+    fn draw_tooltip(&mut self, width: usize) {
+        let Some((view_row, col, text)) = self.tooltip.clone() else {
+            return;
+        };
+        let (cw, ch) = (self.atlas.cell_width, self.atlas.cell_height);
+        let max_chars = (width.saturating_sub(2 * self.padding_x) / cw).saturating_sub(2).max(1);
+        let mut label: String = text.chars().take(max_chars).collect();
+        if text.chars().count() > max_chars {
+            label.pop();
+            label.push('…');
+        }
+        let w = (label.chars().count() + 2) * cw;
+        let h = ch + 4;
+        let cell_x = self.grid_x() + col * cw;
+        let x = cell_x.min(width.saturating_sub(self.padding_x + w)).max(self.padding_x);
+        let row_y = self.grid_y() + view_row * ch;
+        let y = if row_y + 2 * ch + 4 <= self.canvas.height {
+            row_y + ch
+        } else {
+            row_y.saturating_sub(h)
+        };
+        self.canvas.fill_rect(x, y, w, h, SEARCH_BAR_BG);
+        self.canvas.stroke_rect(x, y, w, h, GUTTER_FG);
+        for (i, c) in label.chars().enumerate() {
+            self.draw_glyph(c, Face::Regular, DEFAULT_FG, x + (i + 1) * cw, y + 2);
+        }
+    }
+
+    /// Height of one context menu entry.
+    fn menu_item_height(&self) -> usize {
+        self.atlas.cell_height + 4
+    }
+
+    /// Area the context menu covers, kept inside the window.
+    fn menu_rect(&self, menu: &Menu) -> Rect {
+        let longest = menu.items.iter().map(|i| i.chars().count()).max().unwrap_or(0);
+        let w = (longest + 2) * self.atlas.cell_width;
+        let h = menu.items.len() * self.menu_item_height() + 4;
+        let x = menu.x.min(self.canvas.width.saturating_sub(w));
+        let y = menu.y.min(self.canvas.height.saturating_sub(h));
+        Rect::new(x, y, w, h)
+    }
+
+    /// Index of the context menu entry at pixel `x`, `y`.
+    pub fn menu_item_at(&self, x: usize, y: usize) -> Option<usize> {
+        let menu = self.menu.as_ref()?;
+        let rect = self.menu_rect(menu);
+        if !rect.contains(x, y) {
+            return None;
+        }
+        let i = y.saturating_sub(rect.y + 2) / self.menu_item_height();
+        (i < menu.items.len()).then_some(i)
+    }
+
+    fn draw_menu(&mut self) {
+        let Some(menu) = self.menu.take() else {
+            return;
+        };
+        let rect = self.menu_rect(&menu);
+        let (cw, item_h) = (self.atlas.cell_width, self.menu_item_height());
+        self.canvas.fill_rect(rect.x, rect.y, rect.w, rect.h, SEARCH_BAR_BG);
+        self.canvas.stroke_rect(rect.x, rect.y, rect.w, rect.h, GUTTER_FG);
+        for (i, item) in menu.items.iter().enumerate() {
+            let y = rect.y + 2 + i * item_h;
+            if menu.selected == Some(i) {
+                self.canvas.fill_rect(rect.x + 1, y, rect.w.saturating_sub(2), item_h, SEARCH_BG);
+            }
+            for (j, c) in item.chars().enumerate() {
+                self.draw_glyph(c, Face::Regular, DEFAULT_FG, rect.x + (j + 1) * cw, y + 2);
+            }
+        }
+        self.menu = Some(menu);
+    }
+
+    /// Foreground and background for a cell's style: inverse swaps them,
+    /// dim fades the foreground towards the background.
+    fn cell_colors(&self, style: Style, palette: &Palette) -> (Rgb, Rgb) {
+        let mut fg = resolve_color(style.draw_fg(self.bold_is_bright), palette);
+        let mut bg = resolve_color(style.bg, palette);
+        if style.inverse {
+            std::mem::swap(&mut fg, &mut bg);
+        }
+        if style.dim {
+            fg = fg.blend_over(bg, 160);
+        }
+        (fg, bg)
+    }
+
+    /// Zoom relative to the configured size, e.g. `125%`; `None` at 100%.
+    fn zoom_label(&self) -> Option<String> {
+        let pct = (self.atlas.px / self.default_px * 100.0).round();
+        (pct != 100.0).then(|| format!("{pct}%"))
+    }
+
+    /// Left edge of the focused pane's grid: padding plus the timestamp gutter.
+    pub fn grid_x(&self) -> usize {
+        self.pane_rect.x + self.padding_x + self.gutter_width()
+    }
+
+    /// Top edge of the focused pane's grid.
+    pub fn grid_y(&self) -> usize {
+        self.pane_rect.y + self.padding_y
+    }
+
+    /// The area panes share: the window minus the tab bar and status line.
+    pub fn content_rect(&self, width: usize, height: usize) -> Rect {
+        let top = self.tab_bar_height();
+        Rect::new(0, top, width, height.saturating_sub(top + self.status_line_height()))
+    }
+
+    fn tab_bar_height(&self) -> usize {
+        if self.tab_bar.is_empty() {
+            0
+        } else {
+            self.chrome_cell_height() + 6
+        }
+    }
+
+    fn gutter_width(&self) -> usize {
+        if self.timestamps == Timestamps::Off {
+            0
+        } else {
+            GUTTER_COLS * self.atlas.cell_width
+        }
     }
 
     pub fn grid_size_for_pixels(&self, width: usize, height: usize) -> (usize, usize) {
-        let usable_w = width.saturating_sub(self.padding_x * 2);
+        let area = self.content_rect(width, height);
+        self.grid_size_for_pane(area.w, area.h)
+    }
+
+    /// Grid that fits a pane of `width`×`height` pixels inside its padding.
+    pub fn grid_size_for_pane(&self, width: usize, height: usize) -> (usize, usize) {
+        let usable_w = width.saturating_sub(self.padding_x * 2 + self.gutter_width());
         let usable_h = height.saturating_sub(self.padding_y * 2);
         let cols = (usable_w / self.atlas.cell_width).max(1);
         let rows = (usable_h / self.atlas.cell_height).max(1);
         (cols, rows)
     }
 
-    #[allow(dead_code)]    pub fn surface_size_for_grid(&self, cols: usize, rows: usize) -> (usize, usize) {
+    pub fn surface_size_for_grid(&self, cols: usize, rows: usize) -> (usize, usize) {
         (
-            cols * self.atlas.cell_width + self.padding_x * 2,
-            rows * self.atlas.cell_height + self.padding_y * 2,
+            cols * self.atlas.cell_width + self.padding_x * 2 + self.gutter_width(),
+            rows * self.atlas.cell_height + self.padding_y * 2 + self.tab_bar_height() + self.status_line_height(),
         )
     }
 
-    pub fn render_with_search(&mut self, term: &Terminal, search: &crate::search::SearchState, width: usize, height: usize) {
-        self.render_inner(term, Some(search), width, height);
+    pub fn render_with_search(
+        &mut self,
+        term: &Terminal,
+        others: &[(&Terminal, Rect, f32)],
+        search: &crate::search::SearchState,
+        width: usize,
+        height: usize,
+    ) {
+        self.render_inner(term, others, &[], Some(search), width, height);
     }
 
-    pub fn render(&mut self, term: &Terminal, width: usize, height: usize) {
-        self.render_inner(term, None, width, height);
+    /// Draw the focused pane's `term` and the tab's `others` panes, each at
+    /// its own font size. `damage`
+    /// has each pane's changed rows, focused pane first; when nothing else
+    /// changed since the last frame only those rows and the cursor are drawn.
+    pub fn render(&mut self, term: &Terminal, others: &[(&Terminal, Rect, f32)], damage: &[Damage], width: usize, height: usize) {
+        self.render_inner(term, others, damage, None, width, height);
     }
 
-    fn render_inner(&mut self, term: &Terminal, search: Option<&crate::search::SearchState>, width: usize, height: usize) {
-        self.canvas.resize(width.max(1), height.max(1));
-        self.canvas.clear(DEFAULT_BG);
+    fn frame_key(&mut self, panes: &[(&Terminal, Rect, f32)], width: usize, height: usize) -> FrameKey {
+        let term = panes[0].0;
+        let scroll_marks = (self.scroll_indicator && term.total_lines() > term.rows()).then(|| {
+            self.update_scroll_marks(term);
+            self.marks_generation
+        });
+        FrameKey {
+            width,
+            height,
+            px: self.atlas.px,
+            padding: (self.padding_x, self.padding_y),
+            focused: self.focused,
+            recording: self.recording,
+            paste_progress: self.paste_progress,
+            timestamps: self.timestamps,
+            bold_is_bright: self.bold_is_bright,
+            link_underline: self.link_underline,
+            command_block: self.command_block,
+            scroll_marks,
+            tab_bar: self.tab_bar.clone(),
+            active_tab: self.active_tab,
+            status_line: self.status_line,
+            status_bell: self.status_bell,
+            dividers: self.dividers.clone(),
+            panes: panes
+                .iter()
+                .map(|&(t, rect, px)| PaneFrame {
+                    rect,
+                    px,
+                    view_scroll: t.view_scroll,
+                    total: t.total_lines(),
+                    selection: t.selection.clone(),
+                    palette: t.palette,
+                    cursor_style: t.cursor_style,
+                })
+                .collect(),
+        }
+    }
 
+    /// View rows to redraw in each pane, or `None` if the whole frame must be.
+    fn damaged_rows(
+        &self,
+        key: &FrameKey,
+        cursors: &[Option<(usize, usize)>],
+        damage: &[Damage],
+        full: bool,
+    ) -> Option<Vec<Vec<usize>>> {
+        let last_key = self.last_frame.as_ref()?;
+        let last_cursors = &self.last_cursors;
+        if full || last_key != key || damage.len() != key.panes.len() || key.timestamps == Timestamps::Relative {
+            return None;
+        }
+        let mut all = Vec::new();
+        for (i, d) in damage.iter().enumerate() {
+            let Damage::Rows(screen_rows) = d else {
+                return None;
+            };
+            let view_scroll = key.panes[i].view_scroll;
+            let mut rows: Vec<usize> = screen_rows.iter().map(|r| r + view_scroll).collect();
+            if cursors[i] != last_cursors[i] {
+                rows.extend(cursors[i].iter().chain(&last_cursors[i]).map(|c| c.0));
+            }
+            rows.sort_unstable();
+            rows.dedup();
+            all.push(rows);
+        }
+        Some(all)
+    }
+
+    fn render_inner(
+        &mut self,
+        term: &Terminal,
+        others: &[(&Terminal, Rect, f32)],
+        damage: &[Damage],
+        search: Option<&crate::search::SearchState>,
+        width: usize,
+        height: usize,
+    ) {
+        self.canvas.resize(width.max(1), height.max(1));
+        let px = self.atlas.px;
+        let panes: Vec<(&Terminal, Rect, f32)> = std::iter::once((term, self.pane_rect, px)).chain(others.iter().copied()).collect();
+        let key = self.frame_key(&panes, width, height);
+        let cursors: Vec<_> = panes.iter().enumerate().map(|(i, (t, ..))| self.drawn_cursor(t, i == 0)).collect();
+        let full = search.is_some()
+            || self.tooltip.is_some()
+            || self.menu.is_some()
+            || !self.hint_labels.is_empty()
+            || self.cover.is_some();
+        let damaged = self.damaged_rows(&key, &cursors, damage, full);
+        // Search, tooltips, menu, hint labels and the cover aren't in the key: redraw all after them
+        self.last_frame = (!full).then_some(key);
+        self.last_cursors = cursors;
         let start_global = term.visible_start_global_row();
-        let cursor = if term.view_scroll == 0 {
-            Some(term.cursor_screen_pos())
+
+        if let Some(damaged) = damaged {
+            // Only rows change; overlays drawn over them are put back
+            let mut rects = Vec::new();
+            for (i, &(t, rect, pane_px)) in panes.iter().enumerate() {
+                self.atlas.set_px(pane_px);
+                rects.extend(self.draw_grid(t, rect, i == 0, None, Some(&damaged[i])));
+            }
+            self.atlas.set_px(px);
+            self.draw_command_block(term, start_global);
+            if !self.status_line {
+                let labels = self.labels();
+                self.at_default_size(|r| r.draw_corner_labels(labels, width));
+            }
+            self.damage = Some(rects);
+            return;
+        }
+        self.damage = None;
+        self.canvas.clear(term.palette.bg);
+
+        for &(other, rect, other_px) in others {
+            self.atlas.set_px(other_px);
+            self.draw_grid(other, rect, false, None, None);
+        }
+        self.atlas.set_px(px);
+        let spans = search.map(|s| s.row_spans(start_global, term.rows(), term.cols()));
+        self.draw_grid(term, self.pane_rect, true, spans.as_deref(), None);
+        for divider in self.dividers.clone() {
+            self.canvas.fill_rect(divider.x, divider.y, divider.w, divider.h, GUTTER_FG);
+        }
+
+        self.at_default_size(|r| r.draw_tab_bar(term.palette.bg, width));
+        self.draw_command_block(term, start_global);
+        self.draw_scroll_indicator(term, start_global);
+        self.draw_hint_labels(term, start_global);
+        self.draw_tooltip(width);
+
+        let labels = self.labels();
+        self.at_default_size(|r| {
+            if r.status_line {
+                r.draw_status_line(term, labels, width, height);
+            } else {
+                r.draw_corner_labels(labels, width);
+            }
+            // The search bar goes over the status line
+            if let Some(s) = search.filter(|s| s.active) {
+                r.draw_search_bar(s, width, height);
+            }
+        });
+        self.draw_menu();
+
+        match self.cover {
+            Some(Cover::Blur) => self.canvas.pixelate(self.atlas.cell_height),
+            Some(Cover::Blank) => self.canvas.clear(term.palette.bg),
+            None => {}
+        }
+    }
+
+    fn draw_search_bar(&mut self, s: &crate::search::SearchState, width: usize, height: usize) {
+        let bar_h = self.search_bar_height();
+        let bar_y = height.saturating_sub(bar_h);
+        self.canvas.fill_rect(0, bar_y, width, bar_h, SEARCH_BAR_BG);
+        let label = format!("🔍 {}", s.query);
+        let match_info = if s.matches.is_empty() {
+            if s.query.is_empty() { String::new() } else { crate::i18n::tr(crate::i18n::Msg::SearchNoMatch).to_string() }
         } else {
-            None
+            format!(" ({}/{})", s.current + 1, s.matches.len())
         };
-        for view_row in 0..term.rows() {
+        let text = format!("{}{}", label, match_info);
+        let mut x = 4;
+        let y = bar_y + 2;
+        for ch in text.chars() {
+            if x + self.atlas.cell_width > width { break; }
+            self.draw_glyph(ch, Face::Regular, Rgb::new(0xee, 0xee, 0xee), x, y);
+            x += self.atlas.cell_width;
+        }
+    }
+
+    /// Zoom and paste progress, shown left of the recording dot.
+    fn labels(&self) -> Vec<String> {
+        let paste = self.paste_progress.map(|p| {
+            let pct = (p * 100.0).floor().to_string();
+            crate::i18n::tr(crate::i18n::Msg::PasteProgress).replace("{pct}", &pct)
+        });
+        [self.zoom_label(), paste].into_iter().flatten().collect()
+    }
+
+    /// Recording dot and labels in the top-right corner, without a status line.
+    fn draw_corner_labels(&mut self, labels: Vec<String>, width: usize) {
+        let grid_y = self.tab_bar_height() + self.padding_y;
+        let mut right = width.saturating_sub(self.padding_x);
+        if self.recording {
+            let d = (self.atlas.cell_height / 2).max(4);
+            let x = right.saturating_sub(d + 2);
+            self.canvas.fill_rect(x, grid_y + 2, d, d, REC_INDICATOR);
+            right = x.saturating_sub(self.atlas.cell_width);
+        }
+        for label in labels {
+            let w = label.chars().count() * self.atlas.cell_width;
+            let x0 = right.saturating_sub(w);
+            self.canvas.fill_rect(x0, grid_y, w, self.atlas.cell_height, SEARCH_BAR_BG);
+            for (i, ch) in label.chars().enumerate() {
+                self.draw_glyph(ch, Face::Regular, GUTTER_FG, x0 + i * self.atlas.cell_width, grid_y);
+            }
+            right = x0.saturating_sub(self.atlas.cell_width);
+        }
+    }
+
+    /// The cell the cursor is drawn on in a pane, if any.
+    fn drawn_cursor(&self, term: &Terminal, active: bool) -> Option<(usize, usize)> {
+        let visible = !active || self.cursor_visible;
+        (visible && term.view_scroll == 0).then(|| term.cursor_screen_pos())
+    }
+
+    /// One pane's rows (with timestamps and cursor) inside `rect`; `active`
+    /// is the pane with keyboard focus. With `only`, just those view rows
+    /// are cleared and redrawn, and their areas returned.
+    fn draw_grid(
+        &mut self,
+        term: &Terminal,
+        rect: Rect,
+        active: bool,
+        spans: Option<&[Vec<MatchSpan>]>,
+        only: Option<&[usize]>,
+    ) -> Vec<Rect> {
+        let palette = &term.palette;
+        let start_global = term.visible_start_global_row();
+        let cursor = self.drawn_cursor(term, active);
+        let now = crate::unix_now();
+        let gutter_x = rect.x + self.padding_x;
+        let grid_x = gutter_x + self.gutter_width();
+        let grid_y = rect.y + self.padding_y;
+        let flashing = self.match_flash.is_some_and(|t| t.elapsed() < MATCH_FLASH);
+        // Other panes keep a steady hollow cursor
+        let focused = active && self.focused;
+        let all: Vec<usize> = (0..term.rows()).collect();
+        let mut redrawn = Vec::new();
+        // Rows scrolled below the view are damaged too but not shown
+        for &view_row in only.unwrap_or(&all).iter().filter(|&&r| r < term.rows()) {
             let global_row = start_global + view_row;
             let Some(row) = term.visible_line(view_row) else {
                 continue;
             };
+            if only.is_some() {
+                let y = grid_y + view_row * self.atlas.cell_height;
+                let w = (self.gutter_width() + term.cols() * self.atlas.cell_width).min(self.canvas.width.saturating_sub(gutter_x));
+                let h = self.atlas.cell_height.min(self.canvas.height.saturating_sub(y));
+                self.canvas.fill_rect(gutter_x, y, w, h, palette.bg);
+                redrawn.push(Rect::new(gutter_x, y, w, h));
+            }
+            // Stamp only the first row of a logical line
+            let continued = global_row > 0
+                && term.line_at_global(global_row - 1).is_some_and(|r| r.wrapped);
+            if self.timestamps != Timestamps::Off && row.time != 0 && !continued {
+                let label = match self.timestamps {
+                    Timestamps::Relative => relative_time(now.saturating_sub(row.time)),
+                    _ => crate::clock_time(row.time),
+                };
+                let y = grid_y + view_row * self.atlas.cell_height;
+                let pad = (GUTTER_COLS - 1).saturating_sub(label.chars().count());
+                for (i, ch) in label.chars().enumerate() {
+                    let x = gutter_x + (pad + i) * self.atlas.cell_width;
+                    self.draw_glyph(ch, Face::Regular, GUTTER_FG, x, y);
+                }
+            }
+            let row_spans = spans.map_or(&[][..], |s| &s[view_row][..]);
+            let links = if self.link_underline { self.row_links(term, global_row) } else { Vec::new() };
             for col in 0..term.cols() {
-                let cell = row.cells[col];
+                // Scrollback rows may stop at their last non-blank cell
+                let cell = row.cells.get(col).copied().unwrap_or_default();
                 if cell.wide_cont {
                     continue;
                 }
-                let mut bg = resolve_color(cell.style.bg);
-                let mut fg = resolve_color(cell.style.fg);
-                if term.is_selected(global_row, col) {
-                    bg = SELECTION_BG;
+                let (mut fg, mut bg) = self.cell_colors(cell.style, palette);
+                let hit = row_spans.iter().filter(|s| (s.start..s.end).contains(&col));
+                let (highlighted, current) = hit.fold((false, false), |(_, cur), s| (true, cur || s.current));
+                if current {
+                    bg = if flashing { SEARCH_FLASH_BG } else { SEARCH_CURRENT_BG };
+                    fg = Rgb::new(0, 0, 0);
+                } else if highlighted {
+                    bg = SEARCH_BG;
                 }
-                if let Some(s) = search {
-                    if s.is_current_highlight(global_row, col) {
-                        bg = SEARCH_CURRENT_BG;
-                        fg = Rgb::new(0, 0, 0);
-                    } else if s.is_highlighted(global_row, col) {
-                        bg = SEARCH_BG;
-                    }
+                // Selection wins so it stays visible over search matches
+                if term.is_selected(global_row, col) {
+                    bg = palette.selection;
+                    fg = self.cell_colors(cell.style, palette).0;
                 }
-                let is_cursor = self.cursor_visible && matches!(cursor, Some((cr, cc)) if view_row == cr && col == cc);
-                if is_cursor && term.cursor_style == crate::terminal::CursorStyle::Block {
-                    bg = CURSOR_BG;
-                    fg = CURSOR_FG;
+                let is_cursor = matches!(cursor, Some((cr, cc)) if view_row == cr && col == cc);
+                if is_cursor && focused && term.cursor_style == crate::terminal::CursorStyle::Block {
+                    bg = palette.cursor;
+                    fg = palette.cursor_text;
                 }
-                let x = self.padding_x + col * self.atlas.cell_width;
-                let y = self.padding_y + view_row * self.atlas.cell_height;
+                let x = grid_x + col * self.atlas.cell_width;
+                let y = grid_y + view_row * self.atlas.cell_height;
                 self.canvas
                     .fill_rect(x, y, self.atlas.cell_width, self.atlas.cell_height, bg);
-                if cell.ch != ' ' {
-                    self.draw_glyph(cell.ch, fg, x, y);
+                if cell.ch != ' ' && !cell.style.hidden {
+                    let face = Face::of(cell.style.bold, cell.style.italic);
+                    self.draw_glyph(cell.ch, face, fg, x, y);
                 }
-                if is_cursor && term.cursor_style == crate::terminal::CursorStyle::Beam {
+                let wide = row.cells.get(col + 1).is_some_and(|c| c.wide_cont);
+                let w = if wide { 2 * self.atlas.cell_width } else { self.atlas.cell_width };
+                let line_h = (self.atlas.cell_height / 16).max(1);
+                if cell.style.underline {
+                    let uy = y + self.atlas.cell_height.saturating_sub(line_h + 1);
+                    self.canvas.fill_rect(x, uy, w, line_h, fg);
+                }
+                if cell.style.strikethrough {
+                    self.canvas.fill_rect(x, y + self.atlas.cell_height / 2, w, line_h, fg);
+                }
+                let linked = (self.link_underline && cell.link != 0) || links.iter().any(|&(s, e)| (s..e).contains(&col));
+                if linked && !cell.style.underline {
+                    let uy = y + self.atlas.cell_height.saturating_sub(line_h + 1);
+                    for dx in (0..w).step_by(2) {
+                        self.canvas.blend_pixel(x + dx, uy, fg, 0x60);
+                    }
+                }
+                if current {
+                    let uy = y + self.atlas.cell_height.saturating_sub(2);
+                    self.canvas.fill_rect(x, uy, w, 2, SEARCH_CURRENT_LINE);
+                }
+                if is_cursor && !focused {
+                    self.canvas.stroke_rect(x, y, self.atlas.cell_width, self.atlas.cell_height, palette.cursor);
+                } else if is_cursor && term.cursor_style == crate::terminal::CursorStyle::Beam {
                     // 2px wide beam at left edge
-                    self.canvas.fill_rect(x, y, 2, self.atlas.cell_height, CURSOR_BG);
+                    self.canvas.fill_rect(x, y, 2, self.atlas.cell_height, palette.cursor);
                 } else if is_cursor && term.cursor_style == crate::terminal::CursorStyle::Underline {
                     // 2px underline at bottom
                     let uy = y + self.atlas.cell_height.saturating_sub(2);
-                    self.canvas.fill_rect(x, uy, self.atlas.cell_width, 2, CURSOR_BG);
+                    self.canvas.fill_rect(x, uy, self.atlas.cell_width, 2, palette.cursor);
+                }
+            }
+            let cell = (self.atlas.cell_width, self.atlas.cell_height);
+            for slice in &row.images {
+                if let Some(image) = term.images.get(slice.id) {
+                    let x = grid_x + slice.col * cell.0;
+                    let y = grid_y + view_row * cell.1;
+                    self.canvas.draw_image_slice(image, slice, x, y, cell, grid_x + term.cols() * cell.0);
                 }
             }
         }
+        redrawn
+    }
 
-        // Draw search bar at bottom if active
-        if let Some(s) = search {
-            if s.active {
-                let bar_h = self.atlas.cell_height + 4;
-                let bar_y = height.saturating_sub(bar_h);
-                self.canvas.fill_rect(0, bar_y, width, bar_h, SEARCH_BAR_BG);
-                let label = format!("🔍 {}", s.query);
-                let match_info = if s.matches.is_empty() {
-                    if s.query.is_empty() { String::new() } else { " (无匹配)".to_string() }
-                } else {
-                    format!(" ({}/{})", s.current + 1, s.matches.len())
-                };
-                let text = format!("{}{}", label, match_info);
-                let mut x = 4;
-                let y = bar_y + 2;
-                for ch in text.chars() {
-                    if x + self.atlas.cell_width > width { break; }
-                    self.draw_glyph(ch, Rgb::new(0xee, 0xee, 0xee), x, y);
-                    x += self.atlas.cell_width;
-                }
+    /// Columns [start, end) of `global_row` covered by a link, detected
+    /// over its whole logical line so wrapped URLs are underlined in full.
+    fn row_links(&mut self, term: &Terminal, global_row: usize) -> Vec<(usize, usize)> {
+        let (text, first) = term.logical_line_text(global_row);
+        if self.link_cache.len() >= LINK_CACHE_LINES && !self.link_cache.contains_key(&text) {
+            self.link_cache.clear();
+        }
+        let spans = self
+            .link_cache
+            .entry(text)
+            .or_insert_with_key(|text| url::detect_links(text).into_iter().map(|l| (l.start, l.end)).collect());
+        let row_start = (global_row - first) * term.cols();
+        let row_end = row_start + term.cols();
+        spans
+            .iter()
+            .filter(|&&(s, e)| s < row_end && e > row_start)
+            .map(|&(s, e)| (s.max(row_start) - row_start, e.min(row_end) - row_start))
+            .collect()
+    }
+
+    /// The reserved bottom row: scroll position on the left; recording,
+    /// zoom, paste progress, tab and bell indicators on the right.
+    fn draw_status_line(&mut self, term: &Terminal, labels: Vec<String>, width: usize, height: usize) {
+        let (cw, ch) = (self.atlas.cell_width, self.atlas.cell_height);
+        let bar_h = self.status_line_height();
+        let y0 = height.saturating_sub(bar_h);
+        let y = y0 + (bar_h - ch) / 2;
+        self.canvas.fill_rect(0, y0, width, bar_h, SEARCH_BAR_BG);
+        let position = if term.view_scroll > 0 {
+            format!("↑{} / {}", term.view_scroll, term.total_lines() - term.rows())
+        } else {
+            format!("{}×{}", term.cols(), term.rows())
+        };
+        for (i, c) in position.chars().enumerate() {
+            self.draw_glyph(c, Face::Regular, GUTTER_FG, self.padding_x + i * cw, y);
+        }
+        let mut right_items = Vec::new();
+        if self.status_bell {
+            right_items.push("🔔".to_string());
+        }
+        if !self.tab_bar.is_empty() {
+            let busy = self.tab_bar.iter().enumerate().any(|(i, t)| t.1 && i != self.active_tab);
+            let dot = if busy { "● " } else { "" };
+            right_items.push(format!("{dot}{}/{}", self.active_tab + 1, self.tab_bar.len()));
+        }
+        right_items.extend(labels);
+        let mut right = width.saturating_sub(self.padding_x);
+        for item in right_items {
+            let x0 = right.saturating_sub(item.chars().count() * cw);
+            for (i, c) in item.chars().enumerate() {
+                self.draw_glyph(c, Face::Regular, GUTTER_FG, x0 + i * cw, y);
+            }
+            right = x0.saturating_sub(2 * cw);
+        }
+        if self.recording {
+            let d = (ch / 2).max(4);
+            self.canvas.fill_rect(right.saturating_sub(d), y0 + (bar_h - d) / 2, d, d, REC_INDICATOR);
+        }
+    }
+
+    /// Equal-width tabs across the top; the active one takes the window
+    /// background, tabs with unseen output get a dot.
+    fn draw_tab_bar(&mut self, bg: Rgb, width: usize) {
+        if self.tab_bar.is_empty() {
+            return;
+        }
+        let (cw, ch) = (self.atlas.cell_width, self.atlas.cell_height);
+        let bar_h = self.tab_bar_height();
+        self.canvas.fill_rect(0, 0, width, bar_h, SEARCH_BAR_BG);
+        let tab_w = width / self.tab_bar.len();
+        let max_chars = (tab_w / cw).saturating_sub(2).max(1);
+        for (i, (label, activity)) in self.tab_bar.clone().into_iter().enumerate() {
+            let x0 = i * tab_w;
+            let active = i == self.active_tab;
+            if active {
+                self.canvas.fill_rect(x0, 0, tab_w, bar_h, bg);
+            } else if i > 0 && i != self.active_tab + 1 {
+                self.canvas.fill_rect(x0, 4, 1, bar_h - 8, GUTTER_FG);
+            }
+            let mut text: String = if activity { format!("● {label}") } else { label };
+            if text.chars().count() > max_chars {
+                text = text.chars().take(max_chars.saturating_sub(1)).collect();
+                text.push('…');
+            }
+            let fg = if active { Rgb::new(0xee, 0xee, 0xee) } else { GUTTER_FG };
+            let x = x0 + (tab_w.saturating_sub(text.chars().count() * cw)) / 2;
+            for (j, c) in text.chars().enumerate() {
+                self.draw_glyph(c, Face::Regular, fg, x + j * cw, (bar_h - ch) / 2);
+            }
+        }
+    }
+
+    /// Bar left of the grid along the highlighted command block, and a rule
+    /// above its prompt.
+    fn draw_command_block(&mut self, term: &Terminal, start_global: usize) {
+        let Some(start) = self.command_block else {
+            return;
+        };
+        if !term.line_at_global(start).is_some_and(|r| r.has_mark(MarkKind::Prompt)) {
+            return;
+        }
+        let end = term.next_prompt(start).unwrap_or(term.total_lines());
+        let first = start.max(start_global);
+        let last = end.min(start_global + term.rows());
+        if first >= last {
+            return;
+        }
+        let ch = self.atlas.cell_height;
+        let x = self.grid_x().saturating_sub(3);
+        let y = self.grid_y() + (first - start_global) * ch;
+        self.canvas.fill_rect(x, y, 2, (last - first) * ch, COMMAND_MARK);
+        if start >= start_global {
+            let w = term.cols() * self.atlas.cell_width;
+            self.canvas.fill_rect(self.grid_x(), y, w, 1, COMMAND_MARK);
+        }
+    }
+
+    /// A thin bar in the right padding: the visible part of the history,
+    /// with ticks at prompts (OSC 133) and error rows.
+    /// Bring the focused pane's scroll indicator ticks up to date.
+    fn update_scroll_marks(&mut self, term: &Terminal) {
+        if self.scroll_marks.is_none() {
+            self.marks_generation += 1;
+        }
+        let marks = self.scroll_marks.get_or_insert_with(|| ScrollMarks::new(term));
+        if marks.update(term, &self.error_patterns) {
+            self.marks_generation += 1;
+        }
+    }
+
+    /// Keyboard hint mode labels over the first cell of their targets.
+    fn draw_hint_labels(&mut self, term: &Terminal, start_global: usize) {
+        let (cw, ch) = (self.atlas.cell_width, self.atlas.cell_height);
+        for (row, col, label) in std::mem::take(&mut self.hint_labels) {
+            if row < start_global || row >= start_global + term.rows() {
+                continue;
             }
+            let x = self.grid_x() + col * cw;
+            let y = self.grid_y() + (row - start_global) * ch;
+            self.canvas.fill_rect(x, y, label.chars().count() * cw, ch, SEARCH_CURRENT_BG);
+            for (i, c) in label.chars().enumerate() {
+                self.draw_glyph(c, Face::Bold, Rgb::new(0, 0, 0), x + i * cw, y);
+            }
+            self.hint_labels.push((row, col, label));
         }
     }
 
-    fn draw_glyph(&mut self, ch: char, color: Rgb, cell_x: usize, cell_y: usize) {
-        let font = self.atlas.font_for_char(ch);
+    fn draw_scroll_indicator(&mut self, term: &Terminal, start_global: usize) {
+        let total = term.total_lines();
+        if !self.scroll_indicator || total <= term.rows() {
+            return;
+        }
+        let rect = self.pane_rect;
+        let top = self.grid_y();
+        let h = rect.h.saturating_sub(2 * self.padding_y);
+        self.update_scroll_marks(term);
+        let Some(marks) = &self.scroll_marks else {
+            return;
+        };
+        let x = (rect.x + rect.w).saturating_sub(3);
+        let y_of = |row: usize| top + row * h / total;
+        let thumb = GUTTER_FG.blend_over(term.palette.bg, 90);
+        self.canvas.fill_rect(x, y_of(start_global), 3, (term.rows() * h / total).max(2), thumb);
+        for &(row, color) in marks.settled.iter().chain(&marks.recent) {
+            self.canvas.fill_rect(x, y_of(row), 3, 2, color);
+        }
+    }
+
+    fn draw_glyph(&mut self, ch: char, face: Face, color: Rgb, cell_x: usize, cell_y: usize) {
         let glyph = {
             let mut cache = self.atlas.cache.lock().unwrap();
             cache
-                .get_or_insert(font, ch, self.atlas.px)
+                .get_or_insert(&self.atlas.faces, &self.atlas.fallback_fonts, face, ch, self.atlas.px)
                 .clone()
         };
         if glyph.metrics.width == 0 || glyph.metrics.height == 0 {
@@ -332,3 +1193,111 @@ impl Renderer {
         (w, h)
     }
 }
+
+/// Scroll indicator ticks (global row, color) kept across frames. Settled
+/// scrollback rows are scanned once, as they arrive, and shifted when rows
+/// are evicted; only the rows after them are rescanned following output.
+pub struct ScrollMarks {
+    /// Ticks for settled rows `..scanned`
+    settled: Vec<(usize, Rgb)>,
+    scanned: usize,
+    /// Ticks for the rows after `scanned`
+    recent: Vec<(usize, Rgb)>,
+    /// `Terminal::evicted_rows` and width as of the last update
+    evicted: u64,
+    cols: usize,
+    /// Output arrived since the last update
+    stale: bool,
+}
+
+impl ScrollMarks {
+    fn new(term: &Terminal) -> Self {
+        Self {
+            settled: Vec::new(),
+            scanned: 0,
+            recent: Vec::new(),
+            evicted: term.evicted_rows,
+            cols: term.cols(),
+            stale: true,
+        }
+    }
+
+    /// Rescan what output may have changed on the next update.
+    pub fn touch(&mut self) {
+        self.stale = true;
+    }
+
+    /// Catch up with `term`. Returns whether any tick changed.
+    fn update(&mut self, term: &Terminal, patterns: &[Regex]) -> bool {
+        if !self.stale {
+            return false;
+        }
+        self.stale = false;
+        let mut changed = false;
+        // Rows reflow to a new width
+        if term.cols() != self.cols {
+            *self = Self::new(term);
+            self.stale = false;
+            changed = true;
+        }
+        let evicted = (term.evicted_rows - self.evicted) as usize;
+        if evicted > 0 {
+            self.evicted = term.evicted_rows;
+            self.settled.retain_mut(|(row, _)| row.checked_sub(evicted).map(|r| *row = r).is_some());
+            self.scanned = self.scanned.saturating_sub(evicted);
+            changed = true;
+        }
+        let settled = term.settled_rows();
+        if settled < self.scanned {
+            self.settled.retain(|&(row, _)| row < settled);
+            self.scanned = settled;
+            changed = true;
+        }
+        let before = self.settled.len();
+        scan_marks(term, patterns, self.scanned..settled, &mut self.settled);
+        self.scanned = settled;
+        let mut recent = Vec::new();
+        scan_marks(term, patterns, settled..term.total_lines(), &mut recent);
+        changed |= self.settled.len() != before || recent != self.recent;
+        self.recent = recent;
+        changed
+    }
+}
+
+/// Add ticks for `rows` to `marks`: prompt rows, then rows whose text
+/// matches an error pattern.
+fn scan_marks(term: &Terminal, patterns: &[Regex], rows: std::ops::Range<usize>, marks: &mut Vec<(usize, Rgb)>) {
+    let mut text = String::new();
+    for r in rows {
+        let Some(row) = term.line_at_global(r) else {
+            continue;
+        };
+        if row.has_mark(MarkKind::Prompt) {
+            marks.push((r, COMMAND_MARK));
+        } else if !patterns.is_empty() {
+            text.clear();
+            text.extend(row.cells.iter().filter(|c| !c.wide_cont).map(|c| c.ch));
+            if !text.trim().is_empty() && patterns.iter().any(|p| p.is_match(&text)) {
+                marks.push((r, ERROR_MARK));
+            }
+        }
+    }
+}
+
+/// Compile `scrolling.error_patterns`, skipping invalid ones.
+pub fn compile_error_patterns(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|p| Regex::new(p).map_err(|e| log::warn!("错误标记正则无效 ({p}): {e}")).ok())
+        .collect()
+}
+
+/// Compact age such as `42s`, `5m`, `3h` or `2d`.
+fn relative_time(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
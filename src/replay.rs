@@ -0,0 +1,133 @@
+//! `--replay`: plays an asciinema recording into the terminal through the
+//! same output path as a live PTY.
+
+use std::time::{Duration, Instant};
+
+use moterm_core::cast::Cast;
+
+/// What a stretch of the recording does to the terminal.
+pub enum Step {
+    Output(Vec<u8>),
+    /// An `r` event: the recorded terminal became (cols, rows)
+    Resize(usize, usize),
+}
+
+pub struct Player {
+    cast: Cast,
+    /// Index of the next event to emit
+    pos: usize,
+    pub speed: f64,
+    pub paused: bool,
+    /// Wall-clock instant corresponding to `base` in recording time
+    started: Instant,
+    base: f64,
+}
+
+impl Player {
+    pub fn new(cast: Cast, speed: f64) -> Self {
+        Self {
+            cast,
+            pos: 0,
+            speed: if speed > 0.0 { speed } else { 1.0 },
+            paused: false,
+            started: Instant::now(),
+            base: 0.0,
+        }
+    }
+
+    /// Recorded grid size.
+    pub fn size(&self) -> (usize, usize) {
+        (self.cast.width, self.cast.height)
+    }
+
+    /// Current position in recording time (seconds).
+    pub fn position(&self) -> f64 {
+        if self.paused {
+            self.base
+        } else {
+            self.base + self.started.elapsed().as_secs_f64() * self.speed
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.pos >= self.cast.events.len()
+    }
+
+    /// All events that are due now.
+    pub fn take_due(&mut self) -> Vec<Step> {
+        let now = self.position();
+        self.take_until(now)
+    }
+
+    fn take_until(&mut self, t: f64) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while let Some(ev) = self.cast.events.get(self.pos) {
+            if ev.time > t {
+                break;
+            }
+            match (ev.kind, steps.last_mut()) {
+                ('o', Some(Step::Output(out))) => out.extend_from_slice(ev.data.as_bytes()),
+                ('o', _) => steps.push(Step::Output(ev.data.as_bytes().to_vec())),
+                ('r', _) => match ev.data.split_once('x').map(|(c, r)| (c.parse(), r.parse())) {
+                    Some((Ok(cols), Ok(rows))) if cols > 0 && rows > 0 => steps.push(Step::Resize(cols, rows)),
+                    _ => log::warn!("回放中的尺寸事件无效: {}", ev.data),
+                },
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        steps
+    }
+
+    /// When the next event is due, for the event loop's wake-up.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.paused {
+            return None;
+        }
+        let ev = self.cast.events.get(self.pos)?;
+        let wait = ((ev.time - self.position()) / self.speed).max(0.0);
+        Some(Instant::now() + Duration::from_secs_f64(wait))
+    }
+
+    fn rebase(&mut self, t: f64) {
+        self.base = t;
+        self.started = Instant::now();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        let t = self.position();
+        self.paused = !self.paused;
+        self.rebase(t);
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        let t = self.position();
+        self.speed = speed.clamp(0.125, 64.0);
+        self.rebase(t);
+    }
+
+    /// Jump by `delta` seconds. Returns `(reset, steps)`: when seeking
+    /// backwards the terminal must be reset to the recorded [`Self::size`]
+    /// and `steps` replay everything up to the new position.
+    pub fn seek(&mut self, delta: f64) -> (bool, Vec<Step>) {
+        let target = (self.position() + delta).max(0.0);
+        let reset = delta < 0.0;
+        if reset {
+            self.pos = 0;
+        }
+        let out = self.take_until(target);
+        self.rebase(target);
+        (reset, out)
+    }
+
+    pub fn status(&self) -> String {
+        let state = if self.finished() {
+            "■"
+        } else if self.paused {
+            "⏸"
+        } else {
+            "▶"
+        };
+        format!("{state} {:.1}s ({}x)", self.position(), self.speed)
+    }
+}
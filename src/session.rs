@@ -0,0 +1,62 @@
+//! `[window] restore_session`: the open windows and tabs (directory and
+//! title, not scrollback), saved on quit and reopened on the next launch.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Session {
+    #[serde(default)]
+    pub windows: Vec<WindowState>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WindowState {
+    /// In tab bar order
+    pub tabs: Vec<TabState>,
+    #[serde(default)]
+    pub active: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TabState {
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub title: String,
+}
+
+fn path() -> PathBuf {
+    crate::config::config_dir().join("session.toml")
+}
+
+/// The session saved at the last quit; empty if there is none.
+pub fn load() -> Session {
+    let Ok(text) = std::fs::read_to_string(path()) else {
+        return Session::default();
+    };
+    toml::from_str(&text).unwrap_or_else(|e| {
+        log::warn!("会话文件解析失败: {e}");
+        Session::default()
+    })
+}
+
+/// Save the session; with no windows left the file is removed.
+pub fn save(session: &Session) {
+    let path = path();
+    if session.windows.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+    let result = toml::to_string(session)
+        .map_err(|e| e.to_string())
+        .and_then(|text| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&path, text).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log::error!("保存会话失败 {}: {e}", path.display());
+    }
+}
@@ -0,0 +1,85 @@
+//! `--install-shell-integration`: writes the zsh/bash/fish scripts that emit
+//! OSC 7 and OSC 133, then offers to load them from each shell's rc file.
+
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::file_error;
+use crate::i18n::{tr, Msg};
+
+/// (shell, script file, contents)
+const SCRIPTS: [(&str, &str, &str); 3] = [
+    ("zsh", "moterm.zsh", include_str!("../shell-integration/moterm.zsh")),
+    ("bash", "moterm.bash", include_str!("../shell-integration/moterm.bash")),
+    ("fish", "moterm.fish", include_str!("../shell-integration/moterm.fish")),
+];
+
+pub fn install() -> Result<(), String> {
+    let dir = crate::config::config_dir().join("shell");
+    fs::create_dir_all(&dir).map_err(|e| file_error(Msg::CreateDirFailed, &dir, e))?;
+    let login_shell = std::env::var("SHELL").unwrap_or_default();
+    for (shell, name, script) in SCRIPTS {
+        let path = dir.join(name);
+        fs::write(&path, script).map_err(|e| file_error(Msg::WriteFailed, &path, e))?;
+        println!("{}", tr(Msg::ShellScriptWritten).replace("{path}", &path.display().to_string()));
+
+        // Only touch rc files of shells the user actually has set up
+        let Some(rc) = rc_file(shell) else { continue };
+        if !rc.exists() && !login_shell.ends_with(shell) {
+            continue;
+        }
+        let rc_name = rc.display().to_string();
+        let line = source_line(shell, &path);
+        if fs::read_to_string(&rc).unwrap_or_default().contains(&line) {
+            println!("{}", tr(Msg::ShellAlreadySourced).replace("{rc}", &rc_name));
+            continue;
+        }
+        if !ask(&tr(Msg::ShellAddSource).replace("{rc}", &rc_name)) {
+            continue;
+        }
+        append(&rc, &format!("\n# moterm shell integration\n{line}\n"))?;
+        println!("{}", tr(Msg::ShellSourceAdded).replace("{rc}", &rc_name));
+    }
+    Ok(())
+}
+
+fn rc_file(shell: &str) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    Some(match shell {
+        "zsh" => std::env::var_os("ZDOTDIR").map_or(home, PathBuf::from).join(".zshrc"),
+        "bash" => home.join(".bashrc"),
+        _ => home.join(".config/fish/config.fish"),
+    })
+}
+
+fn source_line(shell: &str, script: &Path) -> String {
+    let path = script.display();
+    if shell == "fish" {
+        format!("test -f \"{path}\"; and source \"{path}\"")
+    } else {
+        format!("[ -f \"{path}\" ] && source \"{path}\"")
+    }
+}
+
+fn ask(question: &str) -> bool {
+    print!("{question}");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn append(path: &Path, text: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| file_error(Msg::CreateDirFailed, parent, e))?;
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| f.write_all(text.as_bytes()))
+        .map_err(|e| file_error(Msg::WriteFailed, path, e))
+}
@@ -0,0 +1,44 @@
+//! SIGTERM and SIGHUP (logout, shutdown, `kill`): quit through the event
+//! loop so recordings are flushed and shells get SIGHUP, instead of dying
+//! mid-write.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::AppEvent;
+
+/// Write end of the pipe the handler wakes the watcher thread through.
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn on_signal(sig: libc::c_int) {
+    // Only async-signal-safe calls here
+    let byte = sig as u8;
+    unsafe {
+        libc::write(PIPE.load(Ordering::Relaxed), (&byte as *const u8).cast(), 1);
+    }
+}
+
+/// Turn SIGTERM / SIGHUP into `AppEvent::Terminate`.
+pub fn install(proxy: EventLoopProxy<AppEvent>) {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        log::warn!("信号管道创建失败: {}", std::io::Error::last_os_error());
+        return;
+    }
+    PIPE.store(fds[1], Ordering::Relaxed);
+    std::thread::spawn(move || {
+        let mut sig = 0u8;
+        while unsafe { libc::read(fds[0], (&mut sig as *mut u8).cast(), 1) } == 1 {
+            log::info!("收到信号 {sig}，正在退出");
+            if proxy.send_event(AppEvent::Terminate).is_err() {
+                break;
+            }
+        }
+    });
+    for sig in [libc::SIGTERM, libc::SIGHUP] {
+        unsafe {
+            libc::signal(sig, on_signal as *const () as libc::sighandler_t);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Tabs and their panes. A window shows one tab at a time; the others keep
+//! running in the background. Each pane is one shell: its terminal, parser
+//! and PTY.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use winit::event_loop::EventLoopProxy;
+use winit::window::WindowId;
+
+use crate::layout::{Layout, PaneId};
+use crate::output_log::OutputLog;
+use crate::pty::{PtyEvent, PtyHandle};
+use crate::terminal::Terminal;
+use crate::vte_handler::VteHandler;
+use crate::AppEvent;
+
+/// Minimum gap between PTY size updates during a live resize
+const PTY_RESIZE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The grid's size in pixels, told to the PTY for image tools.
+fn pixel_size(term: &Terminal) -> (u16, u16) {
+    let (cw, ch) = term.cell_size;
+    let clamp = |px: usize| px.min(u16::MAX as usize) as u16;
+    (clamp(term.cols() * cw), clamp(term.rows() * ch))
+}
+
+pub struct Pane {
+    pub id: PaneId,
+    pub term: Terminal,
+    pub parser: vte::Parser,
+    pub pty: Arc<Mutex<PtyHandle>>,
+    /// Grid size the PTY was last told, when, and when a pending update is due
+    pub pty_size: (usize, usize),
+    pub pty_resize_at: Instant,
+    pub pty_resize_due: Option<Instant>,
+    /// `[logging] session_logs` file
+    pub log: Option<OutputLog>,
+    /// `--hold`: stay open showing the exit status when the program exits
+    pub hold: bool,
+    /// Font size in pixels; zooming changes only the focused pane's
+    pub px: f32,
+}
+
+impl Pane {
+    /// Start a shell for `term`, drawn at `px`, in `window`, or the `-e`
+    /// command if `spawn` has one. Without `spawn` (replay) the pane gets a
+    /// PTY that drops input.
+    pub fn open(
+        window: WindowId,
+        term: Terminal,
+        px: f32,
+        proxy: EventLoopProxy<AppEvent>,
+        spawn: Option<(&str, Option<&std::path::Path>, &[String])>,
+    ) -> Result<Self, String> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let size = (term.cols(), term.rows());
+        let pixels = pixel_size(&term);
+        let pty = match spawn {
+            Some((term_name, cwd, command)) => PtyHandle::spawn(size.0 as u16, size.1 as u16, pixels, term_name, cwd, command, move |ev| {
+                let _ = match ev {
+                    PtyEvent::Output(data) => proxy.send_event(AppEvent::PtyOutput(window, id, data)),
+                    PtyEvent::Exit(status) => proxy.send_event(AppEvent::PtyExit(window, id, status)),
+                };
+            })?,
+            None => PtyHandle::detached(),
+        };
+        Ok(Self {
+            id,
+            term,
+            parser: vte::Parser::new(),
+            pty,
+            pty_size: size,
+            pty_resize_at: Instant::now(),
+            pty_resize_due: None,
+            log: None,
+            hold: false,
+            px,
+        })
+    }
+
+    /// Run PTY output through the parser.
+    pub fn feed(&mut self, data: &[u8]) {
+        if let Some(log) = &mut self.log {
+            log.write(data);
+        }
+        let mut performer = VteHandler::new(&mut self.term);
+        for &b in data {
+            performer.advance(&mut self.parser, b);
+        }
+    }
+
+    /// Tell the PTY the grid size, at most every `PTY_RESIZE_INTERVAL`;
+    /// the last size of a live resize is sent once it settles.
+    pub fn resize_pty(&mut self) {
+        let size = (self.term.cols(), self.term.rows());
+        if size == self.pty_size {
+            self.pty_resize_due = None;
+            return;
+        }
+        let at = self.pty_resize_at + PTY_RESIZE_INTERVAL;
+        if Instant::now() < at {
+            self.pty_resize_due = Some(at);
+            return;
+        }
+        self.pty_resize_due = None;
+        self.pty_resize_at = Instant::now();
+        self.pty_size = size;
+        if let Ok(pty) = self.pty.lock() {
+            pty.resize(size.0 as u16, size.1 as u16, pixel_size(&self.term));
+        }
+    }
+}
+
+pub struct Tab {
+    pub layout: Layout,
+    /// The pane with keyboard focus
+    pub pane: Pane,
+    /// The other panes, in no particular order
+    pub panes: Vec<Pane>,
+    /// Output arrived while in the background; marked in the tab bar
+    pub activity: bool,
+}
+
+impl Tab {
+    pub fn new(pane: Pane) -> Self {
+        Self { layout: Layout::Pane(pane.id), pane, panes: Vec::new(), activity: false }
+    }
+
+    /// The focused pane first, then the others.
+    pub fn all(&self) -> impl Iterator<Item = &Pane> {
+        std::iter::once(&self.pane).chain(&self.panes)
+    }
+
+    pub fn all_mut(&mut self) -> impl Iterator<Item = &mut Pane> {
+        std::iter::once(&mut self.pane).chain(&mut self.panes)
+    }
+
+    pub fn find_mut(&mut self, id: PaneId) -> Option<&mut Pane> {
+        self.all_mut().find(|p| p.id == id)
+    }
+
+    /// Split the focused pane and focus the new one.
+    pub fn split(&mut self, pane: Pane, vertical: bool) {
+        self.layout.split(self.pane.id, pane.id, vertical);
+        let old = std::mem::replace(&mut self.pane, pane);
+        self.panes.push(old);
+    }
+
+    /// Move keyboard focus to another pane of this tab.
+    pub fn focus(&mut self, id: PaneId) -> bool {
+        let Some(i) = self.panes.iter().position(|p| p.id == id) else {
+            return false;
+        };
+        std::mem::swap(&mut self.pane, &mut self.panes[i]);
+        true
+    }
+
+    /// Close a pane (its shell gets SIGHUP). Returns true when it is the
+    /// last one and the tab should close instead.
+    pub fn close(&mut self, id: PaneId) -> bool {
+        if self.layout == Layout::Pane(id) {
+            return true;
+        }
+        let Some(next) = self.layout.remove(id) else {
+            return false;
+        };
+        if id == self.pane.id {
+            self.focus(next);
+        }
+        self.panes.retain(|p| p.id != id);
+        false
+    }
+
+    /// Label for the tab bar: the focused program's title, else its directory.
+    pub fn label(&self) -> String {
+        let term = &self.pane.term;
+        if !term.title.is_empty() {
+            return term.title.clone();
+        }
+        let cwd = term.cwd.as_deref().unwrap_or_default();
+        match cwd.rsplit('/').find(|s| !s.is_empty()) {
+            Some(dir) => dir.to_string(),
+            None => "moterm".into(),
+        }
+    }
+}
@@ -0,0 +1,2120 @@
+//! One terminal window: its PTY, grid, renderer and input state.
+
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use fontdue::Font;
+use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
+use winit::event::{
+    ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode,
+    WindowEvent,
+};
+use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
+use winit::window::{CursorIcon, Fullscreen, UserAttentionType, Window, WindowBuilder, WindowId};
+
+use crate::config::Config;
+use crate::font::FontFaces;
+use crate::i18n::{tr, Msg};
+use crate::notify::{self, Notification};
+use crate::renderer::{self, Cover, Renderer, Timestamps};
+use crate::output_log::OutputLog;
+use crate::replay::{Player, Step};
+use crate::session;
+use crate::layout::{Direction, PaneId, Rect};
+use crate::tab::{Pane, Tab};
+use crate::terminal::{Selection, Terminal};
+use crate::{
+    busy_command, clipboard, click_target_at, confirm_quit, crash, export_scrollback, hints, input, macos,
+    mouse, open_context, paste_bytes, paste_data, pixel_to_cell, search, visible_targets,
+    start_recording, web_search_selection, write_pty, AppEvent, Recorder,
+};
+
+const BLINK_INTERVAL: Duration = Duration::from_millis(530);
+/// Auto-scroll step while drag-selecting past the top or bottom edge.
+const DRAG_SCROLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Fastest auto-scroll, in lines per step.
+const DRAG_SCROLL_MAX_LINES: f64 = 20.0;
+/// Multi-click interval when neither the config nor the system gives one.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// How often relative timestamps in the gutter are refreshed.
+const GUTTER_REFRESH: Duration = Duration::from_secs(1);
+/// Minimum gap between title updates driven by output.
+const TITLE_INTERVAL: Duration = Duration::from_millis(250);
+/// Notifications per window kept for routing clicks
+const MAX_NOTIFICATIONS: usize = 32;
+/// Pastes larger than this are sent in chunks of this size from `tick`.
+const PASTE_CHUNK: usize = 64 * 1024;
+/// Gap between paste chunks, so output and input keep being handled.
+const PASTE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A large paste still being written to the PTY.
+struct PendingPaste {
+    data: Vec<u8>,
+    sent: usize,
+    bracketed: bool,
+    next_at: Instant,
+}
+
+/// Context menu entries.
+#[derive(Clone, Copy)]
+enum MenuItem {
+    OpenLink,
+    CopyLink,
+    Copy,
+    SearchWeb,
+    Paste,
+}
+
+impl MenuItem {
+    fn label(self) -> &'static str {
+        tr(match self {
+            MenuItem::OpenLink => Msg::MenuOpenLink,
+            MenuItem::CopyLink => Msg::MenuCopyLink,
+            MenuItem::Copy => Msg::MenuCopy,
+            MenuItem::SearchWeb => Msg::MenuSearchWeb,
+            MenuItem::Paste => Msg::MenuPaste,
+        })
+    }
+}
+
+/// The open context menu: its entries, and the cell (global row, col) it
+/// was opened on.
+struct ContextMenu {
+    items: Vec<MenuItem>,
+    row: usize,
+    col: usize,
+}
+
+/// Keyboard hint mode: each target's label and cell (global row, col),
+/// and the label keys typed so far.
+struct HintMode {
+    targets: Vec<(String, usize, usize)>,
+    typed: String,
+}
+
+/// State shared by all windows.
+pub struct Shared {
+    pub cfg: Config,
+    pub font: FontFaces,
+    pub fallback_fonts: Vec<Font>,
+    pub hint_patterns: Vec<hints::HintPattern>,
+    pub proxy: EventLoopProxy<AppEvent>,
+    /// `--working-directory`, for new windows whose parent's cwd is unknown
+    pub working_directory: Option<std::path::PathBuf>,
+    /// `--config`, re-read on reload
+    pub config_file: Option<std::path::PathBuf>,
+    /// Modification time of the config file when it was last read
+    pub config_modified: Option<std::time::SystemTime>,
+    /// Second copy register (Cmd+Option+C / Cmd+Option+V), apart from the
+    /// system clipboard
+    pub register: std::cell::RefCell<String>,
+}
+
+/// What a window asks the app to do after handling an event.
+pub enum Action {
+    None,
+    NewWindow,
+    ReloadConfig,
+    Close,
+    Quit,
+}
+
+pub struct TermWindow {
+    // Dropped in order: the surface must go before the window
+    surface: softbuffer::Surface,
+    _context: softbuffer::Context,
+    pub window: Window,
+    renderer: Renderer,
+    scale_factor: f64,
+    /// The tab on screen
+    tab: Tab,
+    /// The other tabs, in tab bar order with the active one taken out at
+    /// `active`
+    tabs: Vec<Tab>,
+    active: usize,
+    search: search::SearchState,
+    dirty: bool,
+    cursor_visible: bool,
+    cursor_blink_timer: Instant,
+    cursor_blink: bool,
+    mouse_pos: PhysicalPosition<f64>,
+    selecting: bool,
+    drag_scroll_at: Instant,
+    /// Button held while mouse reporting is on, for drag events
+    mouse_button: Option<u8>,
+    /// Cell of the last reported motion, so each cell is reported once
+    mouse_cell: Option<(usize, usize)>,
+    /// Trackpad scroll not yet amounting to a whole cell (cols, lines)
+    scroll_rest: (f64, f64),
+    last_click_time: Instant,
+    click_count: u8,
+    focused: bool,
+    /// Output or a bell arrived while in the background; shown as a dot in
+    /// the title until the window is focused
+    activity: bool,
+    /// Title and focus last set on the window (and tab)
+    shown_title: (String, bool),
+    /// OSC 22 pointer shape last set on the window
+    shown_pointer: String,
+    /// When the title was last updated, and when a pending update is due
+    title_at: Instant,
+    title_due: Option<Instant>,
+    /// Last time the window was drawn, for refreshing relative timestamps
+    last_redraw: Instant,
+    /// Bells received while in the background, for the Dock badge
+    pub pending_bells: usize,
+    /// Last key, click or pointer move, and the `[privacy]` idle cover
+    last_input: Instant,
+    idle_cover: Option<(Duration, Cover)>,
+    /// Last bell that beeped or bounced, for `bell.min_interval_ms`
+    bell_at: Option<Instant>,
+    /// Notifications posted for this window: identifier and pane, newest last
+    notifications: Vec<(String, PaneId, Notification)>,
+    /// Session recording and the pane it follows
+    pub recorder: Option<(PaneId, Recorder)>,
+    pub player: Option<Player>,
+    paste: Option<PendingPaste>,
+    menu: Option<ContextMenu>,
+    hint_mode: Option<HintMode>,
+}
+
+impl TermWindow {
+    /// Open a window and start its shell. With `player`, the window replays
+    /// a recording instead and is sized to the recorded grid.
+    pub fn new(
+        target: &EventLoopWindowTarget<AppEvent>,
+        shared: &Shared,
+        player: Option<Player>,
+        record: Option<&std::path::Path>,
+        cwd: Option<&std::path::Path>,
+        command: &[String],
+        hold: bool,
+    ) -> Result<Self, String> {
+        let cfg = &shared.cfg;
+        let window = WindowBuilder::new()
+            .with_title("moterm")
+            .with_inner_size(LogicalSize::new(
+                cfg.window.width as f64,
+                cfg.window.height as f64,
+            ))
+            .with_resizable(true)
+            .build(target)
+            .map_err(|e| tr(Msg::WindowFailed).replace("{error}", &e.to_string()))?;
+        macos::set_tabbing(&window, &cfg.window.tabbing);
+
+        let scale_factor = window.scale_factor();
+        let font_size = (cfg.font.size * scale_factor as f32).max(8.0);
+        let mut renderer = Renderer::new(
+            shared.font.clone(),
+            shared.fallback_fonts.clone(),
+            font_size,
+        );
+        renderer.timestamps = Timestamps::from_config(&cfg.scrolling.timestamps);
+        renderer.bold_is_bright = cfg.colors.draw_bold_text_with_bright_colors;
+        renderer.scroll_indicator = cfg.scrolling.indicator;
+        renderer.link_underline = cfg.links.underline;
+        renderer.status_line = cfg.window.status_line;
+        renderer.error_patterns = renderer::compile_error_patterns(&cfg.scrolling.error_patterns);
+
+        // Size the window to the recorded grid
+        let mut size = window.inner_size();
+        if let Some(p) = &player {
+            let (w, h) = renderer.surface_size_for_grid(p.size().0, p.size().1);
+            size = PhysicalSize::new(w as u32, h as u32);
+            window.set_inner_size(size);
+        }
+
+        renderer.pane_rect = renderer.content_rect(size.width as usize, size.height as usize);
+        let (cols, rows) = renderer.grid_size_for_pixels(size.width as usize, size.height as usize);
+        let mut term = new_terminal(cols, rows, cfg);
+        term.cell_size = renderer.cell_size();
+
+        let spawn = player.is_none().then_some((cfg.env.term.as_str(), cwd, command));
+        let mut pane = Pane::open(window.id(), term, font_size, shared.proxy.clone(), spawn)?;
+        pane.hold = hold;
+        if player.is_none() {
+            pane.log = OutputLog::open(&cfg.logging, pane.id);
+        }
+        let pane_id = pane.id;
+        let tab = Tab::new(pane);
+
+        let recorder = match record {
+            Some(path) => Some((pane_id, start_recording(path, cols, rows)?)),
+            None => None,
+        };
+        renderer.recording = recorder.is_some();
+
+        let context = unsafe { softbuffer::Context::new(&window) }
+            .map_err(|e| tr(Msg::SurfaceFailed).replace("{error}", &e.to_string()))?;
+        let surface = unsafe { softbuffer::Surface::new(&context, &window) }
+            .map_err(|e| tr(Msg::SurfaceFailed).replace("{error}", &e.to_string()))?;
+
+        let win = Self {
+            surface,
+            _context: context,
+            window,
+            renderer,
+            scale_factor,
+            tab,
+            tabs: Vec::new(),
+            active: 0,
+            search: search::SearchState::new(),
+            dirty: true,
+            cursor_visible: true,
+            cursor_blink_timer: Instant::now(),
+            cursor_blink: cfg.cursor.blink,
+            last_redraw: Instant::now(),
+            mouse_pos: PhysicalPosition::new(0.0, 0.0),
+            selecting: false,
+            drag_scroll_at: Instant::now(),
+            mouse_button: None,
+            mouse_cell: None,
+            scroll_rest: (0.0, 0.0),
+            last_click_time: Instant::now(),
+            click_count: 0,
+            focused: true,
+            activity: false,
+            shown_title: (String::new(), false),
+            shown_pointer: String::new(),
+            title_at: Instant::now(),
+            title_due: None,
+            pending_bells: 0,
+            last_input: Instant::now(),
+            idle_cover: idle_cover(cfg),
+            bell_at: None,
+            notifications: Vec::new(),
+            recorder,
+            player,
+            paste: None,
+            menu: None,
+            hint_mode: None,
+        };
+        win.snap_to_grid(cfg);
+        Ok(win)
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// The shell's working directory, if it reports one (OSC 7).
+    pub fn cwd(&self) -> Option<&str> {
+        self.tab.pane.term.cwd.as_deref()
+    }
+
+    /// With `[window] resize_increments`, live resizing steps by whole cells
+    /// and the window is trimmed to the grid.
+    fn snap_to_grid(&self, cfg: &Config) {
+        if !cfg.window.resize_increments {
+            return;
+        }
+        let atlas = &self.renderer.atlas;
+        self.window.set_resize_increments(Some(PhysicalSize::new(
+            atlas.cell_width as u32,
+            atlas.cell_height as u32,
+        )));
+        // Split panes don't line up with a single grid
+        if !self.tab.panes.is_empty() {
+            return;
+        }
+        let (w, h) = self.renderer.surface_size_for_grid(self.tab.pane.term.cols(), self.tab.pane.term.rows());
+        self.window.set_inner_size(PhysicalSize::new(w as u32, h as u32));
+    }
+
+    /// Show the cursor solid and restart the blink phase.
+    fn reset_blink(&mut self) {
+        self.cursor_visible = true;
+        self.cursor_blink_timer = Instant::now();
+    }
+
+    /// Called before a key press is sent to the PTY.
+    fn before_input(&mut self, cfg: &Config) {
+        self.reset_blink();
+        self.renderer.command_block = None;
+        if cfg.scrolling.scroll_to_bottom_on_keypress && self.tab.pane.term.view_scroll > 0 {
+            self.tab.pane.term.scroll_view_to_bottom();
+            self.redraw_later();
+        }
+    }
+
+    fn redraw_later(&mut self) {
+        self.dirty = true;
+        self.window.request_redraw();
+    }
+
+    /// Report a mouse event to the application, adding modifier bits.
+    fn send_mouse(&self, button: u8, view_row: usize, col: usize, pressed: bool, modifiers: ModifiersState) {
+        let btn = button | mouse::modifier_bits(modifiers.shift(), modifiers.alt(), modifiers.ctrl());
+        write_pty(&self.tab.pane.pty, &mouse::encode(btn, col, view_row, pressed, self.tab.pane.term.mouse_sgr));
+    }
+
+    /// Feed PTY (or replayed) output through the parser.
+    pub fn handle_output(&mut self, data: &[u8], cfg: &Config) {
+        self.record_output(self.tab.pane.id, data);
+        self.reset_blink();
+        self.tab.pane.term.clock = crate::unix_now();
+        self.tab.pane.feed(data);
+        if cfg.scrolling.auto_scroll_on_output {
+            self.tab.pane.term.scroll_view_to_bottom();
+        }
+        if self.tab.pane.term.scrollback_cleared {
+            self.after_scrollback_cleared();
+        }
+        if let Some(marks) = &mut self.renderer.scroll_marks {
+            marks.touch();
+        }
+        for n in std::mem::take(&mut self.tab.pane.term.notifications) {
+            self.post_notification(self.tab.pane.id, n);
+        }
+        if self.tab.pane.term.pointer_shape != self.shown_pointer {
+            self.shown_pointer = self.tab.pane.term.pointer_shape.clone();
+            self.window.set_cursor_icon(pointer_icon(&self.shown_pointer));
+        }
+        record_crash_state(&self.tab.pane);
+        let term = &mut self.tab.pane.term;
+        // Send any DSR replies back to PTY
+        if !term.reply_buf.is_empty() {
+            let reply = std::mem::take(&mut term.reply_buf);
+            write_pty(&self.tab.pane.pty, &reply);
+        }
+        if std::mem::take(&mut term.title_changed) {
+            self.update_tab_bar();
+        }
+        if std::mem::take(&mut self.tab.pane.term.bell) {
+            self.ring_bell(cfg);
+        }
+        if !self.focused {
+            self.activity = true;
+        }
+        // Apps that animate their title would otherwise retitle per chunk
+        if self.title_due.is_none() {
+            self.title_due = Some((self.title_at + TITLE_INTERVAL).max(Instant::now()));
+        }
+        self.redraw_later();
+    }
+
+    /// Output from one of this window's PTYs.
+    pub fn handle_pane_output(&mut self, id: PaneId, data: &[u8], cfg: &Config) {
+        if id == self.tab.pane.id {
+            self.handle_output(data, cfg);
+            return;
+        }
+        self.record_output(id, data);
+        // Another pane, maybe in a background tab: keep its grid current and
+        // answer its queries
+        let in_view = self.tab.panes.iter().any(|p| p.id == id);
+        let tabs = std::iter::once(&mut self.tab).chain(&mut self.tabs);
+        let Some(tab) = tabs.into_iter().find(|t| t.all().any(|p| p.id == id)) else {
+            return;
+        };
+        tab.activity |= !in_view;
+        let Some(p) = tab.find_mut(id) else {
+            return;
+        };
+        p.term.clock = crate::unix_now();
+        p.feed(data);
+        record_crash_state(p);
+        if !p.term.reply_buf.is_empty() {
+            write_pty(&p.pty, &std::mem::take(&mut p.term.reply_buf));
+        }
+        p.term.title_changed = false;
+        let bell = std::mem::take(&mut p.term.bell);
+        let notifications = std::mem::take(&mut p.term.notifications);
+        self.update_tab_bar();
+        for n in notifications {
+            self.post_notification(id, n);
+        }
+        if bell {
+            self.ring_bell(cfg);
+        }
+        self.redraw_later();
+    }
+
+    /// Keep pane `id`'s raw output for a crash report, and for the recording
+    /// if that's the pane being recorded.
+    fn record_output(&mut self, id: PaneId, data: &[u8]) {
+        crash::record_input(data);
+        let Some((_, rec)) = self.recorder.as_mut().filter(|(pane, _)| *pane == id) else {
+            return;
+        };
+        if let Err(e) = rec.output(data) {
+            log::error!("录制写入失败: {e}");
+            self.recorder = None;
+            self.renderer.recording = false;
+        }
+    }
+
+    /// Beep, bounce and badge for a bell, at most once per `bell.min_interval_ms`.
+    fn ring_bell(&mut self, cfg: &Config) {
+        // Coalesce bell storms (e.g. `cat` of a binary file) into one beep
+        let now = Instant::now();
+        let interval = Duration::from_millis(cfg.bell.min_interval_ms);
+        let audible = self.bell_at.is_none_or(|at| now.duration_since(at) >= interval);
+        if audible {
+            self.bell_at = Some(now);
+        }
+        self.renderer.status_bell = true;
+        // Visual bell: briefly invert isn't easy without timer,
+        // so we use macOS system beep
+        #[cfg(target_os = "macos")]
+        if audible && cfg.bell.sound {
+            unsafe { libc::write(libc::STDOUT_FILENO, b"\x07".as_ptr() as _, 1); }
+        }
+        // The desktop's bell sound via libcanberra, if installed
+        #[cfg(not(target_os = "macos"))]
+        if audible && cfg.bell.sound {
+            std::thread::spawn(|| {
+                let _ = std::process::Command::new("canberra-gtk-play").args(["--id=bell"]).status();
+            });
+        }
+        if !self.focused {
+            if audible && cfg.bell.bounce {
+                self.window
+                    .request_user_attention(Some(UserAttentionType::Informational));
+            }
+            if cfg.bell.badge {
+                self.pending_bells += 1;
+            }
+        }
+    }
+
+    /// Start a shell in the focused pane's directory, sized like that pane
+    /// but at the configured font size.
+    fn open_pane(&self, shared: &Shared) -> Option<Pane> {
+        let cwd = self.cwd().map(std::path::PathBuf::from).or_else(|| shared.working_directory.clone());
+        self.open_pane_in(shared, cwd.as_deref())
+    }
+
+    fn open_pane_in(&self, shared: &Shared, cwd: Option<&std::path::Path>) -> Option<Pane> {
+        let cfg = &shared.cfg;
+        let mut term = new_terminal(self.tab.pane.term.cols(), self.tab.pane.term.rows(), cfg);
+        let px = self.renderer.default_px;
+        term.cell_size = self.renderer.atlas.cell_size_at(px);
+        let spawn = Some((cfg.env.term.as_str(), cwd, &[][..]));
+        let mut pane = Pane::open(self.window.id(), term, px, shared.proxy.clone(), spawn)
+            .map_err(|e| log::error!("{e}"))
+            .ok()?;
+        pane.log = OutputLog::open(&cfg.logging, pane.id);
+        Some(pane)
+    }
+
+    /// Open a tab after the current one, starting in its directory.
+    fn open_tab(&mut self, shared: &Shared) {
+        if let Some(pane) = self.open_pane(shared) {
+            self.cancel_paste();
+            let old = std::mem::replace(&mut self.tab, Tab::new(pane));
+            self.tabs.insert(self.active, old);
+            self.active += 1;
+            self.after_focus_change(&shared.cfg);
+        }
+    }
+
+    /// Tabs in bar order, for the session file.
+    pub fn session_state(&self) -> session::WindowState {
+        let (before, after) = self.tabs.split_at(self.active);
+        let tabs = before.iter().chain([&self.tab]).chain(after).map(|t| session::TabState {
+            cwd: t.pane.term.cwd.clone(),
+            title: t.pane.term.title.clone(),
+        });
+        session::WindowState { tabs: tabs.collect(), active: self.active }
+    }
+
+    /// Reopen a saved window's tabs. The first is this window's own, already
+    /// started in its directory.
+    pub fn restore_tabs(&mut self, shared: &Shared, state: &session::WindowState) {
+        let mut saved = state.tabs.iter();
+        if let Some(first) = saved.next() {
+            self.tab.pane.term.title = first.title.clone();
+        }
+        for t in saved {
+            if let Some(mut pane) = self.open_pane_in(shared, t.cwd.as_deref().map(std::path::Path::new)) {
+                pane.term.title = t.title.clone();
+                self.tabs.push(Tab::new(pane));
+            }
+        }
+        self.after_focus_change(&shared.cfg);
+        self.switch_tab(state.active, &shared.cfg);
+    }
+
+    /// Split the focused pane side by side (`vertical`) or stacked; the new
+    /// pane gets focus.
+    fn split_pane(&mut self, shared: &Shared, vertical: bool) {
+        if let Some(pane) = self.open_pane(shared) {
+            self.cancel_paste();
+            self.tab.split(pane, vertical);
+            self.after_focus_change(&shared.cfg);
+        }
+    }
+
+    /// Give keyboard focus to another pane of the current tab.
+    fn focus_pane(&mut self, id: PaneId, cfg: &Config) {
+        if id == self.tab.pane.id {
+            return;
+        }
+        self.cancel_paste();
+        if self.tab.focus(id) {
+            self.after_focus_change(cfg);
+        }
+    }
+
+    /// A pane's program exited: a `--hold` pane shows `status`, others close.
+    pub fn pane_exited(&mut self, id: PaneId, status: Option<i32>, cfg: &Config) -> bool {
+        let tabs = std::iter::once(&mut self.tab).chain(&mut self.tabs);
+        let Some(pane) = tabs.into_iter().find_map(|t| t.find_mut(id)).filter(|p| p.hold) else {
+            return self.close_pane(id, cfg);
+        };
+        let status = status.map_or_else(|| "?".to_string(), |s| s.to_string());
+        let msg = tr(Msg::ProcessExited).replace("{status}", &status);
+        pane.feed(format!("\r\n\x1b[0m{msg}").as_bytes());
+        self.redraw_later();
+        false
+    }
+
+    /// Close a pane (its shell gets SIGHUP), and its tab with the last
+    /// pane. Returns true when that was the window's last tab.
+    pub fn close_pane(&mut self, id: PaneId, cfg: &Config) -> bool {
+        if let Some(i) = self.tabs.iter().position(|t| t.all().any(|p| p.id == id)) {
+            if self.tabs[i].close(id) {
+                self.tabs.remove(i);
+                if i < self.active {
+                    self.active -= 1;
+                }
+                self.update_tab_bar();
+                self.fit_grid();
+                self.redraw_later();
+            }
+            return false;
+        }
+        if !self.tab.all().any(|p| p.id == id) {
+            return false;
+        }
+        let focused = id == self.tab.pane.id;
+        if focused {
+            self.cancel_paste();
+        }
+        if !self.tab.close(id) {
+            if focused {
+                self.after_focus_change(cfg);
+            } else {
+                self.fit_grid();
+                self.redraw_later();
+            }
+            return false;
+        }
+        if self.tabs.is_empty() {
+            return true;
+        }
+        // Show the tab to the right, or the new last one
+        let i = self.active.min(self.tabs.len() - 1);
+        self.tab = self.tabs.remove(i);
+        self.active = i;
+        self.after_focus_change(cfg);
+        false
+    }
+
+    /// Show the tab at `index` in tab bar order.
+    fn switch_tab(&mut self, index: usize, cfg: &Config) {
+        if index == self.active || index > self.tabs.len() {
+            return;
+        }
+        self.cancel_paste();
+        let mut next = self.tabs.remove(if index < self.active { index } else { index - 1 });
+        std::mem::swap(&mut self.tab, &mut next);
+        self.tabs.insert(if index < self.active { self.active - 1 } else { self.active }, next);
+        self.active = index;
+        self.after_focus_change(cfg);
+    }
+
+    fn tab_count(&self) -> usize {
+        self.tabs.len() + 1
+    }
+
+    /// Per-view state belongs to the previously focused pane; fit the
+    /// current tab's panes to the window.
+    fn after_focus_change(&mut self, cfg: &Config) {
+        self.tab.activity = false;
+        self.search.active = false;
+        self.selecting = false;
+        self.mouse_button = None;
+        self.mouse_cell = None;
+        self.renderer.command_block = None;
+        self.renderer.scroll_marks = None;
+        self.renderer.set_font_size(self.tab.pane.px);
+        self.update_tab_bar();
+        self.fit_grid();
+        self.shown_pointer = self.tab.pane.term.pointer_shape.clone();
+        self.window.set_cursor_icon(pointer_icon(&self.shown_pointer));
+        self.reset_blink();
+        self.update_title(cfg);
+        self.redraw_later();
+    }
+
+    /// Tab bar labels; hidden with a single tab.
+    fn update_tab_bar(&mut self) {
+        self.renderer.tab_bar.clear();
+        if self.tabs.is_empty() {
+            return;
+        }
+        let (before, after) = self.tabs.split_at(self.active);
+        let tabs = before.iter().chain([&self.tab]).chain(after);
+        self.renderer.tab_bar.extend(tabs.map(|t| (t.label(), t.activity)));
+        self.renderer.active_tab = self.active;
+    }
+
+    /// Programs running in this window's panes, for the close confirmation.
+    pub fn busy_commands(&self) -> Vec<String> {
+        std::iter::once(&self.tab).chain(&self.tabs).flat_map(Tab::all).filter_map(busy_in).collect()
+    }
+
+    /// Ask before closing panes whose shell is running something.
+    pub fn confirm_close(&self) -> bool {
+        confirm_quit(&self.busy_commands())
+    }
+
+    /// Show an OSC 9/99 notification, remembering it so a click can be
+    /// routed back here.
+    fn post_notification(&mut self, pane: PaneId, n: Notification) {
+        if n.only_unfocused && self.focused {
+            return;
+        }
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let identifier = format!("moterm-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let title = if n.title.is_empty() { self.window.title() } else { n.title.clone() };
+        crate::macos::post_notification(&identifier, &title, &n.body, &n.buttons);
+        if self.notifications.len() >= MAX_NOTIFICATIONS {
+            self.notifications.remove(0);
+        }
+        self.notifications.push((identifier, pane, n));
+    }
+
+    /// Whether `identifier` is one of this window's notifications.
+    pub fn owns_notification(&self, identifier: &str) -> bool {
+        self.notifications.iter().any(|(id, _, _)| id == identifier)
+    }
+
+    /// The user clicked a notification (`button` is 1-based): report it to
+    /// the program if asked, and bring its window and tab forward.
+    pub fn notification_activated(&mut self, identifier: &str, button: Option<usize>, cfg: &Config) {
+        let Some(i) = self.notifications.iter().position(|(id, _, _)| id == identifier) else {
+            return;
+        };
+        let (_, pane, n) = self.notifications.remove(i);
+        if n.report {
+            let report = notify::activation_report(&n.id, button);
+            match std::iter::once(&self.tab).chain(&self.tabs).flat_map(Tab::all).find(|p| p.id == pane) {
+                Some(p) => write_pty(&p.pty, &report),
+                None => return,
+            }
+        }
+        if n.focus {
+            if let Some(i) = self.tabs.iter().position(|t| t.all().any(|p| p.id == pane)) {
+                self.switch_tab(if i < self.active { i } else { i + 1 }, cfg);
+            }
+            self.focus_pane(pane, cfg);
+            self.window.focus_window();
+        }
+    }
+
+    /// Window (and tab) title: the terminal's title, else `title_format`,
+    /// with a dot for background activity; bold in the tab bar while focused.
+    fn update_title(&mut self, cfg: &Config) {
+        self.title_at = Instant::now();
+        self.title_due = None;
+        let mut base = self.tab.pane.term.title.clone();
+        if base.is_empty() && !cfg.window.title_format.is_empty() {
+            let command = self.tab.pane.pty.lock().ok().and_then(|p| p.foreground_command());
+            base = format_title(&cfg.window.title_format, command.as_deref(), self.tab.pane.term.cwd.as_deref());
+        }
+        if base.is_empty() {
+            base = "moterm".into();
+        }
+        let title = if self.activity { format!("● {base}") } else { base };
+        let shown = (title, self.focused);
+        if shown != self.shown_title {
+            self.window.set_title(&shown.0);
+            macos::set_tab_title(&self.window, &shown.0, self.focused);
+            self.shown_title = shown;
+        }
+    }
+
+    /// Send the clipboard to the PTY. Large pastes are queued and written in
+    /// chunks from `tick`, with progress shown and Esc to cancel.
+    fn paste(&mut self) {
+        if self.paste.is_some() {
+            return;
+        }
+        if let Some(data) = paste_bytes(self.tab.pane.term.bracketed_paste) {
+            self.send_paste(data);
+        }
+    }
+
+    /// Paste the copy register instead of the clipboard.
+    fn paste_register(&mut self, text: &str) {
+        if self.paste.is_some() || text.is_empty() {
+            return;
+        }
+        self.send_paste(paste_data(text, self.tab.pane.term.bracketed_paste));
+    }
+
+    /// Write pasted bytes, in chunks if they are large.
+    fn send_paste(&mut self, data: Vec<u8>) {
+        let bracketed = self.tab.pane.term.bracketed_paste;
+        if data.len() <= PASTE_CHUNK {
+            write_pty(&self.tab.pane.pty, &data);
+            return;
+        }
+        self.paste = Some(PendingPaste {
+            data,
+            sent: 0,
+            bracketed,
+            next_at: Instant::now(),
+        });
+        self.send_paste_chunk();
+    }
+
+    fn send_paste_chunk(&mut self) {
+        let Some(p) = &mut self.paste else {
+            return;
+        };
+        let end = (p.sent + PASTE_CHUNK).min(p.data.len());
+        match self.tab.pane.pty.lock().map_err(|e| e.to_string()).and_then(|pty| pty.try_write(&p.data[p.sent..end])) {
+            Ok(n) => p.sent += n,
+            Err(e) => {
+                log::error!("粘贴中断 ({} / {} bytes): {e}", p.sent, p.data.len());
+                p.sent = p.data.len();
+            }
+        }
+        p.next_at = Instant::now() + PASTE_INTERVAL;
+        self.renderer.paste_progress = Some(p.sent as f32 / p.data.len() as f32);
+        if p.sent >= p.data.len() {
+            self.paste = None;
+            self.renderer.paste_progress = None;
+        }
+        self.redraw_later();
+    }
+
+    /// Drop the rest of a chunked paste, closing the bracket if one was opened.
+    fn cancel_paste(&mut self) {
+        if let Some(p) = self.paste.take() {
+            log::info!("粘贴已取消 ({} / {} bytes)", p.sent, p.data.len());
+            if p.bracketed && p.sent > 0 {
+                write_pty(&self.tab.pane.pty, b"\x1b[201~");
+            }
+        }
+        self.renderer.paste_progress = None;
+        self.redraw_later();
+    }
+
+    /// With Cmd held, show where the link or hint under the pointer goes.
+    pub fn update_tooltip(&mut self, modifiers: ModifiersState, shared: &Shared) {
+        let tooltip = if modifiers.logo() && self.focused {
+            pixel_to_cell(&self.renderer, &self.window, self.mouse_pos).and_then(|(view_row, col)| {
+                click_target_at(&self.tab.pane.term, view_row, col, &shared.hint_patterns)
+                    .map(|t| (view_row, col, t.destination()))
+            })
+        } else {
+            None
+        };
+        if tooltip != self.renderer.tooltip {
+            self.renderer.tooltip = tooltip;
+            self.redraw_later();
+        }
+    }
+
+    /// Drop state pointing at rows that were cleared from history.
+    fn after_scrollback_cleared(&mut self) {
+        self.tab.pane.term.scrollback_cleared = false;
+        self.renderer.command_block = None;
+        self.renderer.scroll_marks = None;
+        if self.search.active {
+            self.search.search(&self.tab.pane.term);
+        }
+    }
+
+    /// The focused pane's font size changed: keep it and refit its grid.
+    fn after_zoom(&mut self, cfg: &Config) {
+        self.tab.pane.px = self.renderer.atlas.px;
+        self.fit_grid();
+        self.snap_to_grid(cfg);
+        self.redraw_later();
+    }
+
+    /// Apply a reloaded config. The shell keeps running; the grid is refit
+    /// to the new font size.
+    pub fn apply_config(&mut self, shared: &Shared) {
+        let cfg = &shared.cfg;
+        let px = (cfg.font.size * self.scale_factor as f32).max(8.0);
+        self.renderer.set_font(shared.font.clone(), shared.fallback_fonts.clone(), px);
+        self.renderer.timestamps = Timestamps::from_config(&cfg.scrolling.timestamps);
+        self.renderer.bold_is_bright = cfg.colors.draw_bold_text_with_bright_colors;
+        self.renderer.scroll_indicator = cfg.scrolling.indicator;
+        self.renderer.link_underline = cfg.links.underline;
+        self.renderer.status_line = cfg.window.status_line;
+        self.idle_cover = idle_cover(cfg);
+        self.renderer.error_patterns = renderer::compile_error_patterns(&cfg.scrolling.error_patterns);
+        self.renderer.scroll_marks = None;
+        for tab in std::iter::once(&mut self.tab).chain(&mut self.tabs) {
+            for pane in tab.all_mut() {
+                configure_terminal(&mut pane.term, cfg);
+                pane.px = px;
+            }
+        }
+        macos::set_tabbing(&self.window, &cfg.window.tabbing);
+        self.after_zoom(cfg);
+        self.update_title(cfg);
+    }
+
+    /// Resize the panes' grids to the current window size; the PTYs follow.
+    fn fit_grid(&mut self) {
+        let size = self.window.inner_size();
+        self.fit_panes(size.width as usize, size.height as usize);
+    }
+
+    /// Give each pane of the current tab its share of a `width`×`height`
+    /// window, in cells of its own font size.
+    fn fit_panes(&mut self, width: usize, height: usize) {
+        let area = self.renderer.content_rect(width, height);
+        let (rects, dividers) = self.tab.layout.rects(area);
+        let focused_px = self.tab.pane.px;
+        for (id, rect) in rects {
+            if id == self.tab.pane.id {
+                self.renderer.pane_rect = rect;
+            }
+            if let Some(pane) = self.tab.find_mut(id) {
+                self.renderer.set_font_size(pane.px);
+                let (cols, rows) = self.renderer.grid_size_for_pane(rect.w, rect.h);
+                let resized = (cols, rows) != (pane.term.cols(), pane.term.rows());
+                pane.term.cell_size = self.renderer.cell_size();
+                pane.term.resize(cols, rows);
+                pane.resize_pty();
+                if let Some((_, rec)) = self.recorder.as_mut().filter(|(pane, _)| resized && *pane == id) {
+                    let _ = rec.resize(cols, rows);
+                }
+            }
+        }
+        self.renderer.set_font_size(focused_px);
+        self.renderer.dividers = dividers;
+        self.renderer.scroll_marks = None;
+    }
+
+    /// Each pane of the current tab with its area in the window.
+    fn pane_rects(&self) -> Vec<(PaneId, Rect)> {
+        let size = self.window.inner_size();
+        let area = self.renderer.content_rect(size.width as usize, size.height as usize);
+        self.tab.layout.rects(area).0
+    }
+
+    /// Scroll the previous/next prompt mark to the top of the view and
+    /// highlight its command block. Past the last prompt, return to the bottom.
+    fn jump_to_prompt(&mut self, up: bool) {
+        let term = &self.tab.pane.term;
+        let from = self.renderer.command_block.unwrap_or(if term.view_scroll == 0 {
+            term.grid.scrollback_len() + term.cursor_row
+        } else {
+            term.visible_start_global_row()
+        });
+        let target = if up {
+            term.prev_prompt(from)
+        } else {
+            term.next_prompt(from)
+        };
+        match target {
+            Some(row) => self.tab.pane.term.scroll_view_to_row(row),
+            None if up => return,
+            None => self.tab.pane.term.scroll_view_to_bottom(),
+        }
+        self.renderer.command_block = target;
+        self.redraw_later();
+    }
+
+    /// Bring the current match into view and flash it.
+    fn scroll_to_current_match(&mut self) {
+        self.renderer.match_flash = Some(Instant::now());
+        let term = &mut self.tab.pane.term;
+        if let Some(m) = self.search.current_match() {
+            let vis_start = term.visible_start_global_row();
+            let vis_end = vis_start + term.rows();
+            if m.start.row < vis_start || m.start.row >= vis_end {
+                let total = term.total_lines();
+                let scroll = total.saturating_sub(m.start.row + term.rows());
+                term.view_scroll = scroll;
+            }
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        event: WindowEvent,
+        modifiers: ModifiersState,
+        shared: &Shared,
+    ) -> Action {
+        let cfg = &shared.cfg;
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::ReceivedCharacter(_)
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+        ) {
+            self.note_input();
+        }
+        match event {
+            WindowEvent::Focused(f) => {
+                self.focused = f;
+                // Blinking pauses in the background
+                self.reset_blink();
+                self.redraw_later();
+                if f {
+                    self.pending_bells = 0;
+                    self.activity = false;
+                } else {
+                    self.close_menu();
+                }
+                self.update_title(cfg);
+            }
+            WindowEvent::CloseRequested if self.confirm_close() => {
+                return Action::Close;
+            }
+            WindowEvent::Resized(new_size) => {
+                self.fit_grid();
+                // Immediately resize surface and fill with bg to prevent white flash
+                let (w_nz, h_nz) = renderer::Renderer::nonzero_dims(new_size.width, new_size.height);
+                if self.surface.resize(w_nz, h_nz).is_ok() {
+                    if let Ok(mut buffer) = self.surface.buffer_mut() {
+                        buffer.fill(self.tab.pane.term.palette.bg.to_u32());
+                        let _ = buffer.present();
+                    }
+                }
+                self.redraw_later();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
+                self.scale_factor = scale_factor;
+                self.fit_panes(new_inner_size.width as usize, new_inner_size.height as usize);
+                self.redraw_later();
+            }
+            WindowEvent::ReceivedCharacter(_) if self.menu.is_some() => {}
+            WindowEvent::ReceivedCharacter(ch) if self.hint_mode.is_some() => {
+                self.type_hint_key(ch, modifiers, shared);
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                if self.search.active {
+                    if !ch.is_control() && !modifiers.logo() && !modifiers.ctrl() {
+                        self.search.push_char(ch);
+                        self.search.search(&self.tab.pane.term);
+                        self.redraw_later();
+                    }
+                    return Action::None;
+                }
+                if let Some(bytes) = input::map_received_char(ch, modifiers) {
+                    self.before_input(cfg);
+                    write_pty(&self.tab.pane.pty, &bytes);
+                }
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                return self.handle_key(input, modifiers, shared);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_pos = position;
+                if let Some(menu) = &self.renderer.menu {
+                    let hover = self.renderer.menu_item_at(position.x.max(0.0) as usize, position.y.max(0.0) as usize);
+                    if hover != menu.selected {
+                        self.renderer.menu.as_mut().unwrap().selected = hover;
+                        self.redraw_later();
+                    }
+                    return Action::None;
+                }
+                self.update_tooltip(modifiers, shared);
+                let cell = pixel_to_cell(&self.renderer, &self.window, position);
+                // Report motion with a button held (1002) or any motion (1003)
+                if self.tab.pane.term.mouse_mode > 0 && !self.selecting {
+                    let report = match self.mouse_button {
+                        Some(_) => self.tab.pane.term.mouse_mode >= 1002,
+                        None => self.tab.pane.term.mouse_mode == 1003,
+                    };
+                    if let Some((view_row, col)) = cell.filter(|&c| report && self.mouse_cell != Some(c)) {
+                        self.mouse_cell = Some((view_row, col));
+                        let btn = self.mouse_button.unwrap_or(mouse::BUTTON_RELEASE) + mouse::MOTION;
+                        self.send_mouse(btn, view_row, col, true, modifiers);
+                    }
+                } else if self.selecting {
+                    // Past the edges the selection sticks to the nearest cell
+                    let (view_row, col) = self.clamped_cell();
+                    self.tab.pane.term.set_selection_focus_from_view(view_row, col);
+                    self.redraw_later();
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.handle_mouse_button(state, button, modifiers, shared);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (cols, lines) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x.round() as isize, y.round() as isize),
+                    // Trackpads send a few pixels at a time: carry the remainder
+                    // so slow scrolling still moves (and is reported to the app)
+                    MouseScrollDelta::PixelDelta(p) => {
+                        let x = self.scroll_rest.0 + p.x / self.renderer.atlas.cell_width as f64;
+                        let y = self.scroll_rest.1 + p.y / self.renderer.atlas.cell_height as f64;
+                        self.scroll_rest = (x.fract(), y.fract());
+                        (x.trunc() as isize, y.trunc() as isize)
+                    }
+                };
+
+                // Modifier+scroll zooms
+                let zoom = match cfg.mouse.zoom_modifier.as_str() {
+                    "cmd" => modifiers.logo(),
+                    "ctrl" => modifiers.ctrl(),
+                    _ => false,
+                };
+                if zoom {
+                    if lines != 0 {
+                        self.renderer.adjust_font_size(lines as f32);
+                        self.after_zoom(cfg);
+                    }
+                    return Action::None;
+                }
+
+                // Forward scroll to application if mouse mode is active
+                if self.tab.pane.term.mouse_mode > 0 {
+                    if let Some((view_row, col)) =
+                        pixel_to_cell(&self.renderer, &self.window, self.mouse_pos)
+                    {
+                        let vertical = if lines > 0 {
+                            mouse::BUTTON_SCROLL_UP
+                        } else {
+                            mouse::BUTTON_SCROLL_DOWN
+                        };
+                        let horizontal = if cols > 0 {
+                            mouse::BUTTON_SCROLL_LEFT
+                        } else {
+                            mouse::BUTTON_SCROLL_RIGHT
+                        };
+                        for (scroll_btn, n) in [(vertical, lines), (horizontal, cols)] {
+                            for _ in 0..n.unsigned_abs() {
+                                self.send_mouse(scroll_btn, view_row, col, true, modifiers);
+                            }
+                        }
+                    }
+                    return Action::None;
+                }
+
+                // Alternate screen has no scrollback: scroll the app with arrow keys
+                if self.tab.pane.term.alt_screen {
+                    let key: &[u8] = if lines > 0 { b"\x1b[A" } else { b"\x1b[B" };
+                    for _ in 0..lines.unsigned_abs() {
+                        write_pty(&self.tab.pane.pty, key);
+                    }
+                    if cfg.mouse.horizontal_arrows {
+                        let key: &[u8] = if cols > 0 { b"\x1b[D" } else { b"\x1b[C" };
+                        for _ in 0..cols.unsigned_abs() {
+                            write_pty(&self.tab.pane.pty, key);
+                        }
+                    }
+                    return Action::None;
+                }
+
+                if lines != 0 {
+                    self.tab.pane.term.set_view_scroll(-lines);
+                    self.redraw_later();
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_key(&mut self, input: KeyboardInput, modifiers: ModifiersState, shared: &Shared) -> Action {
+        let cfg = &shared.cfg;
+        if input.state != ElementState::Pressed {
+            return Action::None;
+        }
+        self.renderer.status_bell = false;
+        let Some(key) = input.virtual_keycode else {
+            return Action::None;
+        };
+        // The context menu takes the keyboard while it's open
+        if let Some(menu) = &mut self.renderer.menu {
+            let last = menu.items.len() - 1;
+            match key {
+                VirtualKeyCode::Up => {
+                    menu.selected = Some(menu.selected.and_then(|i| i.checked_sub(1)).unwrap_or(last));
+                }
+                VirtualKeyCode::Down => {
+                    menu.selected = Some(menu.selected.filter(|&i| i < last).map_or(0, |i| i + 1));
+                }
+                VirtualKeyCode::Return => {
+                    let picked = menu.selected;
+                    self.pick_menu_item(picked, shared);
+                }
+                VirtualKeyCode::Escape => self.pick_menu_item(None, shared),
+                _ => {}
+            }
+            self.redraw_later();
+            return Action::None;
+        }
+        // Label keys arrive as characters; Esc leaves hint mode
+        if let Some(mode) = &mut self.hint_mode {
+            match key {
+                VirtualKeyCode::Escape => self.exit_hint_mode(),
+                VirtualKeyCode::Back => {
+                    mode.typed.pop();
+                    self.show_hint_labels();
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+        // Replay controls
+        if let Some(p) = &mut self.player {
+            let seek = match key {
+                VirtualKeyCode::Space => {
+                    p.toggle_pause();
+                    None
+                }
+                VirtualKeyCode::Left => Some(-5.0),
+                VirtualKeyCode::Right => Some(5.0),
+                VirtualKeyCode::Up => {
+                    p.set_speed(p.speed * 2.0);
+                    None
+                }
+                VirtualKeyCode::Down => {
+                    p.set_speed(p.speed / 2.0);
+                    None
+                }
+                _ => None,
+            };
+            if let Some((reset, steps)) = seek.map(|delta| p.seek(delta)) {
+                if reset {
+                    let (cols, rows) = p.size();
+                    self.tab.pane.term = new_terminal(cols, rows, cfg);
+                    self.tab.pane.term.cell_size = self.renderer.cell_size();
+                    self.tab.pane.parser = vte::Parser::new();
+                }
+                self.play(steps, cfg);
+            }
+            self.show_replay_status();
+            if !modifiers.logo() {
+                return Action::None;
+            }
+        }
+        // Esc cancels a paste in progress
+        if key == VirtualKeyCode::Escape && self.paste.is_some() && !modifiers.logo() {
+            self.cancel_paste();
+            return Action::None;
+        }
+        // Search mode key handling
+        if self.search.active && !modifiers.logo() {
+            match key {
+                VirtualKeyCode::Escape => {
+                    self.search.close();
+                    self.redraw_later();
+                }
+                VirtualKeyCode::Back => {
+                    self.search.pop_char();
+                    self.search.search(&self.tab.pane.term);
+                    self.redraw_later();
+                }
+                VirtualKeyCode::Return => {
+                    self.search.next_match();
+                    self.scroll_to_current_match();
+                    self.redraw_later();
+                }
+                // Tab: select the current match, ready for Cmd+C
+                VirtualKeyCode::Tab => {
+                    if let Some(m) = self.search.current_match() {
+                        self.tab.pane.term.selection = Some(Selection {
+                            anchor: m.start,
+                            focus: m.end,
+                        });
+                        self.redraw_later();
+                    }
+                }
+                _ => {}
+            }
+            return Action::None;
+        }
+        if modifiers.logo() {
+            match key {
+                // Cmd+Option+C / Cmd+Option+V: copy to / paste from the register
+                VirtualKeyCode::C if modifiers.alt() => {
+                    if self.tab.pane.term.selection_non_empty() {
+                        *shared.register.borrow_mut() = self.tab.pane.term.selection_text_or_empty();
+                    }
+                    return Action::None;
+                }
+                VirtualKeyCode::V if modifiers.alt() => {
+                    let text = shared.register.borrow().clone();
+                    self.paste_register(&text);
+                    return Action::None;
+                }
+                // Cmd+C: copy
+                VirtualKeyCode::C if self.tab.pane.term.selection_non_empty() => {
+                    let text = self.tab.pane.term.selection_text_or_empty();
+                    if let Err(e) = clipboard::copy_to_clipboard(&text) {
+                        log::error!("复制失败: {e}");
+                    }
+                    return Action::None;
+                }
+                // Cmd+V: paste (with bracketed paste support)
+                VirtualKeyCode::V => {
+                    self.paste();
+                    return Action::None;
+                }
+                // Cmd+N: new window
+                VirtualKeyCode::N => return Action::NewWindow,
+                // Cmd+T: new tab
+                VirtualKeyCode::T if !modifiers.shift() && self.player.is_none() => {
+                    self.open_tab(shared);
+                    return Action::None;
+                }
+                // Cmd+W: close the pane, its tab with the last pane, the
+                // window with the last tab
+                VirtualKeyCode::W => {
+                    if !confirm_quit(&busy_in(&self.tab.pane).into_iter().collect::<Vec<_>>()) {
+                        return Action::None;
+                    }
+                    return if self.close_pane(self.tab.pane.id, cfg) { Action::Close } else { Action::None };
+                }
+                // Cmd+D / Cmd+Shift+D: split side by side / stacked
+                VirtualKeyCode::D if self.player.is_none() => {
+                    self.split_pane(shared, !modifiers.shift());
+                    return Action::None;
+                }
+                // Cmd+[ / Cmd+]: previous / next pane
+                VirtualKeyCode::LBracket | VirtualKeyCode::RBracket if !modifiers.shift() => {
+                    let ids = self.tab.layout.panes();
+                    let i = ids.iter().position(|&id| id == self.tab.pane.id).unwrap_or(0);
+                    let n = ids.len();
+                    let next = if key == VirtualKeyCode::LBracket { i + n - 1 } else { i + 1 };
+                    self.focus_pane(ids[next % n], cfg);
+                    return Action::None;
+                }
+                // Cmd+Option+arrows: the pane in that direction
+                VirtualKeyCode::Left | VirtualKeyCode::Right | VirtualKeyCode::Up | VirtualKeyCode::Down
+                    if modifiers.alt() =>
+                {
+                    let dir = match key {
+                        VirtualKeyCode::Left => Direction::Left,
+                        VirtualKeyCode::Right => Direction::Right,
+                        VirtualKeyCode::Up => Direction::Up,
+                        _ => Direction::Down,
+                    };
+                    let size = self.window.inner_size();
+                    let area = self.renderer.content_rect(size.width as usize, size.height as usize);
+                    if let Some(id) = self.tab.layout.neighbor(area, self.tab.pane.id, dir) {
+                        self.focus_pane(id, cfg);
+                    }
+                    return Action::None;
+                }
+                // Cmd+Shift+[ / Cmd+Shift+]: previous / next tab
+                VirtualKeyCode::LBracket | VirtualKeyCode::RBracket if modifiers.shift() => {
+                    let n = self.tab_count();
+                    let next = if key == VirtualKeyCode::LBracket { self.active + n - 1 } else { self.active + 1 };
+                    self.switch_tab(next % n, cfg);
+                    return Action::None;
+                }
+                // Cmd+1..8: that tab; Cmd+9: the last tab
+                VirtualKeyCode::Key1
+                | VirtualKeyCode::Key2
+                | VirtualKeyCode::Key3
+                | VirtualKeyCode::Key4
+                | VirtualKeyCode::Key5
+                | VirtualKeyCode::Key6
+                | VirtualKeyCode::Key7
+                | VirtualKeyCode::Key8
+                | VirtualKeyCode::Key9
+                    if !modifiers.ctrl() =>
+                {
+                    let index = match key {
+                        VirtualKeyCode::Key9 => self.tab_count() - 1,
+                        _ => key as usize - VirtualKeyCode::Key1 as usize,
+                    };
+                    self.switch_tab(index, cfg);
+                    return Action::None;
+                }
+                // Cmd+Shift+,: reload config.toml
+                VirtualKeyCode::Comma if modifiers.shift() => return Action::ReloadConfig,
+                // Cmd+Q: quit (with confirmation if child running)
+                VirtualKeyCode::Q => return Action::Quit,
+                // Cmd+= / Cmd++: zoom in
+                VirtualKeyCode::Equals => {
+                    self.renderer.adjust_font_size(2.0);
+                    self.after_zoom(cfg);
+                    return Action::None;
+                }
+                // Cmd+-: zoom out
+                VirtualKeyCode::Minus => {
+                    self.renderer.adjust_font_size(-2.0);
+                    self.after_zoom(cfg);
+                    return Action::None;
+                }
+                // Cmd+0: reset zoom
+                VirtualKeyCode::Key0 => {
+                    self.renderer.reset_font_size();
+                    self.after_zoom(cfg);
+                    return Action::None;
+                }
+                // Cmd+Ctrl+1/2/3: font size presets
+                VirtualKeyCode::Key1 | VirtualKeyCode::Key2 | VirtualKeyCode::Key3
+                    if modifiers.ctrl() =>
+                {
+                    let presets = &cfg.font.presets;
+                    let size = match key {
+                        VirtualKeyCode::Key1 => presets.small,
+                        VirtualKeyCode::Key2 => presets.medium,
+                        _ => presets.large,
+                    };
+                    let px = (size * self.scale_factor as f32).clamp(8.0, 72.0);
+                    self.renderer.set_font_size(px);
+                    self.after_zoom(cfg);
+                    return Action::None;
+                }
+                // Cmd+Shift+K: clear screen and scrollback, reset attributes
+                VirtualKeyCode::K if modifiers.shift() => {
+                    self.tab.pane.term.clear_and_reset();
+                    self.after_scrollback_cleared();
+                    // Ctrl+L: let the shell redraw its prompt on the blank screen
+                    write_pty(&self.tab.pane.pty, b"\x0c");
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+K: clear scrollback
+                VirtualKeyCode::K => {
+                    self.tab.pane.term.clear_scrollback();
+                    self.after_scrollback_cleared();
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+Shift+A: select and copy the last command's output
+                VirtualKeyCode::A if modifiers.shift() => {
+                    if let Some((a, b)) = self.tab.pane.term.last_command_output() {
+                        self.tab.pane.term.selection = Some(Selection { anchor: a, focus: b });
+                        if let Err(e) = clipboard::copy_to_clipboard(&self.tab.pane.term.selection_text_or_empty()) {
+                            log::error!("复制失败: {e}");
+                        }
+                        self.redraw_later();
+                    }
+                    return Action::None;
+                }
+                // Cmd+Shift+L: copy the last command line
+                VirtualKeyCode::L if modifiers.shift() => {
+                    if let Some(text) =
+                        self.tab.pane.term.last_command_line().and_then(|(a, b)| self.tab.pane.term.text_between(a, b))
+                    {
+                        if let Err(e) = clipboard::copy_to_clipboard(text.trim()) {
+                            log::error!("复制失败: {e}");
+                        }
+                    }
+                    return Action::None;
+                }
+                // Cmd+A: select all
+                VirtualKeyCode::A => {
+                    self.tab.pane.term.select_all();
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+E: expand the selection (word, WORD, quotes, line, output)
+                VirtualKeyCode::E => {
+                    self.tab.pane.term.expand_selection();
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+Shift+R: toggle session recording
+                VirtualKeyCode::R if modifiers.shift() => {
+                    if let Some((_, mut rec)) = self.recorder.take() {
+                        let _ = rec.flush();
+                        log::info!("录制已停止");
+                    } else {
+                        let path = crate::default_recording_path();
+                        match start_recording(&path, self.tab.pane.term.cols(), self.tab.pane.term.rows()) {
+                            Ok(rec) => {
+                                log::info!("开始录制: {}", path.display());
+                                self.recorder = Some((self.tab.pane.id, rec));
+                            }
+                            Err(e) => log::error!("{e}"),
+                        }
+                    }
+                    self.renderer.recording = self.recorder.is_some();
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+Up / Cmd+Down: jump between command prompts (OSC 133)
+                VirtualKeyCode::Up | VirtualKeyCode::Down => {
+                    self.jump_to_prompt(key == VirtualKeyCode::Up);
+                    return Action::None;
+                }
+                // Cmd+Enter: toggle fullscreen
+                VirtualKeyCode::Return => {
+                    if cfg.window.fullscreen == "simple" {
+                        macos::toggle_simple_fullscreen(&self.window);
+                    } else {
+                        let next = match self.window.fullscreen() {
+                            Some(_) => None,
+                            None => Some(Fullscreen::Borderless(None)),
+                        };
+                        self.window.set_fullscreen(next);
+                    }
+                    return Action::None;
+                }
+                // Cmd+Shift+T: cycle the timestamp gutter
+                VirtualKeyCode::T if modifiers.shift() => {
+                    self.renderer.timestamps = self.renderer.timestamps.next();
+                    self.fit_grid();
+                    self.snap_to_grid(cfg);
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+S: export scrollback to a file
+                VirtualKeyCode::S => {
+                    export_scrollback(&self.tab.pane.term, self.renderer.timestamps != Timestamps::Off);
+                    return Action::None;
+                }
+                // Cmd+Shift+F: search web for selection
+                VirtualKeyCode::F if modifiers.shift() => {
+                    web_search_selection(&self.tab.pane.term, cfg);
+                    return Action::None;
+                }
+                // Cmd+Shift+H: label links and hints on screen to pick from the keyboard
+                VirtualKeyCode::H if modifiers.shift() => {
+                    self.enter_hint_mode(shared);
+                    return Action::None;
+                }
+                // Cmd+F: toggle search
+                VirtualKeyCode::F => {
+                    self.search.toggle();
+                    self.redraw_later();
+                    return Action::None;
+                }
+                // Cmd+G: next search match
+                VirtualKeyCode::G => {
+                    if self.search.active {
+                        if modifiers.shift() {
+                            self.search.prev_match();
+                        } else {
+                            self.search.next_match();
+                        }
+                        // Scroll to current match
+                        self.scroll_to_current_match();
+                        self.redraw_later();
+                    }
+                    return Action::None;
+                }
+                _ => {}
+            }
+        }
+
+        // Shift+arrows and Shift+PageUp/PageDown extend an existing selection
+        if modifiers.shift() && !modifiers.alt() && !modifiers.ctrl() && self.tab.pane.term.selection.is_some() {
+            let page = self.tab.pane.term.rows() as isize;
+            let delta = match key {
+                VirtualKeyCode::Left => Some((0, -1)),
+                VirtualKeyCode::Right => Some((0, 1)),
+                VirtualKeyCode::Up => Some((-1, 0)),
+                VirtualKeyCode::Down => Some((1, 0)),
+                VirtualKeyCode::PageUp => Some((-page, 0)),
+                VirtualKeyCode::PageDown => Some((page, 0)),
+                _ => None,
+            };
+            if let Some((rows, cols)) = delta {
+                self.tab.pane.term.extend_selection(rows, cols);
+                self.redraw_later();
+                return Action::None;
+            }
+        }
+
+        // While scrolled back in pager mode, plain paging keys move the view
+        // too; at the bottom they go back to the application
+        let paging = modifiers.shift()
+            || (cfg.scrolling.keyboard == "pager" && modifiers.is_empty() && self.tab.pane.term.view_scroll > 0);
+        match key {
+            VirtualKeyCode::PageUp if paging => {
+                self.tab.pane.term.scroll_view_page(1);
+                self.redraw_later();
+            }
+            VirtualKeyCode::PageDown if paging => {
+                self.tab.pane.term.scroll_view_page(-1);
+                self.redraw_later();
+            }
+            VirtualKeyCode::Home if paging => {
+                self.tab.pane.term.set_view_scroll(self.tab.pane.term.max_view_scroll() as isize);
+                self.redraw_later();
+            }
+            VirtualKeyCode::End if paging => {
+                self.tab.pane.term.scroll_view_to_bottom();
+                self.redraw_later();
+            }
+            _ => {
+                if let Some(bytes) = input::map_special_key(key, modifiers) {
+                    self.before_input(cfg);
+                    write_pty(&self.tab.pane.pty, &bytes);
+                }
+            }
+        }
+        Action::None
+    }
+
+    fn handle_mouse_button(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+        modifiers: ModifiersState,
+        shared: &Shared,
+    ) {
+        let cfg = &shared.cfg;
+        // With the menu open, a click picks an entry or just closes it
+        if self.menu.is_some() {
+            if state == ElementState::Pressed {
+                let (x, y) = (self.mouse_pos.x.max(0.0) as usize, self.mouse_pos.y.max(0.0) as usize);
+                let picked = self.renderer.menu_item_at(x, y).filter(|_| button == MouseButton::Left);
+                self.pick_menu_item(picked, shared);
+            }
+            return;
+        }
+        if self.hint_mode.is_some() {
+            if state == ElementState::Pressed {
+                self.exit_hint_mode();
+            }
+            return;
+        }
+        // Clicks on the search bar or status line don't reach the grid behind it
+        let bar_top = self.window.inner_size().height as f64 - self.renderer.search_bar_height() as f64;
+        if (self.search.active || self.renderer.status_line) && self.mouse_pos.y >= bar_top {
+            return;
+        }
+        // A click in another pane focuses it
+        if state == ElementState::Pressed {
+            let (x, y) = (self.mouse_pos.x.max(0.0) as usize, self.mouse_pos.y.max(0.0) as usize);
+            let rects = self.pane_rects();
+            if let Some(&(id, _)) = rects.iter().find(|(id, r)| *id != self.tab.pane.id && r.contains(x, y)) {
+                self.focus_pane(id, cfg);
+                return;
+            }
+        }
+        let Some((view_row, col)) = pixel_to_cell(&self.renderer, &self.window, self.mouse_pos)
+        else {
+            return;
+        };
+        let btn = match button {
+            MouseButton::Left => mouse::BUTTON_LEFT,
+            MouseButton::Middle => mouse::BUTTON_MIDDLE,
+            MouseButton::Right => mouse::BUTTON_RIGHT,
+            _ => return,
+        };
+        // Forward mouse to application if mouse mode is active
+        if self.tab.pane.term.mouse_mode > 0 && !modifiers.logo() {
+            let pressed = state == ElementState::Pressed;
+            self.mouse_button = pressed.then_some(btn);
+            self.mouse_cell = Some((view_row, col));
+            self.send_mouse(btn, view_row, col, pressed, modifiers);
+            return;
+        }
+        let term = &mut self.tab.pane.term;
+
+        // Normal terminal selection behavior (only left button)
+        if button == MouseButton::Left {
+            match state {
+                ElementState::Pressed => {
+                    // Cmd+click: user hint, else URL or file path (Cmd+Shift+click reveals in Finder)
+                    if modifiers.logo() {
+                        let ctx = open_context(term, cfg, modifiers.shift());
+                        if let Some(target) = click_target_at(term, view_row, col, &shared.hint_patterns) {
+                            target.open(&ctx);
+                            return;
+                        }
+                    }
+                    let now = Instant::now();
+                    let interval = cfg
+                        .mouse
+                        .double_click_ms
+                        .map(Duration::from_millis)
+                        .or_else(macos::double_click_interval)
+                        .unwrap_or(DOUBLE_CLICK_INTERVAL);
+                    if now.duration_since(self.last_click_time) < interval {
+                        self.click_count = (self.click_count + 1).min(3);
+                    } else {
+                        self.click_count = 1;
+                    }
+                    self.last_click_time = now;
+
+                    match self.click_count {
+                        2 => term.select_word_at_view(view_row, col),
+                        3 => term.select_line_at_view(view_row),
+                        _ => {
+                            self.selecting = true;
+                            term.start_selection_from_view(view_row, col);
+                        }
+                    }
+                    self.redraw_later();
+                }
+                ElementState::Released => {
+                    self.selecting = false;
+                }
+            }
+        } else if button == MouseButton::Right && state == ElementState::Pressed {
+            self.open_menu(view_row, col, shared);
+        }
+    }
+
+    /// Show the context menu at the pointer: link actions when over a
+    /// link, plus copy/paste.
+    fn open_menu(&mut self, view_row: usize, col: usize, shared: &Shared) {
+        let term = &self.tab.pane.term;
+        let mut items = Vec::new();
+        if click_target_at(term, view_row, col, &shared.hint_patterns).is_some() {
+            items.extend([MenuItem::OpenLink, MenuItem::CopyLink]);
+        }
+        if term.selection_non_empty() {
+            items.extend([MenuItem::Copy, MenuItem::SearchWeb]);
+        }
+        items.push(MenuItem::Paste);
+        self.renderer.tooltip = None;
+        self.renderer.menu = Some(renderer::Menu {
+            x: self.mouse_pos.x.max(0.0) as usize,
+            y: self.mouse_pos.y.max(0.0) as usize,
+            items: items.iter().map(|i| i.label().to_string()).collect(),
+            selected: None,
+        });
+        let row = term.visible_global_row_for_view(view_row);
+        self.menu = Some(ContextMenu { items, row, col });
+        self.redraw_later();
+    }
+
+    fn close_menu(&mut self) -> Option<ContextMenu> {
+        if self.menu.is_some() {
+            self.renderer.menu = None;
+            self.redraw_later();
+        }
+        self.menu.take()
+    }
+
+    /// Close the context menu and run entry `picked`, if any.
+    fn pick_menu_item(&mut self, picked: Option<usize>, shared: &Shared) {
+        let Some(menu) = self.close_menu() else {
+            return;
+        };
+        let Some(&item) = picked.and_then(|i| menu.items.get(i)) else {
+            return;
+        };
+        let cfg = &shared.cfg;
+        let term = &self.tab.pane.term;
+        // Output may have scrolled the view since: look the link up on its row
+        let target = menu
+            .row
+            .checked_sub(term.visible_start_global_row())
+            .filter(|&view_row| view_row < term.rows())
+            .and_then(|view_row| click_target_at(term, view_row, menu.col, &shared.hint_patterns));
+        match item {
+            MenuItem::OpenLink => {
+                if let Some(target) = target {
+                    target.open(&open_context(term, cfg, false));
+                }
+            }
+            MenuItem::CopyLink => {
+                if let Err(e) = clipboard::copy_to_clipboard(&target.map(|t| t.text()).unwrap_or_default()) {
+                    log::error!("复制失败: {e}");
+                }
+            }
+            MenuItem::Copy => {
+                if let Err(e) = clipboard::copy_to_clipboard(&term.selection_text_or_empty()) {
+                    log::error!("复制失败: {e}");
+                }
+            }
+            MenuItem::SearchWeb => web_search_selection(term, cfg),
+            MenuItem::Paste => self.paste(),
+        }
+    }
+
+    /// Label every link and hint on screen; typing a label opens it, or
+    /// copies it when typed in capitals.
+    fn enter_hint_mode(&mut self, shared: &Shared) {
+        let cells = visible_targets(&self.tab.pane.term, &shared.hint_patterns);
+        if cells.is_empty() {
+            return;
+        }
+        let targets = hints::labels(cells.len())
+            .into_iter()
+            .zip(cells)
+            .map(|(label, (row, col))| (label, row, col))
+            .collect();
+        self.hint_mode = Some(HintMode { targets, typed: String::new() });
+        self.show_hint_labels();
+    }
+
+    fn exit_hint_mode(&mut self) {
+        self.hint_mode = None;
+        self.renderer.hint_labels.clear();
+        self.redraw_later();
+    }
+
+    /// Show what's left to type of the labels matching the keys so far.
+    fn show_hint_labels(&mut self) {
+        let Some(mode) = &self.hint_mode else {
+            return;
+        };
+        self.renderer.hint_labels = mode
+            .targets
+            .iter()
+            .filter_map(|(label, row, col)| Some((*row, *col, label.strip_prefix(&mode.typed)?.to_string())))
+            .collect();
+        self.redraw_later();
+    }
+
+    fn type_hint_key(&mut self, ch: char, modifiers: ModifiersState, shared: &Shared) {
+        let Some(mode) = &mut self.hint_mode else {
+            return;
+        };
+        // Not the H of the Cmd+Shift+H that started it
+        if !ch.is_ascii_alphabetic() || modifiers.logo() {
+            return;
+        }
+        mode.typed.push(ch.to_ascii_lowercase());
+        if let Some(&(_, row, col)) = mode.targets.iter().find(|(label, ..)| *label == mode.typed) {
+            self.exit_hint_mode();
+            self.run_hint_target(row, col, ch.is_ascii_uppercase(), shared);
+        } else if mode.targets.iter().any(|(label, ..)| label.starts_with(&mode.typed)) {
+            self.show_hint_labels();
+        } else {
+            mode.typed.pop();
+        }
+    }
+
+    /// Open the target at `row`, `col` as Cmd+click would, or copy it.
+    fn run_hint_target(&mut self, row: usize, col: usize, copy: bool, shared: &Shared) {
+        let term = &self.tab.pane.term;
+        let target = row
+            .checked_sub(term.visible_start_global_row())
+            .filter(|&view_row| view_row < term.rows())
+            .and_then(|view_row| click_target_at(term, view_row, col, &shared.hint_patterns));
+        let Some(target) = target else {
+            return;
+        };
+        if !copy {
+            target.open(&open_context(term, &shared.cfg, false));
+        } else if let Err(e) = clipboard::copy_to_clipboard(&target.text()) {
+            log::error!("复制失败: {e}");
+        }
+    }
+
+    /// Apply replayed output and resizes to the replaying pane.
+    fn play(&mut self, steps: Vec<Step>, cfg: &Config) {
+        for step in steps {
+            match step {
+                Step::Output(out) => self.handle_output(&out, cfg),
+                Step::Resize(cols, rows) => {
+                    self.tab.pane.term.resize(cols, rows);
+                    self.redraw_later();
+                }
+            }
+        }
+    }
+
+    /// Per-iteration work: due replay events and cursor blink.
+    pub fn tick(&mut self, cfg: &Config) {
+        if let Some(p) = &mut self.player {
+            let steps = p.take_due();
+            if !steps.is_empty() {
+                self.play(steps, cfg);
+                self.show_replay_status();
+            }
+        }
+        if self.blink_deadline().is_some_and(|d| Instant::now() >= d) {
+            self.cursor_visible = !self.cursor_visible;
+            self.cursor_blink_timer = Instant::now();
+            self.redraw_later();
+        }
+        let lines = self.drag_scroll_lines();
+        if lines != 0 && Instant::now() >= self.drag_scroll_at + DRAG_SCROLL_INTERVAL {
+            self.drag_scroll_at = Instant::now();
+            self.tab.pane.term.set_view_scroll(-lines);
+            let (view_row, col) = self.clamped_cell();
+            self.tab.pane.term.set_selection_focus_from_view(view_row, col);
+            self.redraw_later();
+        }
+        if self.flash_deadline().is_some_and(|d| Instant::now() >= d) {
+            self.renderer.match_flash = None;
+            self.redraw_later();
+        }
+        if self.title_due.is_some_and(|d| Instant::now() >= d) {
+            self.update_title(cfg);
+        }
+        if self.idle_deadline().is_some_and(|d| Instant::now() >= d) {
+            self.renderer.cover = self.idle_cover.map(|(_, cover)| cover);
+            self.redraw_later();
+        }
+        for pane in self.tab.all_mut() {
+            if pane.pty_resize_due.is_some_and(|d| Instant::now() >= d) {
+                pane.resize_pty();
+            }
+        }
+        if self.paste.as_ref().is_some_and(|p| Instant::now() >= p.next_at) {
+            self.send_paste_chunk();
+        }
+        if self.gutter_deadline().is_some_and(|d| Instant::now() >= d) {
+            // Also pushes the deadline out if the redraw never comes (hidden window)
+            self.last_redraw = Instant::now();
+            self.redraw_later();
+        }
+    }
+
+    /// Input restarts the idle timer and uncovers the window.
+    fn note_input(&mut self) {
+        self.last_input = Instant::now();
+        if self.renderer.cover.take().is_some() {
+            self.redraw_later();
+        }
+    }
+
+    /// When an idle window gets covered, unless it already is.
+    fn idle_deadline(&self) -> Option<Instant> {
+        let (after, _) = self.idle_cover?;
+        self.renderer.cover.is_none().then(|| self.last_input + after)
+    }
+
+    /// End of the search match flash, if one is showing.
+    fn flash_deadline(&self) -> Option<Instant> {
+        self.renderer.match_flash.map(|t| t + renderer::MATCH_FLASH)
+    }
+
+    /// Next cursor blink, if the cursor is blinking at all.
+    fn blink_deadline(&self) -> Option<Instant> {
+        (self.cursor_blink && self.focused).then(|| self.cursor_blink_timer + BLINK_INTERVAL)
+    }
+
+    /// Cell under the pointer, clamped to the grid.
+    fn clamped_cell(&self) -> (usize, usize) {
+        let r = &self.renderer;
+        let x = (self.mouse_pos.x - r.grid_x() as f64).max(0.0) as usize / r.atlas.cell_width;
+        let y = (self.mouse_pos.y - r.grid_y() as f64).max(0.0) as usize / r.atlas.cell_height;
+        (y.min(self.tab.pane.term.rows() - 1), x.min(self.tab.pane.term.cols() - 1))
+    }
+
+    /// Lines to auto-scroll per step while drag-selecting above (< 0) or
+    /// below (> 0) the grid; faster the further past the edge.
+    fn drag_scroll_lines(&self) -> isize {
+        if !self.selecting || self.tab.pane.term.mouse_mode != 0 || self.tab.pane.term.alt_screen {
+            return 0;
+        }
+        let ch = self.renderer.atlas.cell_height as f64;
+        let top = self.renderer.grid_y() as f64;
+        let bottom = top + self.tab.pane.term.rows() as f64 * ch;
+        let y = self.mouse_pos.y;
+        let past = if y < top {
+            y - top
+        } else if y >= bottom {
+            y - bottom + 1.0
+        } else {
+            return 0;
+        };
+        let lines = (1.0 + past.abs() / ch).min(DRAG_SCROLL_MAX_LINES) as isize;
+        if past < 0.0 { -lines } else { lines }
+    }
+
+    fn drag_scroll_deadline(&self) -> Option<Instant> {
+        (self.drag_scroll_lines() != 0).then(|| self.drag_scroll_at + DRAG_SCROLL_INTERVAL)
+    }
+
+    fn gutter_deadline(&self) -> Option<Instant> {
+        (self.renderer.timestamps == Timestamps::Relative).then(|| self.last_redraw + GUTTER_REFRESH)
+    }
+
+    fn show_replay_status(&self) {
+        if let Some(p) = &self.player {
+            self.window.set_title(&format!("moterm replay {}", p.status()));
+        }
+    }
+
+    /// When this window next needs to wake the event loop, if ever.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let replay = self.player.as_ref().and_then(|p| p.next_deadline());
+        let paste = self.paste.as_ref().map(|p| p.next_at);
+        [
+            replay,
+            self.blink_deadline(),
+            self.gutter_deadline(),
+            self.drag_scroll_deadline(),
+            paste,
+            self.title_due,
+            self.tab.all().filter_map(|p| p.pty_resize_due).min(),
+            self.flash_deadline(),
+            self.idle_deadline(),
+        ]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    pub fn redraw(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let size = self.window.inner_size();
+        let (w_nz, h_nz) = renderer::Renderer::nonzero_dims(size.width, size.height);
+        self.surface
+            .resize(w_nz, h_nz)
+            .map_err(|e| format!("surface resize 失败: {e}"))?;
+
+        let rects = self.pane_rects();
+        let shown = |p: &Pane| rects.iter().find(|(id, _)| *id == p.id).map(|&(_, r)| r);
+        // Changed rows of each drawn pane, focused first like `others`
+        let mut damage = vec![self.tab.pane.term.take_damage()];
+        for p in &mut self.tab.panes {
+            if shown(p).is_some() {
+                damage.push(p.term.take_damage());
+            }
+        }
+        let others: Vec<_> = self.tab.panes.iter().filter_map(|p| shown(p).map(|r| (&p.term, r, p.px))).collect();
+        let renderer = &mut self.renderer;
+        renderer.cursor_visible = self.cursor_visible;
+        renderer.focused = self.focused;
+        if self.search.active {
+            renderer.render_with_search(&self.tab.pane.term, &others, &self.search, size.width as usize, size.height as usize);
+        } else {
+            renderer.render(&self.tab.pane.term, &others, &damage, size.width as usize, size.height as usize);
+        }
+
+        let mut buffer = self
+            .surface
+            .buffer_mut()
+            .map_err(|e| format!("获取绘制缓冲区失败: {e}"))?;
+        let bg = self.tab.pane.term.palette.bg.to_u32();
+        let canvas = &renderer.canvas;
+        // The buffer still holds the last frame: copy and present only what changed
+        let partial = renderer.damage.as_ref().filter(|_| buffer.age() == 1 && buffer.len() == canvas.pixels.len());
+        if let Some(rects) = partial {
+            let mut regions = Vec::with_capacity(rects.len());
+            for r in rects {
+                for y in r.y..r.y + r.h {
+                    let row = y * canvas.width;
+                    buffer[row + r.x..row + r.x + r.w].copy_from_slice(&canvas.pixels[row + r.x..row + r.x + r.w]);
+                }
+                if let (Some(width), Some(height)) = (NonZeroU32::new(r.w as u32), NonZeroU32::new(r.h as u32)) {
+                    regions.push(softbuffer::Rect { x: r.x as u32, y: r.y as u32, width, height });
+                }
+            }
+            buffer.present_with_damage(&regions).map_err(|e| format!("present 失败: {e}"))?;
+        } else {
+            if buffer.len() == canvas.pixels.len() {
+                buffer.copy_from_slice(&canvas.pixels);
+            } else {
+                // Fill entire buffer with bg first, then copy canvas
+                buffer.fill(bg);
+                for (dst, src) in buffer.iter_mut().zip(canvas.pixels.iter().copied()) {
+                    *dst = src;
+                }
+            }
+            buffer.present().map_err(|e| format!("present 失败: {e}"))?;
+        }
+        self.dirty = false;
+        self.last_redraw = Instant::now();
+        Ok(())
+    }
+}
+
+/// What a pane is running, with its directory: "vim — src".
+fn busy_in(pane: &Pane) -> Option<String> {
+    let command = busy_command(&pane.pty)?;
+    let dir = pane.term.cwd.as_deref().and_then(|c| c.rsplit('/').find(|s| !s.is_empty()));
+    Some(match dir {
+        Some(dir) => format!("{command} — {dir}"),
+        None => command,
+    })
+}
+
+/// Remember `pane`'s terminal state for a crash report.
+fn record_crash_state(pane: &Pane) {
+    let term = &pane.term;
+    crash::record_state(crash::State {
+        pane: pane.id,
+        cols: term.cols(),
+        rows: term.rows(),
+        cursor: (term.cursor_row, term.cursor_col),
+        scroll_region: (term.scroll_top, term.scroll_bottom),
+        alt_screen: term.alt_screen,
+        mouse_mode: term.mouse_mode,
+        scrollback: term.total_lines() - term.rows(),
+    });
+}
+
+/// `[privacy]` idle timeout and how to cover, if enabled.
+fn idle_cover(cfg: &Config) -> Option<(Duration, Cover)> {
+    let minutes = cfg.privacy.idle_minutes;
+    (minutes > 0).then(|| (Duration::from_secs(minutes * 60), Cover::from_config(&cfg.privacy.idle_cover)))
+}
+
+/// A blank terminal with the config's TERM and terminal options.
+fn new_terminal(cols: usize, rows: usize, cfg: &Config) -> Terminal {
+    let mut term = Terminal::with_scrollback(cols, rows, cfg.scrollback.limit());
+    term.set_term_name(&cfg.env.term);
+    configure_terminal(&mut term, cfg);
+    term
+}
+
+/// Cursor style, colors and compat options: what a config reload changes
+/// (TERM stays what the shell was started with).
+fn configure_terminal(term: &mut Terminal, cfg: &Config) {
+    term.set_default_cursor_style(cfg.initial_cursor_style());
+    term.alt_history = cfg.scrolling.alt_screen_history;
+    term.set_scrollback_limit(cfg.scrollback.limit());
+    term.collapse_invisible = cfg.compat.invisible_chars == "collapse";
+    term.palette = cfg.colors.palette();
+}
+
+/// Map an OSC 22 shape to a pointer: CSS cursor names, plus the X cursor
+/// font names xterm uses. Unknown shapes get the default pointer.
+fn pointer_icon(shape: &str) -> CursorIcon {
+    match shape {
+        "pointer" | "hand" | "hand1" | "hand2" => CursorIcon::Hand,
+        "text" | "xterm" => CursorIcon::Text,
+        "vertical-text" => CursorIcon::VerticalText,
+        "crosshair" | "cross" | "tcross" => CursorIcon::Crosshair,
+        "cell" | "plus" => CursorIcon::Cell,
+        "wait" | "watch" => CursorIcon::Wait,
+        "progress" => CursorIcon::Progress,
+        "help" | "question_arrow" => CursorIcon::Help,
+        "move" | "fleur" => CursorIcon::Move,
+        "all-scroll" => CursorIcon::AllScroll,
+        "not-allowed" => CursorIcon::NotAllowed,
+        "no-drop" => CursorIcon::NoDrop,
+        "copy" => CursorIcon::Copy,
+        "alias" => CursorIcon::Alias,
+        "context-menu" => CursorIcon::ContextMenu,
+        "grab" => CursorIcon::Grab,
+        "grabbing" => CursorIcon::Grabbing,
+        "zoom-in" => CursorIcon::ZoomIn,
+        "zoom-out" => CursorIcon::ZoomOut,
+        "col-resize" | "sb_h_double_arrow" => CursorIcon::ColResize,
+        "row-resize" | "sb_v_double_arrow" => CursorIcon::RowResize,
+        "ew-resize" => CursorIcon::EwResize,
+        "ns-resize" => CursorIcon::NsResize,
+        "nesw-resize" => CursorIcon::NeswResize,
+        "nwse-resize" => CursorIcon::NwseResize,
+        "n-resize" | "top_side" => CursorIcon::NResize,
+        "s-resize" | "bottom_side" => CursorIcon::SResize,
+        "e-resize" | "right_side" => CursorIcon::EResize,
+        "w-resize" | "left_side" => CursorIcon::WResize,
+        "ne-resize" | "top_right_corner" => CursorIcon::NeResize,
+        "nw-resize" | "top_left_corner" => CursorIcon::NwResize,
+        "se-resize" | "bottom_right_corner" => CursorIcon::SeResize,
+        "sw-resize" | "bottom_left_corner" => CursorIcon::SwResize,
+        _ => CursorIcon::Default,
+    }
+}
+
+/// Fill `{command}` and `{cwd}` in a title template (home shown as `~`).
+/// Separators left dangling by a missing value are trimmed.
+fn format_title(format: &str, command: Option<&str>, cwd: Option<&str>) -> String {
+    let home = dirs::home_dir();
+    let cwd = cwd.map(|dir| {
+        match home.as_deref().and_then(|h| std::path::Path::new(dir).strip_prefix(h).ok()) {
+            Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+            Some(rest) => format!("~/{}", rest.display()),
+            None => dir.to_string(),
+        }
+    });
+    format
+        .replace("{command}", command.unwrap_or(""))
+        .replace("{cwd}", cwd.as_deref().unwrap_or(""))
+        .trim_matches(|c: char| c.is_whitespace() || "—–-:|".contains(c))
+        .to_string()
+}